@@ -9,15 +9,17 @@ pub use secure_gate::{
 
 // Fixed-size secrets
 fixed_alias!(FileKey32, 32); // 256-bit AES-Crypt v3 file key
-fixed_alias!(VaultKey32, 32); // Future: per-vault master key
-fixed_alias!(IndexKey32, 32); // Future: separate index encryption key
+fixed_alias!(VaultKey32, 32); // Raw SQLCipher key for vault.db
+fixed_alias!(IndexKey32, 32); // Raw SQLCipher key for index.db
+fixed_alias!(FileIdSecret32, 32); // Per-vault BLAKE3 key for file ids (vault_meta)
 
 // Dynamic secrets
 dynamic_alias!(MasterPassword, String); // For legacy file upgrades
-dynamic_alias!(UserPassphrase, String); // Future: vault unlock passphrase
+dynamic_alias!(UserPassphrase, String); // Vault unlock passphrase (Argon2id)
 dynamic_alias!(FilePassword, String); // Replacement for aescrypt-rs::Password (handles hex keys or legacy strings)
 dynamic_alias!(CypherText, Vec<u8>); // ← already correct
 dynamic_alias!(PlainText, Vec<u8>); // ← this is the one you need
+dynamic_alias!(PasswordBlob, Vec<u8>); // Raw key blob read back from the vault DB
 
 // Random secrets
 random_alias!(RandomPassword32, 32);
@@ -0,0 +1,132 @@
+// src/atomic_write.rs
+//! Crash-safe output files
+//!
+//! Every file the crate produces goes through [`write_atomic`]: data lands in
+//! a temp file in the destination directory, the file is fsynced, renamed over
+//! the destination, and the directory is fsynced. A crash, a full disk or a
+//! failed encryption leaves any existing destination untouched.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Prefix for in-flight temp files — safe to delete if found after a crash
+pub const ATOMIC_TEMP_PREFIX: &str = ".efv-tmp-";
+
+/// Atomically create or replace `dest` with whatever `write` produces
+///
+/// The closure receives the temp file; it must not rename or close it.
+/// If the closure fails, the temp file is removed and `dest` is not touched.
+pub fn write_atomic<P, T, E, F>(dest: P, write: F) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut File) -> Result<T, E>,
+    E: From<io::Error>,
+{
+    let dest = dest.as_ref();
+    let dir = parent_dir(dest);
+
+    let mut temp = tempfile::Builder::new()
+        .prefix(ATOMIC_TEMP_PREFIX)
+        .tempfile_in(dir)?;
+
+    let value = write(temp.as_file_mut())?;
+
+    temp.as_file().sync_all()?;
+    temp.persist(dest).map_err(|e| e.error)?;
+    sync_dir(dir)?;
+
+    Ok(value)
+}
+
+/// Which step of [`rename_durable`] failed
+#[derive(Debug, thiserror::Error)]
+pub enum RenameError {
+    /// The rename itself failed; `to` was not replaced
+    #[error("rename failed: {0}")]
+    Rename(#[source] io::Error),
+    /// `to` was replaced, but the directory fsync after it failed
+    #[error("directory fsync after rename failed: {0}")]
+    Sync(#[source] io::Error),
+}
+
+impl From<RenameError> for io::Error {
+    fn from(err: RenameError) -> Self {
+        match err {
+            RenameError::Rename(e) | RenameError::Sync(e) => e,
+        }
+    }
+}
+
+/// Rename `from` over `to` and fsync the containing directory
+///
+/// Both paths must be on the same filesystem (normally the same directory).
+/// The error says whether the rename happened, for callers that must know.
+pub fn rename_durable<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> Result<(), RenameError> {
+    std::fs::rename(from.as_ref(), to.as_ref()).map_err(RenameError::Rename)?;
+    if fault::take_rename_sync_failure() {
+        return Err(RenameError::Sync(io::Error::other(
+            "injected directory fsync failure",
+        )));
+    }
+    sync_dir(parent_dir(to.as_ref())).map_err(RenameError::Sync)
+}
+
+/// [`rename_durable`] that refuses to replace an existing `to`
+///
+/// Fails with an `AlreadyExists` [`RenameError::Rename`] instead. The check
+/// and the rename are two steps, so this guards against a caller naming a
+/// file that is already there, not against a concurrent writer.
+pub fn rename_durable_noclobber<P: AsRef<Path>, Q: AsRef<Path>>(
+    from: P,
+    to: Q,
+) -> Result<(), RenameError> {
+    let to = to.as_ref();
+    if to.try_exists().map_err(RenameError::Rename)? {
+        return Err(RenameError::Rename(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        )));
+    }
+    rename_durable(from, to)
+}
+
+/// Directory that holds `path` (`.` for bare file names)
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Persist directory entries (the rename itself) to disk
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// Directories cannot be fsynced portably off Unix — rely on the rename alone
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Failure points for the integration tests; not part of the public API
+#[doc(hidden)]
+pub mod fault {
+    use std::cell::Cell;
+
+    thread_local! {
+        static FAIL_RENAME_SYNC: Cell<bool> = const { Cell::new(false) };
+    }
+
+    /// Make the directory fsync of the next [`rename_durable`](super::rename_durable)
+    /// on this thread fail, after the rename itself has gone through
+    pub fn fail_next_rename_sync() {
+        FAIL_RENAME_SYNC.with(|flag| flag.set(true));
+    }
+
+    pub(super) fn take_rename_sync_failure() -> bool {
+        FAIL_RENAME_SYNC.with(|flag| flag.replace(false))
+    }
+}
@@ -28,10 +28,6 @@ fn main() -> Result<()> {
     let index_conn =
         open_index_db().context("Failed to open index database — is EFV_INDEX_KEY set?")?;
 
-    index_conn
-        .execute_batch("ALTER TABLE files ADD COLUMN known_password_hex TEXT;")
-        .ok();
-
     // Load all known passwords once
     let mut known_passwords: Vec<FilePassword> = index_conn
         .prepare(
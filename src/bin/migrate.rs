@@ -0,0 +1,60 @@
+// src/bin/migrate.rs
+//! Bring vault.db / index.db up to the current schema
+//!
+//! Uses the same config / `EFV_*` env vars as the library.
+//! `--dry-run` lists pending migrations without touching either database.
+
+use anyhow::{Context, Result};
+use encrypted_file_vault::db::migrations::{
+    self, MigrationPlan, INDEX_MIGRATIONS, VAULT_MIGRATIONS,
+};
+use encrypted_file_vault::{index_db_conn, vault_db_conn};
+
+fn main() -> Result<()> {
+    let dry_run = std::env::args().skip(1).any(|arg| arg == "--dry-run");
+
+    let (vault_path, vault_key) =
+        vault_db_conn::configured_vault_db().context("vault.db location / key")?;
+    let (index_path, index_key) =
+        index_db_conn::configured_index_db().context("index.db location / key")?;
+
+    let plans = [
+        migrations::dry_run(&vault_path, vault_key.expose_secret(), &VAULT_MIGRATIONS)
+            .with_context(|| format!("inspecting {vault_path}"))?,
+        migrations::dry_run(&index_path, index_key.expose_secret(), &INDEX_MIGRATIONS)
+            .with_context(|| format!("inspecting {index_path}"))?,
+    ];
+    for plan in &plans {
+        print_plan(plan);
+    }
+
+    if dry_run {
+        println!("\n--dry-run: nothing was changed");
+        return Ok(());
+    }
+
+    // Opening applies every pending migration
+    vault_db_conn::open_vault_db_at(&vault_path, &vault_key)
+        .with_context(|| format!("migrating {vault_path}"))?;
+    index_db_conn::open_index_db_at(&index_path, &index_key)
+        .with_context(|| format!("migrating {index_path}"))?;
+
+    println!("\nBoth databases are at the current schema");
+    Ok(())
+}
+
+fn print_plan(plan: &MigrationPlan) {
+    println!(
+        "{}: schema v{} → v{}",
+        plan.database, plan.current_version, plan.target_version
+    );
+    if plan.pending.is_empty() {
+        println!("  up to date");
+    }
+    for migration in &plan.pending {
+        println!(
+            "  pending v{}: {}",
+            migration.version, migration.description
+        );
+    }
+}
@@ -1,7 +1,18 @@
 // src/config/app.rs
 use super::defaults::*;
+use super::env::{self, ENV_CONFIG, ENV_PROFILE};
+use crate::error::CoreError;
+use crate::Result;
 use serde::Deserialize;
-use std::sync::OnceLock;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Config file used when `EFV_CONFIG` is not set
+pub const DEFAULT_CONFIG_FILE: &str = "dev-config.toml";
+
+/// Where the built-in default database paths point; refused by `validate`
+const TEST_DATA_DIR: &str = "tests/data_output";
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
@@ -29,31 +40,475 @@ pub struct Features {
     pub allow_insecure_export: bool,
 }
 
-static CONFIG: OnceLock<Config> = OnceLock::new();
+/// Every overridable setting, named as in the TOML file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConfigField {
+    VaultDb,
+    IndexDb,
+    VaultKey,
+    IndexKey,
+    UseDevKeys,
+    SkipKdfSlowdown,
+    AllowInsecureExport,
+}
+
+impl ConfigField {
+    pub const ALL: [ConfigField; 7] = [
+        ConfigField::VaultDb,
+        ConfigField::IndexDb,
+        ConfigField::VaultKey,
+        ConfigField::IndexKey,
+        ConfigField::UseDevKeys,
+        ConfigField::SkipKdfSlowdown,
+        ConfigField::AllowInsecureExport,
+    ];
+
+    /// `(section, key)` in the TOML file
+    pub fn toml_key(self) -> (&'static str, &'static str) {
+        match self {
+            ConfigField::VaultDb => ("paths", "vault_db"),
+            ConfigField::IndexDb => ("paths", "index_db"),
+            ConfigField::VaultKey => ("keys", "vault_key"),
+            ConfigField::IndexKey => ("keys", "index_key"),
+            ConfigField::UseDevKeys => ("features", "use_dev_keys"),
+            ConfigField::SkipKdfSlowdown => ("features", "skip_kdf_slowdown"),
+            ConfigField::AllowInsecureExport => ("features", "allow_insecure_export"),
+        }
+    }
+
+    /// The `EFV_*` variable that overrides this field
+    pub fn env_var(self) -> &'static str {
+        env::env_var(self)
+    }
+
+    /// Key material must never be echoed in reports
+    pub fn is_secret(self) -> bool {
+        matches!(self, ConfigField::VaultKey | ConfigField::IndexKey)
+    }
+
+    fn is_bool(self) -> bool {
+        matches!(
+            self,
+            ConfigField::UseDevKeys
+                | ConfigField::SkipKdfSlowdown
+                | ConfigField::AllowInsecureExport
+        )
+    }
+}
+
+impl fmt::Display for ConfigField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (section, key) = self.toml_key();
+        write!(f, "{section}.{key}")
+    }
+}
+
+/// Where an effective value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    File(PathBuf),
+    /// `[profiles.<name>]` in the config file
+    Profile(String),
+    Env(&'static str),
+    Programmatic,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => f.write_str("built-in default"),
+            ConfigSource::File(path) => write!(f, "file {}", path.display()),
+            ConfigSource::Profile(name) => write!(f, "profile {name}"),
+            ConfigSource::Env(name) => write!(f, "env {name}"),
+            ConfigSource::Programmatic => f.write_str("set in code"),
+        }
+    }
+}
+
+/// Per-field provenance of a loaded [`Config`]
+#[derive(Debug, Clone)]
+pub struct ConfigSources(BTreeMap<ConfigField, ConfigSource>);
+
+impl ConfigSources {
+    fn defaults() -> Self {
+        Self(
+            ConfigField::ALL
+                .into_iter()
+                .map(|field| (field, ConfigSource::Default))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn record(&mut self, field: ConfigField, source: ConfigSource) {
+        self.0.insert(field, source);
+    }
+
+    pub fn get(&self, field: ConfigField) -> &ConfigSource {
+        self.0.get(&field).unwrap_or(&ConfigSource::Default)
+    }
+}
+
+/// A config together with where each value came from
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    pub config: Config,
+    pub sources: ConfigSources,
+    /// The selected `[profiles.<name>]`, if any
+    pub profile: Option<String>,
+    /// Every profile defined in the config file
+    pub available_profiles: Vec<String>,
+}
+
+impl LoadedConfig {
+    pub fn source(&self, field: ConfigField) -> &ConfigSource {
+        self.sources.get(field)
+    }
+
+    /// A database key, refusing the built-in dev key unless `use_dev_keys` is on
+    pub fn key_hex(&self, field: ConfigField) -> Result<&str> {
+        let value = match field {
+            ConfigField::VaultKey => &self.config.keys.vault_key,
+            ConfigField::IndexKey => &self.config.keys.index_key,
+            other => {
+                return Err(CoreError::Config(format!("{other} is not a key")));
+            }
+        };
+        if !self.config.features.use_dev_keys && *self.source(field) == ConfigSource::Default {
+            return Err(CoreError::Config(format!(
+                "{} required (dev keys are disabled)",
+                field.env_var()
+            )));
+        }
+        Ok(value)
+    }
+
+    /// One line per field: `name = value  (source)`; keys are masked
+    pub fn report(&self) -> String {
+        let profile = match &self.profile {
+            Some(name) => format!("profile = {name}\n"),
+            None => String::new(),
+        };
+        profile
+            + &ConfigField::ALL
+                .into_iter()
+                .map(|field| {
+                    let value = if field.is_secret() {
+                        "<redacted>".to_string()
+                    } else {
+                        self.config.get(field)
+                    };
+                    format!("{field} = {value}  ({})\n", self.source(field))
+                })
+                .collect::<String>()
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            keys: default_keys(),
+            paths: default_paths(),
+            features: default_features(),
+        }
+    }
+}
+
+impl Config {
+    /// Set one field from its string form (booleans: 1/0, true/false, yes/no, on/off)
+    pub fn set(&mut self, field: ConfigField, value: &str) -> Result<()> {
+        match field {
+            ConfigField::VaultDb => self.paths.vault_db = value.to_string(),
+            ConfigField::IndexDb => self.paths.index_db = value.to_string(),
+            ConfigField::VaultKey => self.keys.vault_key = value.to_string(),
+            ConfigField::IndexKey => self.keys.index_key = value.to_string(),
+            ConfigField::UseDevKeys => self.features.use_dev_keys = parse_bool(field, value)?,
+            ConfigField::SkipKdfSlowdown => {
+                self.features.skip_kdf_slowdown = parse_bool(field, value)?
+            }
+            ConfigField::AllowInsecureExport => {
+                self.features.allow_insecure_export = parse_bool(field, value)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject settings that are only acceptable in development
+    ///
+    /// Dev keys (`use_dev_keys` or the built-in key values), the default
+    /// `tests/…` database paths, malformed keys and a shared path for both
+    /// databases are all refused. Every problem is reported at once.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if self.features.use_dev_keys {
+            problems.push("features.use_dev_keys is on".to_string());
+        }
+        for (field, value, dev_value) in [
+            (
+                ConfigField::VaultKey,
+                &self.keys.vault_key,
+                DEFAULT_VAULT_KEY,
+            ),
+            (
+                ConfigField::IndexKey,
+                &self.keys.index_key,
+                DEFAULT_INDEX_KEY,
+            ),
+        ] {
+            if value.trim().eq_ignore_ascii_case(dev_value) {
+                problems.push(format!("{field} is the built-in development key"));
+            } else if crate::db::keying::parse_key_hex(value).is_err() {
+                problems.push(format!("{field} is not 64 hex characters"));
+            }
+        }
+        for (field, value) in [
+            (ConfigField::VaultDb, &self.paths.vault_db),
+            (ConfigField::IndexDb, &self.paths.index_db),
+        ] {
+            if Path::new(value).starts_with(TEST_DATA_DIR) {
+                problems.push(format!(
+                    "{field} points into the test data directory ({value})"
+                ));
+            }
+        }
+        if self.paths.vault_db == self.paths.index_db {
+            problems.push("paths.vault_db and paths.index_db are the same file".to_string());
+        }
 
-pub fn load() -> &'static Config {
-    CONFIG.get_or_init(|| {
-        let config_path =
-            std::env::var("EFV_CONFIG").unwrap_or_else(|_| "dev-config.toml".to_string());
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(CoreError::Config(format!(
+                "invalid configuration: {}",
+                problems.join("; ")
+            )))
+        }
+    }
 
-        let mut conf = if std::path::Path::new(&config_path).exists() {
-            let content =
-                std::fs::read_to_string(&config_path).expect("Failed to read dev-config.toml");
-            toml::from_str(&content).expect("Invalid TOML in dev-config.toml")
+    /// [`Config::validate`], unless built with the `dev-config` feature
+    ///
+    /// Every config-driven open goes through this, so dev keys and test
+    /// paths are only ever accepted by a build that asked for them.
+    pub fn validate_for_build(&self) -> Result<()> {
+        if cfg!(feature = "dev-config") {
+            Ok(())
         } else {
-            eprintln!("Warning: dev-config.toml not found — using built-in defaults");
-            Config {
-                keys: default_keys(),
-                paths: default_paths(),
-                features: default_features(),
+            self.validate()
+        }
+    }
+
+    /// String form of one field
+    pub fn get(&self, field: ConfigField) -> String {
+        match field {
+            ConfigField::VaultDb => self.paths.vault_db.clone(),
+            ConfigField::IndexDb => self.paths.index_db.clone(),
+            ConfigField::VaultKey => self.keys.vault_key.clone(),
+            ConfigField::IndexKey => self.keys.index_key.clone(),
+            ConfigField::UseDevKeys => self.features.use_dev_keys.to_string(),
+            ConfigField::SkipKdfSlowdown => self.features.skip_kdf_slowdown.to_string(),
+            ConfigField::AllowInsecureExport => self.features.allow_insecure_export.to_string(),
+        }
+    }
+}
+
+/// Builds a [`Config`] in layers:
+/// defaults < file < `[profiles.<name>]` < env < programmatic
+///
+/// ```no_run
+/// use encrypted_file_vault::config::{ConfigField, ConfigLoader};
+///
+/// let loaded = ConfigLoader::new()
+///     .set(ConfigField::VaultDb, "/srv/vault/vault.db")
+///     .load()?;
+/// print!("{}", loaded.report());
+/// # Ok::<(), encrypted_file_vault::CoreError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    file: Option<PathBuf>,
+    profile: Option<String>,
+    use_env: bool,
+    overrides: Vec<(ConfigField, String)>,
+}
+
+impl Default for ConfigLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigLoader {
+    /// File from `EFV_CONFIG` (else `dev-config.toml`, if present), env on
+    pub fn new() -> Self {
+        Self {
+            file: None,
+            profile: None,
+            use_env: true,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Read this file instead of `EFV_CONFIG` / `dev-config.toml` (must exist)
+    pub fn file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Select `[profiles.<name>]` (beats `EFV_PROFILE`)
+    pub fn profile(mut self, name: impl Into<String>) -> Self {
+        self.profile = Some(name.into());
+        self
+    }
+
+    /// Ignore `EFV_*` variables (the file path included)
+    pub fn without_env(mut self) -> Self {
+        self.use_env = false;
+        self
+    }
+
+    /// Programmatic override — beats every other layer
+    pub fn set(mut self, field: ConfigField, value: impl Into<String>) -> Self {
+        self.overrides.push((field, value.into()));
+        self
+    }
+
+    pub fn load(&self) -> Result<LoadedConfig> {
+        let mut config = Config::default();
+        let mut sources = ConfigSources::defaults();
+
+        let table = match self.config_file()? {
+            Some(path) => {
+                let table = read_table(&path)?;
+                apply_table(
+                    &mut config,
+                    &mut sources,
+                    &table,
+                    ConfigSource::File(path.clone()),
+                )?;
+                Some(table)
             }
+            None => None,
         };
 
-        // Test mode override
-        if std::env::var("EFV_TEST_MODE").is_ok() {
-            conf.features.use_dev_keys = false;
+        let profiles = table
+            .as_ref()
+            .and_then(|t| t.get("profiles"))
+            .and_then(toml::Value::as_table);
+        let available_profiles: Vec<String> = profiles
+            .map(|p| p.keys().cloned().collect())
+            .unwrap_or_default();
+
+        let profile = self.profile.clone().or_else(|| {
+            self.use_env
+                .then(|| std::env::var(ENV_PROFILE).ok())
+                .flatten()
+        });
+        if let Some(name) = &profile {
+            let section = profiles
+                .and_then(|p| p.get(name))
+                .and_then(toml::Value::as_table)
+                .ok_or_else(|| {
+                    CoreError::Config(format!(
+                        "unknown profile {name:?} (available: {})",
+                        available_profiles.join(", ")
+                    ))
+                })?;
+            apply_table(
+                &mut config,
+                &mut sources,
+                section,
+                ConfigSource::Profile(name.clone()),
+            )?;
+        }
+
+        if self.use_env {
+            env::apply_env(&mut config, &mut sources)?;
         }
 
-        conf
-    })
+        for (field, value) in &self.overrides {
+            config.set(*field, value)?;
+            sources.record(*field, ConfigSource::Programmatic);
+        }
+
+        Ok(LoadedConfig {
+            config,
+            sources,
+            profile,
+            available_profiles,
+        })
+    }
+
+    /// Explicit file > `EFV_CONFIG` (both must exist) > `dev-config.toml` if present
+    fn config_file(&self) -> Result<Option<PathBuf>> {
+        let explicit = self.file.clone().or_else(|| {
+            self.use_env
+                .then(|| std::env::var(ENV_CONFIG).ok().map(PathBuf::from))
+                .flatten()
+        });
+
+        match explicit {
+            Some(path) if !path.exists() => Err(CoreError::Config(format!(
+                "config file {} does not exist",
+                path.display()
+            ))),
+            Some(path) => Ok(Some(path)),
+            None => {
+                let fallback = PathBuf::from(DEFAULT_CONFIG_FILE);
+                Ok(fallback.exists().then_some(fallback))
+            }
+        }
+    }
+}
+
+fn read_table(path: &Path) -> Result<toml::Table> {
+    let content = std::fs::read_to_string(path)?;
+    toml::from_str(&content)
+        .map_err(|e| CoreError::Config(format!("invalid TOML in {}: {e}", path.display())))
+}
+
+/// Overlay every field present in a TOML table; absent fields keep their value
+fn apply_table(
+    config: &mut Config,
+    sources: &mut ConfigSources,
+    table: &toml::Table,
+    source: ConfigSource,
+) -> Result<()> {
+    for field in ConfigField::ALL {
+        let (section, key) = field.toml_key();
+        let Some(value) = table.get(section).and_then(|s| s.get(key)) else {
+            continue;
+        };
+
+        let value = match value {
+            toml::Value::String(s) if !field.is_bool() => s.clone(),
+            toml::Value::Boolean(b) if field.is_bool() => b.to_string(),
+            other => {
+                return Err(CoreError::Config(format!(
+                    "{field} ({source}): unexpected {} value",
+                    other.type_str()
+                )));
+            }
+        };
+        config.set(field, &value)?;
+        sources.record(field, source.clone());
+    }
+    Ok(())
+}
+
+fn parse_bool(field: ConfigField, value: &str) -> Result<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        other => Err(CoreError::Config(format!(
+            "{field}: expected a boolean, got {other:?}"
+        ))),
+    }
+}
+
+/// Load the config from the default layers (file, profile, env)
+///
+/// Nothing is cached: every call re-reads the file and environment.
+pub fn load() -> Result<Config> {
+    Ok(ConfigLoader::new().load()?.config)
 }
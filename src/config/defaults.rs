@@ -1,8 +1,11 @@
 // src/config/defaults.rs
 use crate::config::app::{Features, Keys, Paths};
 
-pub const DEFAULT_VAULT_KEY: &str = "dev-vault-master-password-2025";
-pub const DEFAULT_INDEX_KEY: &str = "dev-index-password-2025";
+// Raw 256-bit SQLCipher keys (64 hex chars) — development only
+pub const DEFAULT_VAULT_KEY: &str =
+    "dededededededededededededededededededededededededededededededede";
+pub const DEFAULT_INDEX_KEY: &str =
+    "d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1";
 
 pub fn default_keys() -> Keys {
     Keys {
@@ -20,8 +23,8 @@ pub fn default_paths() -> Paths {
 
 pub fn default_features() -> Features {
     Features {
-        use_dev_keys: true,
+        use_dev_keys: false,
         skip_kdf_slowdown: true,
-        allow_insecure_export: true,
+        allow_insecure_export: false,
     }
 }
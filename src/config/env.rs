@@ -1,3 +1,71 @@
 // src/config/env.rs
-// Future: override config from environment variables
-// e.g. EFV_VAULT_DB, EFV_FEATURE_SKIP_KDF, etc.
+//! `EFV_*` environment overrides
+//!
+//! | Variable                    | Field                            |
+//! |-----------------------------|----------------------------------|
+//! | `EFV_CONFIG`                | config file path (not a field)   |
+//! | `EFV_PROFILE`               | `[profiles.<name>]` to apply     |
+//! | `EFV_VAULT_DB`              | `paths.vault_db`                 |
+//! | `EFV_INDEX_DB`              | `paths.index_db`                 |
+//! | `EFV_VAULT_KEY`             | `keys.vault_key` (64 hex)        |
+//! | `EFV_INDEX_KEY`             | `keys.index_key` (64 hex)        |
+//! | `EFV_USE_DEV_KEYS`          | `features.use_dev_keys`          |
+//! | `EFV_SKIP_KDF_SLOWDOWN`     | `features.skip_kdf_slowdown`     |
+//! | `EFV_ALLOW_INSECURE_EXPORT` | `features.allow_insecure_export` |
+//! | `EFV_TEST_MODE`             | legacy alias: `use_dev_keys = false` |
+//!
+//! Booleans accept `1/0`, `true/false`, `yes/no`, `on/off`.
+
+use super::app::{Config, ConfigField, ConfigSource, ConfigSources};
+use crate::Result;
+
+/// Path of the TOML config file
+pub const ENV_CONFIG: &str = "EFV_CONFIG";
+
+/// Name of the `[profiles.<name>]` section to apply
+pub const ENV_PROFILE: &str = "EFV_PROFILE";
+
+/// Legacy switch: any value forces `features.use_dev_keys = false`
+pub const ENV_TEST_MODE: &str = "EFV_TEST_MODE";
+
+/// Variable that overrides `field`
+pub fn env_var(field: ConfigField) -> &'static str {
+    match field {
+        ConfigField::VaultDb => "EFV_VAULT_DB",
+        ConfigField::IndexDb => "EFV_INDEX_DB",
+        ConfigField::VaultKey => "EFV_VAULT_KEY",
+        ConfigField::IndexKey => "EFV_INDEX_KEY",
+        ConfigField::UseDevKeys => "EFV_USE_DEV_KEYS",
+        ConfigField::SkipKdfSlowdown => "EFV_SKIP_KDF_SLOWDOWN",
+        ConfigField::AllowInsecureExport => "EFV_ALLOW_INSECURE_EXPORT",
+    }
+}
+
+/// Apply every set `EFV_*` variable on top of `config`
+pub(crate) fn apply_env(config: &mut Config, sources: &mut ConfigSources) -> Result<()> {
+    apply_env_from(config, sources, |name| std::env::var(name).ok())
+}
+
+pub(crate) fn apply_env_from<F>(
+    config: &mut Config,
+    sources: &mut ConfigSources,
+    lookup: F,
+) -> Result<()>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if lookup(ENV_TEST_MODE).is_some() {
+        config.features.use_dev_keys = false;
+        sources.record(ConfigField::UseDevKeys, ConfigSource::Env(ENV_TEST_MODE));
+    }
+
+    // The explicit variable wins over the legacy alias
+    for field in ConfigField::ALL {
+        let name = env_var(field);
+        if let Some(value) = lookup(name) {
+            config.set(field, &value)?;
+            sources.record(field, ConfigSource::Env(name));
+        }
+    }
+    Ok(())
+}
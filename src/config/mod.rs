@@ -1,10 +1,16 @@
 // src/config/mod.rs
 //! Configuration system for encrypted-file-vault
 //!
-//! Central, lazy-loaded global config with TOML + env overrides.
+//! `Config` is a plain value: build one with [`ConfigLoader`] and hand it to
+//! [`crate::Vault::from_config`]. Layered: built-in defaults < TOML file <
+//! `[profiles.<name>]` (`EFV_PROFILE`) < `EFV_*` env vars < values set in code.
+//! Every effective value remembers its source.
 
-pub use app::{load, Config};
+pub use app::{
+    load, Config, ConfigField, ConfigLoader, ConfigSource, ConfigSources, Features, Keys,
+    LoadedConfig, Paths, DEFAULT_CONFIG_FILE,
+};
 
 mod app;
 mod defaults;
-// mod env;
+pub mod env;
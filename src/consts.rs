@@ -1,19 +1,50 @@
 // src/consts.rs
 //! Shared constants — security parameters and defaults
 
-/// Recommended KDF iterations for SQLCipher databases (2025+)
-// ~0.1–0.2s on modern hardware — good default
+/// KDF iterations of legacy passphrase-keyed SQLCipher databases
+// Only used by `db::keying::migrate_passphrase_db`; raw-key DBs skip PBKDF2
 pub const DB_KDF_ITERATIONS: u32 = 256_000;
 
-/// KDF iterations when deriving from a human passphrase (future use)
-pub const PASSPHRASE_KDF_ITERATIONS: u32 = 600_000;
+/// Argon2id memory cost (KiB) when deriving DB keys from a passphrase
+// 64 MiB / 3 passes / 1 lane — OWASP 2025 guidance, ~0.3s on modern hardware
+pub const ARGON2_M_COST_KIB: u32 = 64 * 1024;
+
+/// Argon2id time cost (passes)
+pub const ARGON2_T_COST: u32 = 3;
+
+/// Argon2id parallelism (lanes)
+pub const ARGON2_P_COST: u32 = 1;
+
+/// Highest Argon2id memory cost (KiB) accepted from a header — 4 GiB
+// Headers are read before the passphrase is checked, so their parameters
+// are untrusted until then
+pub const ARGON2_MAX_M_COST_KIB: u32 = 4 * 1024 * 1024;
+
+/// Highest Argon2id time cost (passes) accepted from a header
+pub const ARGON2_MAX_T_COST: u32 = 16;
 
 /// KDF iterations when using a random 256-bit key (current use case)
 pub const RANDOM_KEY_KDF_ITERATIONS: u32 = 1;
 
+/// Buffer size for streaming file encryption/decryption
+// Caps peak memory per stream regardless of file size
+pub const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// File names inside a vault directory
+pub const VAULT_DB_FILE_NAME: &str = "vault.db";
+pub const INDEX_DB_FILE_NAME: &str = "index.db";
+pub const VAULT_HEADER_FILE_NAME: &str = "vault-header.json";
+
 /// Default number of hex characters shown in human-readable filenames
 pub const DEFAULT_ID_LENGTH_HEX: i64 = 20;
 
+/// Shortest / longest id prefix in a `human` filename (the id is 64 hex)
+pub const MIN_ID_LENGTH_HEX: u64 = 8;
+pub const MAX_ID_LENGTH_HEX: u64 = 64;
+
+/// Directory next to vault.db that `Vault::store_file` writes ciphertext into
+pub const STORAGE_DIR_NAME: &str = "files";
+
 /// Default filename style
 pub const DEFAULT_FILENAME_STYLE: &str = "human";
 
@@ -1,8 +1,9 @@
 // src/crypto/decrypt.rs
 use crate::aliases::{CypherText, FilePassword, PlainText};
 use crate::error::CoreError;
+use crate::stream::CountingWriter;
 use aescrypt_rs::decrypt;
-use std::io::Cursor;
+use std::io::{Cursor, Read, Write};
 
 /// Decrypt AES-Crypt ciphertext → plaintext (in-memory)
 ///
@@ -12,7 +13,30 @@ pub fn decrypt_to_vec(
     password: &FilePassword,
 ) -> Result<PlainText, CoreError> {
     let mut out = Vec::new();
-    decrypt(Cursor::new(ciphertext.expose_secret()), &mut out, password)
-        .map_err(CoreError::Crypto)?;
+    decrypt_checked(Cursor::new(ciphertext.expose_secret()), &mut out, password)?;
     Ok(PlainText::new(out))
 }
+
+/// Decrypt any AES Crypt (v0–v3) reader into any writer with typed failures
+///
+/// Input without an AES Crypt header is `CorruptCiphertext` before the
+/// decryptor runs; other failures go through [`CoreError::from_decrypt`].
+/// Returns the number of plaintext bytes written.
+pub(crate) fn decrypt_checked<R: Read, W: Write>(
+    mut input: R,
+    output: W,
+    password: &FilePassword,
+) -> Result<u64, CoreError> {
+    let mut header = Vec::with_capacity(4);
+    input.by_ref().take(4).read_to_end(&mut header)?;
+    if !matches!(header.as_slice(), [b'A', b'E', b'S', 0..=3]) {
+        return Err(CoreError::CorruptCiphertext(
+            "no AES Crypt header".to_string(),
+        ));
+    }
+
+    let mut writer = CountingWriter::new(output);
+    decrypt(Cursor::new(header).chain(input), &mut writer, password)
+        .map_err(CoreError::from_decrypt)?;
+    Ok(writer.bytes_written())
+}
@@ -19,6 +19,6 @@ pub fn encrypt_to_vec(
         password,
         RANDOM_KEY_KDF_ITERATIONS,
     )
-    .map_err(CoreError::Crypto)?;
+    .map_err(CoreError::from)?;
     Ok(CypherText::new(out))
 }
@@ -1,11 +1,12 @@
 // src/crypto/legacy.rs
-use crate::aliases::{CypherText, FileKey32, FilePassword, PlainText, RandomFileKey32};
+use crate::aliases::{CypherText, FileKey32, FilePassword, PlainText};
 use crate::consts::RANDOM_KEY_KDF_ITERATIONS;
 use crate::error::CoreError;
+use crate::key_ops::generate_key;
 use aescrypt_rs::convert::convert_to_v3_ext;
-use secure_gate::SecureRandomExt;
+use secure_gate::SecureConversionsExt;
 use std::io::{Cursor, Write};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, PoisonError};
 
 use super::encrypt::encrypt_to_vec;
 
@@ -14,7 +15,10 @@ struct ThreadSafeVec(Arc<Mutex<Vec<u8>>>);
 
 impl Write for ThreadSafeVec {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.0.lock().unwrap().extend_from_slice(buf);
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend_from_slice(buf);
         Ok(buf.len())
     }
     fn flush(&mut self) -> std::io::Result<()> {
@@ -27,34 +31,24 @@ pub fn upgrade_from_legacy(
     ciphertext: CypherText,
     old_password: &FilePassword,
 ) -> Result<(CypherText, FileKey32), CoreError> {
-    let new_password_hex = RandomFileKey32::random_hex();
-    let new_key_bytes = new_password_hex.to_bytes();
-    let new_key_arr: [u8; 32] = new_key_bytes
-        .try_into()
-        .expect("RandomFileKey32::random_hex() always yields 64 chars → 32 bytes");
-    let new_key = FileKey32::new(new_key_arr);
-    let new_password = FilePassword::new(new_password_hex.expose_secret().clone());
-
-    let leaked: &'static [u8] = Box::leak(ciphertext.into_inner()).as_slice();
+    let new_key = generate_key();
+    let new_password = FilePassword::new(new_key.expose_secret().to_hex());
+
     let buffer = Arc::new(Mutex::new(Vec::new()));
     let writer = ThreadSafeVec(buffer.clone());
 
     convert_to_v3_ext(
-        Cursor::new(leaked),
+        Cursor::new(*ciphertext.into_inner()),
         writer,
         old_password,
         Some(&new_password),
         RANDOM_KEY_KDF_ITERATIONS,
     )
-    .map_err(CoreError::Crypto)?;
+    .map_err(CoreError::from)?;
 
-    let plaintext_vec = std::mem::take(&mut *buffer.lock().unwrap());
+    let plaintext_vec = std::mem::take(&mut *buffer.lock().unwrap_or_else(PoisonError::into_inner));
     let plaintext = PlainText::new(plaintext_vec);
     let final_ct = encrypt_to_vec(&plaintext, &new_password)?;
 
-    unsafe {
-        let _ = Box::from_raw(leaked.as_ptr() as *mut Vec<u8>);
-    }
-
     Ok((final_ct, new_key))
 }
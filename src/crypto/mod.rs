@@ -7,6 +7,7 @@ mod encrypt;
 mod legacy;
 mod rotate; // ← private, correct
 
+pub(crate) use decrypt::decrypt_checked;
 pub use decrypt::decrypt_to_vec;
 pub use encrypt::encrypt_to_vec;
 pub use legacy::upgrade_from_legacy;
@@ -1,12 +1,15 @@
 // src/crypto/rotate.rs
-use crate::aliases::{CypherText, FileKey32, FilePassword, RandomFileKey32};
+use crate::aliases::{CypherText, FileKey32, FilePassword};
 use crate::error::CoreError;
-use aescrypt_rs::{decrypt, encrypt};
-use secure_gate::SecureRandomExt;
+use crate::key_ops::generate_key;
+use aescrypt_rs::encrypt;
+use secure_gate::SecureConversionsExt;
 use std::io::{self, Read, Write};
+use std::thread::JoinHandle;
 
 use pipe::pipe;
 
+use super::decrypt::decrypt_checked;
 use super::legacy::upgrade_from_legacy;
 
 /// In-memory key rotation — only for small files (< ~100 MB)
@@ -19,16 +22,8 @@ pub fn rotate_key(
     if ciphertext.expose_secret().starts_with(b"AES\x03") {
         let plaintext = super::decrypt_to_vec(ciphertext, old_password)?;
 
-        let new_password_hex = RandomFileKey32::random_hex();
-        let new_key_bytes = new_password_hex.to_bytes();
-
-        let new_key = FileKey32::new(
-            new_key_bytes
-                .try_into()
-                .expect("RandomFileKey32::random_hex() always yields exactly 32 bytes"),
-        );
-
-        let new_password = FilePassword::new(new_password_hex.expose_secret().clone());
+        let new_key = generate_key();
+        let new_password = FilePassword::new(new_key.expose_secret().to_hex());
         let new_ct = super::encrypt_to_vec(&plaintext, &new_password)?;
 
         Ok((new_ct, new_key))
@@ -46,16 +41,8 @@ pub fn rotate_key_streaming<R: Read + Send + 'static, W: Write + Send + 'static>
     output: W,
     old_password: &FilePassword,
 ) -> Result<FileKey32, CoreError> {
-    let new_password_hex = RandomFileKey32::random_hex();
-    let new_key_bytes = new_password_hex.to_bytes();
-
-    let new_key = FileKey32::new(
-        new_key_bytes
-            .try_into()
-            .expect("RandomFileKey32::random_hex() always yields exactly 32 bytes"),
-    );
-
-    let new_password = FilePassword::new(new_password_hex.expose_secret().clone());
+    let new_key = generate_key();
+    let new_password = FilePassword::new(new_key.expose_secret().to_hex());
 
     let (mut decrypt_reader, decrypt_writer) = pipe();
     let (encrypt_reader, mut encrypt_writer) = pipe(); // ← MUT HERE
@@ -64,15 +51,28 @@ pub fn rotate_key_streaming<R: Read + Send + 'static, W: Write + Send + 'static>
     let new_password_cloned = new_password.clone();
 
     let decrypt_thread =
-        std::thread::spawn(move || decrypt(input, decrypt_writer, &old_password_cloned));
+        std::thread::spawn(move || decrypt_checked(input, decrypt_writer, &old_password_cloned));
 
     let encrypt_thread =
         std::thread::spawn(move || encrypt(encrypt_reader, output, &new_password_cloned, 1));
 
     io::copy(&mut decrypt_reader, &mut encrypt_writer).map_err(CoreError::Io)?;
+    // Close the pipe so the encryptor sees EOF — otherwise the join below never returns
+    drop(encrypt_writer);
 
-    decrypt_thread.join().unwrap().map_err(CoreError::Crypto)?;
-    encrypt_thread.join().unwrap().map_err(CoreError::Crypto)?;
+    join_worker(decrypt_thread, "decrypt")?;
+    join_worker(encrypt_thread, "encrypt")?;
 
     Ok(new_key)
 }
+
+/// Join a pipeline thread; a panic in it becomes an error, not a panic here
+fn join_worker<T, E>(handle: JoinHandle<Result<T, E>>, stage: &str) -> Result<T, CoreError>
+where
+    CoreError: From<E>,
+{
+    handle
+        .join()
+        .map_err(|_| CoreError::Io(io::Error::other(format!("{stage} worker panicked"))))?
+        .map_err(CoreError::from)
+}
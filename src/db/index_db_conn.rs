@@ -1,69 +1,46 @@
 // src/index.rs
-use crate::consts::DB_KDF_ITERATIONS;
-use rusqlite::{Connection, Result};
-use std::{env, fs, path::Path};
-
+use crate::aliases::IndexKey32;
+use crate::config::{ConfigField, ConfigLoader};
+use crate::db::keying::{apply_raw_key, parse_key_hex};
+use crate::db::migrations::{self, INDEX_MIGRATIONS};
+use crate::Result;
+use rusqlite::Connection;
+use std::{fs, path::Path};
+
+/// Open the index DB named by the layered config / `EFV_INDEX_DB` / `EFV_INDEX_KEY`
+///
+/// Keys are 64 hex characters. Prefer [`open_index_db_at`]
+/// (or [`crate::Vault::open`]) with explicit values.
 pub fn open_index_db() -> Result<Connection> {
-    let config = crate::config::load();
-
-    let db_path = env::var("EFV_INDEX_DB").unwrap_or_else(|_| config.paths.index_db.clone());
+    let (db_path, key) = configured_index_db()?;
+    open_index_db_at(db_path, &key)
+}
 
-    if let Some(parent) = Path::new(&db_path).parent() {
-        let _ = fs::create_dir_all(parent);
-    }
+/// Path and key [`open_index_db`] would use
+///
+/// The loaded config must pass [`crate::config::Config::validate_for_build`].
+pub fn configured_index_db() -> Result<(String, IndexKey32)> {
+    let loaded = ConfigLoader::new().load()?;
+    loaded.config.validate_for_build()?;
 
-    let conn = Connection::open(&db_path)?;
+    let db_path = loaded.config.paths.index_db.clone();
+    let key = IndexKey32::new(*parse_key_hex(loaded.key_hex(ConfigField::IndexKey)?)?);
 
-    let key: &str = if config.features.use_dev_keys {
-        config.keys.index_key.as_str()
-    } else {
-        Box::leak(
-            std::env::var("EFV_INDEX_KEY")
-                .expect("EFV_INDEX_KEY required")
-                .into_boxed_str(),
-        )
-    };
+    Ok((db_path, key))
+}
 
-    conn.execute_batch(&format!(
-        r#"
-        PRAGMA key = '{key}';
-        PRAGMA cipher_page_size = 4096;
-        PRAGMA kdf_iter = {DB_KDF_ITERATIONS};
-        PRAGMA cipher_hmac_algorithm = HMAC_SHA512;
-        PRAGMA cipher_kdf_algorithm = PBKDF2_HMAC_SHA512;
-        PRAGMA cipher_plaintext_header_size = 0;
-        "#
-    ))?;
+/// Open (or create) the index DB at an explicit path with a raw 256-bit key
+pub fn open_index_db_at<P: AsRef<Path>>(db_path: P, key: &IndexKey32) -> Result<Connection> {
+    let db_path = db_path.as_ref();
 
-    let _ = conn.execute(
-        "ALTER TABLE files ADD COLUMN encryption_algo TEXT NOT NULL DEFAULT 'AESCryptV3'",
-        [],
-    );
+    if let Some(parent) = db_path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
 
-    conn.execute_batch(
-        r#"
-        CREATE TABLE IF NOT EXISTS files (
-            file_id TEXT PRIMARY KEY,
-            content_hash TEXT NOT NULL,
-            display_name TEXT NOT NULL,
-            current_path TEXT NOT NULL,
-            plaintext_size INTEGER NOT NULL,
-            created_at TEXT NOT NULL,
-            rotated_at TEXT,
-            encryption_algo TEXT NOT NULL DEFAULT 'AESCryptV3',
-            filename_style TEXT NOT NULL DEFAULT 'human',
-            id_length INTEGER NOT NULL DEFAULT 20,
-            salted_with_path INTEGER NOT NULL DEFAULT 0,
-            tags TEXT,
-            note TEXT
-        );
+    let mut conn = Connection::open(db_path)?;
+    apply_raw_key(&conn, key.expose_secret())?;
 
-        CREATE INDEX IF NOT EXISTS idx_content_hash ON files(content_hash);
-        CREATE INDEX IF NOT EXISTS idx_display_name ON files(display_name);
-        CREATE INDEX IF NOT EXISTS idx_current_path ON files(current_path);
-        CREATE INDEX IF NOT EXISTS idx_encryption_algo ON files(encryption_algo);
-        "#,
-    )?;
+    migrations::migrate(&mut conn, &INDEX_MIGRATIONS)?;
 
     Ok(conn)
 }
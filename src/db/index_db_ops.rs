@@ -4,9 +4,13 @@
 //! in the index database. It does not include DB connection logic
 //! (see crate::index for open_index_db).
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::db::tag_ops;
+use crate::error::CoreError;
+use crate::Result;
 
 #[derive(Debug, Clone)]
 pub struct FileEntry {
@@ -17,27 +21,262 @@ pub struct FileEntry {
     pub plaintext_size: u64,
     pub filename_style: String,
     pub id_length_hex: u64,
+    /// `file_id` is salted with the original plaintext path (see `crate::file_id`)
+    pub salted_with_path: bool,
     pub known_password_hex: Option<String>,
 }
 
 /// Store or update a file entry in the index database
-pub fn store_file_entry(conn: &Connection, entry: &FileEntry) -> rusqlite::Result<()> {
+///
+/// Fails with `CoreError::InvalidPath` if `current_path` is not valid UTF-8.
+pub fn store_file_entry(conn: &Connection, entry: &FileEntry) -> Result<()> {
+    let current_path = entry
+        .current_path
+        .to_str()
+        .ok_or_else(|| CoreError::InvalidPath(entry.current_path.clone()))?;
+
     conn.execute(
         r#"
         INSERT OR REPLACE INTO files (
             file_id, content_hash, display_name, current_path,
-            plaintext_size, created_at, filename_style, id_length
-        ) VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'), ?6, ?7)
+            plaintext_size, created_at, filename_style, id_length, salted_with_path
+        ) VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'), ?6, ?7, ?8)
         "#,
         params![
             &entry.file_id,
             &entry.content_hash,
             &entry.display_name,
-            entry.current_path.to_str().unwrap(),
+            current_path,
             entry.plaintext_size as i64,
             &entry.filename_style,
             entry.id_length_hex as i64,
+            entry.salted_with_path,
         ],
     )?;
     Ok(())
 }
+
+/// Columns read back into a [`FileEntry`], in `file_entry_from_row` order
+const FILE_ENTRY_COLUMNS: &str = "file_id, content_hash, display_name, current_path, \
+     plaintext_size, filename_style, id_length, salted_with_path";
+
+fn file_entry_from_row(row: &Row<'_>) -> rusqlite::Result<FileEntry> {
+    Ok(FileEntry {
+        file_id: row.get(0)?,
+        content_hash: row.get(1)?,
+        display_name: row.get(2)?,
+        current_path: PathBuf::from(row.get::<_, String>(3)?),
+        plaintext_size: row.get::<_, i64>(4)? as u64,
+        filename_style: row.get(5)?,
+        id_length_hex: row.get::<_, i64>(6)? as u64,
+        salted_with_path: row.get(7)?,
+        known_password_hex: None,
+    })
+}
+
+/// Look up a single file entry by id
+pub fn get_file_entry(conn: &Connection, file_id: &str) -> rusqlite::Result<Option<FileEntry>> {
+    conn.query_row(
+        &format!("SELECT {FILE_ENTRY_COLUMNS} FROM files WHERE file_id = ?1"),
+        [file_id],
+        file_entry_from_row,
+    )
+    .optional()
+}
+
+/// All file entries, ordered by display name
+pub fn list_file_entries(conn: &Connection) -> rusqlite::Result<Vec<FileEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {FILE_ENTRY_COLUMNS} FROM files ORDER BY display_name, file_id"
+    ))?;
+    let rows = stmt.query_map([], file_entry_from_row)?;
+    rows.collect()
+}
+
+/// Entries whose display name matches exactly (display names are not unique)
+pub fn find_file_entries_by_name(
+    conn: &Connection,
+    display_name: &str,
+) -> rusqlite::Result<Vec<FileEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {FILE_ENTRY_COLUMNS} FROM files WHERE display_name = ?1 ORDER BY file_id"
+    ))?;
+    let rows = stmt.query_map([display_name], file_entry_from_row)?;
+    rows.collect()
+}
+
+/// Columns shared by `files` and `trash`, moved as a unit
+const TRASH_COLUMNS: &str = "file_id, content_hash, display_name, current_path, plaintext_size, \
+     created_at, rotated_at, encryption_algo, filename_style, id_length, salted_with_path, \
+     tags, note, known_password_hex";
+
+/// Move an entry from `files` to `trash`; returns whether it existed
+pub fn move_to_trash(conn: &Connection, file_id: &str) -> rusqlite::Result<bool> {
+    let tx = conn.unchecked_transaction()?;
+    let moved = tx.execute(
+        &format!("INSERT INTO trash ({TRASH_COLUMNS}) SELECT {TRASH_COLUMNS} FROM files WHERE file_id = ?1"),
+        [file_id],
+    )?;
+    tx.execute("DELETE FROM files WHERE file_id = ?1", [file_id])?;
+    tx.commit()?;
+    Ok(moved > 0)
+}
+
+/// Move an entry from `trash` back to `files`; returns whether it existed
+pub fn restore_from_trash(conn: &Connection, file_id: &str) -> rusqlite::Result<bool> {
+    let tx = conn.unchecked_transaction()?;
+    let restored = tx.execute(
+        &format!("INSERT INTO files ({TRASH_COLUMNS}) SELECT {TRASH_COLUMNS} FROM trash WHERE file_id = ?1"),
+        [file_id],
+    )?;
+    tx.execute("DELETE FROM trash WHERE file_id = ?1", [file_id])?;
+    tx.commit()?;
+    Ok(restored > 0)
+}
+
+/// A soft-deleted entry
+pub fn get_trash_entry(conn: &Connection, file_id: &str) -> rusqlite::Result<Option<FileEntry>> {
+    conn.query_row(
+        &format!("SELECT {FILE_ENTRY_COLUMNS} FROM trash WHERE file_id = ?1"),
+        [file_id],
+        file_entry_from_row,
+    )
+    .optional()
+}
+
+/// Every soft-deleted entry, most recently deleted first
+pub fn list_trash_entries(conn: &Connection) -> rusqlite::Result<Vec<FileEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {FILE_ENTRY_COLUMNS} FROM trash ORDER BY deleted_at DESC, file_id"
+    ))?;
+    let rows = stmt.query_map([], file_entry_from_row)?;
+    rows.collect()
+}
+
+/// Drop an entry (with its tags and references) from both `files` and `trash`; returns rows removed
+pub fn delete_file_entry(conn: &Connection, file_id: &str) -> rusqlite::Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let removed = tx.execute("DELETE FROM files WHERE file_id = ?1", [file_id])?
+        + tx.execute("DELETE FROM trash WHERE file_id = ?1", [file_id])?;
+    tx.execute("DELETE FROM file_refs WHERE file_id = ?1", [file_id])?;
+    tag_ops::clear_tags(&tx, file_id)?;
+    tx.commit()?;
+    Ok(removed)
+}
+
+/// Live (preferred) or trashed entry holding `content_hash`
+pub fn find_by_content_hash(
+    conn: &Connection,
+    content_hash: &str,
+) -> rusqlite::Result<Option<FileEntry>> {
+    for table in ["files", "trash"] {
+        let found = conn
+            .query_row(
+                &format!(
+                    "SELECT {FILE_ENTRY_COLUMNS} FROM {table} WHERE content_hash = ?1 \
+                     ORDER BY created_at, file_id LIMIT 1"
+                ),
+                [content_hash],
+                file_entry_from_row,
+            )
+            .optional()?;
+        if found.is_some() {
+            return Ok(found);
+        }
+    }
+    Ok(None)
+}
+
+/// Is `file_id` in `files` or `trash`?
+pub fn file_id_exists(conn: &Connection, file_id: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM files WHERE file_id = ?1) \
+             OR EXISTS (SELECT 1 FROM trash WHERE file_id = ?1)",
+        [file_id],
+        |row| row.get(0),
+    )
+}
+
+/// Does any live or trashed entry keep its ciphertext at `path`?
+pub fn path_in_use(conn: &Connection, path: &Path) -> Result<bool> {
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| CoreError::InvalidPath(path.to_path_buf()))?;
+    Ok(conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM files WHERE current_path = ?1) \
+             OR EXISTS (SELECT 1 FROM trash WHERE current_path = ?1)",
+        [path_str],
+        |row| row.get(0),
+    )?)
+}
+
+/// One source a stored ciphertext was added from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileRef {
+    pub file_id: String,
+    /// Canonical plaintext path at add time (`""` if added before references)
+    pub source_path: String,
+    pub display_name: String,
+    /// Path inside the directory it was imported from (`import_directory`)
+    pub relative_path: Option<String>,
+    pub added_at: String,
+}
+
+/// Record a reference; returns false if this source was already recorded
+pub fn add_file_ref(
+    conn: &Connection,
+    file_id: &str,
+    source_path: &str,
+    display_name: &str,
+) -> rusqlite::Result<bool> {
+    let added = conn.execute(
+        "INSERT OR IGNORE INTO file_refs (file_id, source_path, display_name) VALUES (?1, ?2, ?3)",
+        params![file_id, source_path, display_name],
+    )?;
+    Ok(added > 0)
+}
+
+/// Record where inside an imported directory a reference came from
+pub fn set_ref_relative_path(
+    conn: &Connection,
+    file_id: &str,
+    source_path: &str,
+    relative_path: &str,
+) -> rusqlite::Result<bool> {
+    let updated = conn.execute(
+        "UPDATE file_refs SET relative_path = ?3 WHERE file_id = ?1 AND source_path = ?2",
+        [file_id, source_path, relative_path],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Every reference to a file, oldest first
+pub fn list_file_refs(conn: &Connection, file_id: &str) -> rusqlite::Result<Vec<FileRef>> {
+    let mut stmt = conn.prepare(
+        "SELECT file_id, source_path, display_name, relative_path, added_at FROM file_refs \
+         WHERE file_id = ?1 ORDER BY added_at, source_path",
+    )?;
+    let rows = stmt.query_map([file_id], |row| {
+        Ok(FileRef {
+            file_id: row.get(0)?,
+            source_path: row.get(1)?,
+            display_name: row.get(2)?,
+            relative_path: row.get(3)?,
+            added_at: row.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Drop one reference; returns whether it existed
+pub fn remove_file_ref(
+    conn: &Connection,
+    file_id: &str,
+    source_path: &str,
+) -> rusqlite::Result<bool> {
+    let removed = conn.execute(
+        "DELETE FROM file_refs WHERE file_id = ?1 AND source_path = ?2",
+        [file_id, source_path],
+    )?;
+    Ok(removed > 0)
+}
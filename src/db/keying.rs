@@ -0,0 +1,197 @@
+// src/db/keying.rs
+//! SQLCipher keying for vault.db and index.db
+//!
+//! Databases are unlocked with SQLCipher's raw-key form (`x'<64 hex>'`):
+//! the 256-bit key is used directly, so there is no PBKDF2 on every open and
+//! no user-supplied text is ever interpolated into SQL. Statements carrying
+//! key material are built in zeroizing buffers.
+
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, OpenFlags};
+use zeroize::Zeroizing;
+
+use crate::atomic_write::{rename_durable, write_atomic};
+use crate::consts::DB_KDF_ITERATIONS;
+use crate::error::CoreError;
+use crate::Result;
+
+/// Cipher settings shared by both databases (must match at create and open)
+const CIPHER_SETTINGS: &str = r#"
+    PRAGMA cipher_page_size = 4096;
+    PRAGMA cipher_hmac_algorithm = HMAC_SHA512;
+    PRAGMA cipher_kdf_algorithm = PBKDF2_HMAC_SHA512;
+    PRAGMA cipher_plaintext_header_size = 0;
+"#;
+
+/// Unlock a freshly opened connection with a raw 256-bit key
+pub(crate) fn apply_raw_key(conn: &Connection, key: &[u8; 32]) -> rusqlite::Result<()> {
+    let statement = raw_key_pragma("key", key);
+    conn.execute_batch(&statement)?;
+    conn.execute_batch(CIPHER_SETTINGS)
+}
+
+/// Re-encrypt an unlocked database under a new raw 256-bit key
+pub(crate) fn rekey_raw(conn: &Connection, new_key: &[u8; 32]) -> rusqlite::Result<()> {
+    let statement = raw_key_pragma("rekey", new_key);
+    conn.execute_batch(&statement)
+}
+
+/// Fail fast if the key is wrong — SQLCipher only checks on first page read
+pub(crate) fn verify_unlocked(conn: &Connection) -> rusqlite::Result<()> {
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| {
+        row.get::<_, i64>(0)
+    })?;
+    Ok(())
+}
+
+/// Migrate a database created with the old passphrase keying to a raw key
+///
+/// Older builds ran `PRAGMA key = '<passphrase>'` with PBKDF2
+/// (`DB_KDF_ITERATIONS`). This opens such a database with its passphrase,
+/// verifies it, and re-keys it in place to `new_key`. Afterwards it opens
+/// with `open_vault_db_at` / `open_index_db_at` as usual.
+pub fn migrate_passphrase_db<P: AsRef<Path>>(
+    db_path: P,
+    passphrase: &str,
+    new_key: &[u8; 32],
+) -> Result<()> {
+    let db_path = db_path.as_ref();
+    if !db_path.exists() {
+        return Err(CoreError::Config(format!(
+            "no database to migrate at {}",
+            db_path.display()
+        )));
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    // Quotes are doubled, so the passphrase can never terminate the literal
+    let statement = Zeroizing::new(format!(
+        "PRAGMA key = '{}';",
+        Zeroizing::new(passphrase.replace('\'', "''")).as_str()
+    ));
+    conn.execute_batch(&statement)?;
+    conn.execute_batch(CIPHER_SETTINGS)?;
+    conn.execute_batch(&format!("PRAGMA kdf_iter = {DB_KDF_ITERATIONS};"))?;
+
+    verify_unlocked(&conn)?;
+    rekey_raw(&conn, new_key)?;
+
+    Ok(())
+}
+
+/// Parse a 64-char hex string (config / env) into raw key bytes
+pub fn parse_key_hex(hex_key: &str) -> Result<Zeroizing<[u8; 32]>> {
+    let mut bytes = Zeroizing::new([0u8; 32]);
+    hex::decode_to_slice(hex_key.trim(), bytes.as_mut_slice()).map_err(|_| {
+        CoreError::Config("database keys must be exactly 64 hex characters (32 bytes)".into())
+    })?;
+    Ok(bytes)
+}
+
+fn raw_key_pragma(pragma: &str, key: &[u8; 32]) -> Zeroizing<String> {
+    let key_hex = Zeroizing::new(hex::encode(key));
+    Zeroizing::new(format!("PRAGMA {pragma} = \"x'{}'\";", key_hex.as_str()))
+}
+
+/// Suffix of the pre-rekey copy kept next to each database until a rekey completes
+pub const REKEY_BACKUP_SUFFIX: &str = ".rekey-backup";
+
+/// Outcome of [`recover_interrupted_rekey`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RekeyRecovery {
+    /// No rekey was in flight
+    Clean,
+    /// The live databases already open with the given keys — backups discarded
+    Completed,
+    /// The backups open with the given keys — restored over the live databases
+    RolledBack,
+}
+
+/// `vault.db` → `vault.db.rekey-backup`
+pub fn rekey_backup_path(db_path: &Path) -> PathBuf {
+    let mut backup = db_path.as_os_str().to_owned();
+    backup.push(REKEY_BACKUP_SUFFIX);
+    PathBuf::from(backup)
+}
+
+/// Copy a database to its rekey backup (fsynced)
+pub(crate) fn backup_for_rekey(db_path: &Path) -> Result<()> {
+    let mut source = File::open(db_path)?;
+    write_atomic(rekey_backup_path(db_path), |out| {
+        io::copy(&mut source, out).map(|_| ())
+    })?;
+    Ok(())
+}
+
+/// Remove a rekey backup if present
+pub(crate) fn discard_rekey_backup(db_path: &Path) -> io::Result<()> {
+    match std::fs::remove_file(rekey_backup_path(db_path)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Does the database at `db_path` exist and unlock with `key`?
+pub(crate) fn opens_with(db_path: &Path, key: &[u8; 32]) -> bool {
+    if !db_path.exists() {
+        return false;
+    }
+    Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .and_then(|conn| {
+            apply_raw_key(&conn, key)?;
+            verify_unlocked(&conn)
+        })
+        .is_ok()
+}
+
+/// Finish or undo a vault/index rekey that was interrupted by a crash
+///
+/// Called with the keys the caller is opening with. Both databases are
+/// decided together, so they can never end up under different key generations:
+/// if both live files open, the backups are stale; if both backups open, they
+/// are restored. Anything else is refused with the files left untouched —
+/// the other key set will resolve it.
+pub fn recover_interrupted_rekey(
+    vault_db: &Path,
+    vault_key: &[u8; 32],
+    index_db: &Path,
+    index_key: &[u8; 32],
+) -> Result<RekeyRecovery> {
+    let vault_backup = rekey_backup_path(vault_db);
+    let index_backup = rekey_backup_path(index_db);
+
+    if !vault_backup.exists() && !index_backup.exists() {
+        return Ok(RekeyRecovery::Clean);
+    }
+
+    if opens_with(vault_db, vault_key) && opens_with(index_db, index_key) {
+        discard_rekey_backup(vault_db)?;
+        discard_rekey_backup(index_db)?;
+        return Ok(RekeyRecovery::Completed);
+    }
+
+    if opens_with(&vault_backup, vault_key) && opens_with(&index_backup, index_key) {
+        restore_rekey_backup(vault_db)?;
+        restore_rekey_backup(index_db)?;
+        return Ok(RekeyRecovery::RolledBack);
+    }
+
+    Err(CoreError::Config(
+        "an interrupted rekey left the databases under a different key — open with the other key set".into(),
+    ))
+}
+
+/// Put the backup back in place, dropping any hot journal from the aborted rekey
+fn restore_rekey_backup(db_path: &Path) -> io::Result<()> {
+    let mut journal = db_path.as_os_str().to_owned();
+    journal.push("-journal");
+    match std::fs::remove_file(PathBuf::from(journal)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    Ok(rename_durable(rekey_backup_path(db_path), db_path)?)
+}
@@ -0,0 +1,493 @@
+// src/db/migrations.rs
+//! Numbered schema migrations for vault.db and index.db
+//!
+//! Each database records the last migration applied in `PRAGMA user_version`.
+//! On open, every migration above that number runs in order inside a single
+//! transaction, so a database is never left half-migrated. A database whose
+//! version is newer than this library knows about is refused.
+//!
+//! Migrations are append-only: never edit or renumber a shipped one, add a
+//! new one at the end. Version 1 of each set adopts databases created before
+//! versioning existed (it only creates what is missing).
+
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags, Transaction};
+
+use crate::db::keying::{apply_raw_key, verify_unlocked};
+use crate::error::CoreError;
+use crate::file_id::{FILE_ID_SCHEME, FILE_ID_SECRET, KEYED_SCHEME};
+use crate::Result;
+
+/// One schema step, applied inside the migration transaction
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&Transaction<'_>) -> rusqlite::Result<()>,
+}
+
+/// The ordered migrations for one database
+pub struct MigrationSet {
+    pub database: &'static str,
+    pub migrations: &'static [Migration],
+}
+
+impl MigrationSet {
+    /// Schema version a fully migrated database is at
+    pub fn latest_version(&self) -> u32 {
+        self.migrations.last().map_or(0, |m| m.version)
+    }
+}
+
+/// What `migrate` would do to a database (see [`dry_run`])
+pub struct MigrationPlan {
+    pub database: &'static str,
+    pub current_version: u32,
+    pub target_version: u32,
+    pub pending: Vec<&'static Migration>,
+}
+
+pub const VAULT_MIGRATIONS: MigrationSet = MigrationSet {
+    database: "vault.db",
+    migrations: &[
+        Migration {
+            version: 1,
+            description: "keys, key_history and the current-key sync trigger",
+            apply: vault_v1_keys,
+        },
+        Migration {
+            version: 2,
+            description: "rotation_journal for crash-safe key rotation",
+            apply: vault_v2_rotation_journal,
+        },
+        Migration {
+            version: 3,
+            description: "audit_log",
+            apply: vault_v3_audit_log,
+        },
+        Migration {
+            version: 4,
+            description: "vault_meta with the file-id secret, file_id_remap journal",
+            apply: vault_v4_file_id_secret,
+        },
+    ],
+};
+
+pub const INDEX_MIGRATIONS: MigrationSet = MigrationSet {
+    database: "index.db",
+    migrations: &[
+        Migration {
+            version: 1,
+            description: "files table and lookup indexes",
+            apply: index_v1_files,
+        },
+        Migration {
+            version: 2,
+            description: "files.known_password_hex",
+            apply: index_v2_known_password_hex,
+        },
+        Migration {
+            version: 3,
+            description: "trash for soft-deleted files",
+            apply: index_v3_trash,
+        },
+        Migration {
+            version: 4,
+            description: "normalized tags and file_tags, migrated from files.tags",
+            apply: index_v4_tags,
+        },
+        Migration {
+            version: 5,
+            description: "file_refs: source paths sharing one ciphertext",
+            apply: index_v5_file_refs,
+        },
+        Migration {
+            version: 6,
+            description: "file_refs.relative_path and the directory import journal",
+            apply: index_v6_import_journal,
+        },
+        Migration {
+            version: 7,
+            description: "relayout_journal for interrupted renames",
+            apply: index_v7_relayout_journal,
+        },
+    ],
+};
+
+/// Current `PRAGMA user_version`
+pub fn schema_version(conn: &Connection) -> rusqlite::Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Bring an unlocked database up to date; returns the versions applied
+pub fn migrate(conn: &mut Connection, set: &MigrationSet) -> Result<Vec<u32>> {
+    let plan = plan(conn, set)?;
+    if plan.pending.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let tx = conn.transaction()?;
+    for migration in &plan.pending {
+        (migration.apply)(&tx)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+    }
+    tx.commit()?;
+
+    Ok(plan.pending.iter().map(|m| m.version).collect())
+}
+
+/// Migrations an unlocked database is missing, without applying them
+pub fn plan(conn: &Connection, set: &MigrationSet) -> Result<MigrationPlan> {
+    plan_from(schema_version(conn)?, set)
+}
+
+/// Report pending migrations for the database at `db_path` without changing it
+///
+/// A missing database is reported as version 0 with everything pending.
+pub fn dry_run<P: AsRef<Path>>(
+    db_path: P,
+    key: &[u8; 32],
+    set: &MigrationSet,
+) -> Result<MigrationPlan> {
+    let db_path = db_path.as_ref();
+    if !db_path.exists() {
+        return plan_from(0, set);
+    }
+
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    apply_raw_key(&conn, key)?;
+    verify_unlocked(&conn)?;
+    plan(&conn, set)
+}
+
+fn plan_from(current_version: u32, set: &MigrationSet) -> Result<MigrationPlan> {
+    let target_version = set.latest_version();
+    if current_version > target_version {
+        return Err(CoreError::SchemaTooNew {
+            database: set.database,
+            found: current_version,
+            supported: target_version,
+        });
+    }
+
+    Ok(MigrationPlan {
+        database: set.database,
+        current_version,
+        target_version,
+        pending: set
+            .migrations
+            .iter()
+            .filter(|m| m.version > current_version)
+            .collect(),
+    })
+}
+
+/// `ALTER TABLE … ADD COLUMN` only when the column is not already there
+fn add_column_if_missing(
+    tx: &Transaction<'_>,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<()> {
+    let exists: bool = tx.query_row(
+        "SELECT EXISTS (SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        tx.execute_batch(&format!(
+            "ALTER TABLE {table} ADD COLUMN {column} {definition};"
+        ))?;
+    }
+    Ok(())
+}
+
+// ──────────────────────────────────────────────────────────────
+// vault.db
+// ──────────────────────────────────────────────────────────────
+
+fn vault_v1_keys(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS keys (
+            file_id TEXT PRIMARY KEY,
+            password_blob BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            rotated_at TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS key_history (
+            file_id TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            password_blob BLOB NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            superseded_at TEXT,
+            note TEXT,
+            PRIMARY KEY (file_id, version)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_key_history_file_id ON key_history(file_id);
+
+        -- Keep `keys` table always in sync with latest version
+        CREATE TRIGGER IF NOT EXISTS sync_current_key_after_insert
+        AFTER INSERT ON key_history
+        WHEN NEW.superseded_at IS NULL
+        BEGIN
+            INSERT OR REPLACE INTO keys (file_id, password_blob, created_at, rotated_at)
+            VALUES (NEW.file_id, NEW.password_blob, NEW.created_at, NULL);
+        END;
+        "#,
+    )
+}
+
+fn vault_v2_rotation_journal(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        -- Pending rotations: written before the re-encrypted file replaces the
+        -- original, removed in the same transaction that commits the new key
+        CREATE TABLE IF NOT EXISTS rotation_journal (
+            file_id TEXT PRIMARY KEY,
+            encrypted_path TEXT NOT NULL,
+            staged_path TEXT NOT NULL,
+            from_version INTEGER NOT NULL,
+            new_password_blob BLOB NOT NULL,
+            note TEXT,
+            started_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        "#,
+    )
+}
+
+fn vault_v3_audit_log(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        -- Security-relevant vault events (rekeys, passphrase changes, recoveries)
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            event TEXT NOT NULL,
+            detail TEXT,
+            at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        "#,
+    )
+}
+
+fn vault_v4_file_id_secret(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS vault_meta (
+            name TEXT PRIMARY KEY,
+            value BLOB NOT NULL
+        );
+
+        -- Old → new ids while `file_id::upgrade_file_ids` rewrites index.db
+        CREATE TABLE IF NOT EXISTS file_id_remap (
+            old_id TEXT PRIMARY KEY,
+            new_id TEXT NOT NULL,
+            new_content_hash TEXT NOT NULL
+        );
+        "#,
+    )?;
+
+    let secret: [u8; 32] = rand::random();
+    tx.execute(
+        "INSERT OR IGNORE INTO vault_meta (name, value) VALUES (?1, ?2)",
+        rusqlite::params![FILE_ID_SECRET, &secret[..]],
+    )?;
+
+    // A vault without keys has no ids to upgrade: keyed from the start
+    let has_keys: bool =
+        tx.query_row("SELECT EXISTS (SELECT 1 FROM keys)", [], |row| row.get(0))?;
+    if !has_keys {
+        tx.execute(
+            "INSERT OR IGNORE INTO vault_meta (name, value) VALUES (?1, ?2)",
+            rusqlite::params![FILE_ID_SCHEME, KEYED_SCHEME],
+        )?;
+    }
+    Ok(())
+}
+
+// ──────────────────────────────────────────────────────────────
+// index.db
+// ──────────────────────────────────────────────────────────────
+
+fn index_v1_files(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS files (
+            file_id TEXT PRIMARY KEY,
+            content_hash TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            current_path TEXT NOT NULL,
+            plaintext_size INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            rotated_at TEXT,
+            encryption_algo TEXT NOT NULL DEFAULT 'AESCryptV3',
+            filename_style TEXT NOT NULL DEFAULT 'human',
+            id_length INTEGER NOT NULL DEFAULT 20,
+            salted_with_path INTEGER NOT NULL DEFAULT 0,
+            tags TEXT,
+            note TEXT
+        );
+        "#,
+    )?;
+
+    // Pre-versioning databases may predate encryption_algo
+    add_column_if_missing(
+        tx,
+        "files",
+        "encryption_algo",
+        "TEXT NOT NULL DEFAULT 'AESCryptV3'",
+    )?;
+
+    tx.execute_batch(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_content_hash ON files(content_hash);
+        CREATE INDEX IF NOT EXISTS idx_display_name ON files(display_name);
+        CREATE INDEX IF NOT EXISTS idx_current_path ON files(current_path);
+        CREATE INDEX IF NOT EXISTS idx_encryption_algo ON files(encryption_algo);
+        "#,
+    )
+}
+
+fn index_v2_known_password_hex(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    // decrypt_batch used to add this at runtime, so it may already exist
+    add_column_if_missing(tx, "files", "known_password_hex", "TEXT")
+}
+
+fn index_v3_trash(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        -- Soft-deleted rows of `files`, restorable until hard-deleted or shredded
+        CREATE TABLE IF NOT EXISTS trash (
+            file_id TEXT PRIMARY KEY,
+            content_hash TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            current_path TEXT NOT NULL,
+            plaintext_size INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            rotated_at TEXT,
+            encryption_algo TEXT NOT NULL DEFAULT 'AESCryptV3',
+            filename_style TEXT NOT NULL DEFAULT 'human',
+            id_length INTEGER NOT NULL DEFAULT 20,
+            salted_with_path INTEGER NOT NULL DEFAULT 0,
+            tags TEXT,
+            note TEXT,
+            known_password_hex TEXT,
+            deleted_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        "#,
+    )
+}
+
+fn index_v4_tags(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE
+        );
+
+        -- Not foreign-keyed to `files`: rows follow a file into `trash` and back
+        CREATE TABLE IF NOT EXISTS file_tags (
+            file_id TEXT NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (file_id, tag_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_file_tags_tag_id ON file_tags(tag_id);
+        "#,
+    )?;
+
+    // Split the old comma-separated column of both tables into rows
+    for table in ["files", "trash"] {
+        tx.execute_batch(&format!(
+            r#"
+            WITH RECURSIVE split(file_id, tag, rest) AS (
+                SELECT file_id, '', tags || ',' FROM {table} WHERE tags IS NOT NULL
+                UNION ALL
+                SELECT file_id,
+                       trim(substr(rest, 1, instr(rest, ',') - 1)),
+                       substr(rest, instr(rest, ',') + 1)
+                FROM split WHERE rest <> ''
+            )
+            INSERT OR IGNORE INTO tags (name)
+                SELECT tag FROM split WHERE tag <> '';
+
+            WITH RECURSIVE split(file_id, tag, rest) AS (
+                SELECT file_id, '', tags || ',' FROM {table} WHERE tags IS NOT NULL
+                UNION ALL
+                SELECT file_id,
+                       trim(substr(rest, 1, instr(rest, ',') - 1)),
+                       substr(rest, instr(rest, ',') + 1)
+                FROM split WHERE rest <> ''
+            )
+            INSERT OR IGNORE INTO file_tags (file_id, tag_id)
+                SELECT split.file_id, tags.id FROM split JOIN tags ON tags.name = split.tag
+                WHERE split.tag <> '';
+
+            UPDATE {table} SET tags = NULL;
+            "#
+        ))?;
+    }
+    Ok(())
+}
+
+fn index_v5_file_refs(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        -- Every source a stored ciphertext was added from; the ciphertext is
+        -- only deleted with its last reference. '' = unknown (added earlier)
+        CREATE TABLE IF NOT EXISTS file_refs (
+            file_id TEXT NOT NULL,
+            source_path TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            added_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (file_id, source_path)
+        );
+
+        INSERT OR IGNORE INTO file_refs (file_id, source_path, display_name, added_at)
+            SELECT file_id, '', display_name, created_at FROM files
+            UNION ALL
+            SELECT file_id, '', display_name, created_at FROM trash;
+        "#,
+    )
+}
+
+fn index_v6_import_journal(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    // Path inside the imported directory, '/'-separated
+    add_column_if_missing(tx, "file_refs", "relative_path", "TEXT")?;
+
+    tx.execute_batch(
+        r#"
+        -- Files a directory import has finished with, so an interrupted
+        -- import resumes where it stopped; cleared when the import completes
+        CREATE TABLE IF NOT EXISTS import_journal (
+            root TEXT NOT NULL,
+            relative_path TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            modified_ns INTEGER,
+            outcome TEXT NOT NULL,
+            file_id TEXT NOT NULL,
+            PRIMARY KEY (root, relative_path)
+        );
+        "#,
+    )
+}
+
+fn index_v7_relayout_journal(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        -- A rename relayout is about to make, written before the file moves
+        -- so a re-run can finish it; cleared with the row update
+        CREATE TABLE IF NOT EXISTS relayout_journal (
+            file_id TEXT PRIMARY KEY,
+            source_table TEXT NOT NULL,
+            from_path TEXT NOT NULL,
+            target_path TEXT NOT NULL,
+            filename_style TEXT NOT NULL,
+            id_length INTEGER NOT NULL
+        );
+        "#,
+    )
+}
@@ -1,4 +1,8 @@
 pub mod index_db_conn;
 pub mod index_db_ops;
+pub mod keying;
+pub mod migrations;
+pub mod query;
+pub mod tag_ops;
 pub mod vault_db_conn;
 pub mod vault_db_ops;
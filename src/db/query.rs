@@ -0,0 +1,419 @@
+// src/db/query.rs
+//! Typed queries over the index
+//!
+//! [`FileQuery`] builds the SQL so callers never hand-roll it:
+//!
+//! ```no_run
+//! # fn demo(vault: &encrypted_file_vault::Vault) -> encrypted_file_vault::Result<()> {
+//! use encrypted_file_vault::db::query::{FileQuery, SortKey};
+//!
+//! let pdfs = FileQuery::new()
+//!     .name_glob("*.pdf")
+//!     .min_size(1024)
+//!     .order_by(SortKey::PlaintextSize)
+//!     .descending();
+//!
+//! for record in vault.query(&pdfs) {
+//!     let record = record?;
+//!     println!("{} {}", record.display_name, record.created_at);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Results are fetched lazily, one page (`page_size` rows) at a time.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, Row};
+
+use crate::db::tag_ops::TagExpr;
+use crate::enums::EncryptionAlgorithm;
+use crate::error::CoreError;
+use crate::Result;
+
+/// Rows fetched per round-trip when iterating
+pub const DEFAULT_PAGE_SIZE: usize = 256;
+
+/// `datetime('now')` format used by every timestamp column
+const SQL_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// One `files` row with every column decoded
+#[derive(Debug, Clone)]
+pub struct FileRecord {
+    pub file_id: String,
+    pub content_hash: String,
+    pub display_name: String,
+    pub current_path: PathBuf,
+    pub plaintext_size: u64,
+    pub created_at: DateTime<Utc>,
+    pub rotated_at: Option<DateTime<Utc>>,
+    pub encryption_algo: EncryptionAlgorithm,
+    pub filename_style: String,
+    pub id_length_hex: u64,
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+}
+
+/// Separator for the aggregated tag list (tag names cannot contain control characters)
+const TAG_SEPARATOR: char = '\u{1f}';
+
+const RECORD_COLUMNS: &str = "file_id, content_hash, display_name, current_path, plaintext_size, \
+     created_at, rotated_at, encryption_algo, filename_style, id_length, \
+     (SELECT group_concat(t.name, char(31)) FROM file_tags ft JOIN tags t ON t.id = ft.tag_id \
+      WHERE ft.file_id = files.file_id), \
+     note";
+
+/// Column to sort by; `file_id` always breaks ties so pages are stable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    #[default]
+    DisplayName,
+    CreatedAt,
+    RotatedAt,
+    PlaintextSize,
+    FileId,
+}
+
+impl SortKey {
+    fn column(self) -> &'static str {
+        match self {
+            SortKey::DisplayName => "display_name",
+            SortKey::CreatedAt => "created_at",
+            SortKey::RotatedAt => "rotated_at",
+            SortKey::PlaintextSize => "plaintext_size",
+            SortKey::FileId => "file_id",
+        }
+    }
+}
+
+/// Filters, ordering and paging for [`FileQuery::run`]
+#[derive(Debug, Clone)]
+pub struct FileQuery {
+    name_glob: Option<String>,
+    tags: Vec<TagExpr>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+    rotated_after: Option<DateTime<Utc>>,
+    rotated_before: Option<DateTime<Utc>>,
+    encryption_algo: Option<EncryptionAlgorithm>,
+    filename_style: Option<String>,
+    sort: SortKey,
+    descending: bool,
+    offset: u64,
+    limit: Option<u64>,
+    page_size: usize,
+}
+
+impl Default for FileQuery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileQuery {
+    /// Every file, by display name
+    pub fn new() -> Self {
+        Self {
+            name_glob: None,
+            tags: Vec::new(),
+            min_size: None,
+            max_size: None,
+            created_after: None,
+            created_before: None,
+            rotated_after: None,
+            rotated_before: None,
+            encryption_algo: None,
+            filename_style: None,
+            sort: SortKey::default(),
+            descending: false,
+            offset: 0,
+            limit: None,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// Display name matches a glob (`*`, `?`, `[...]`; case-sensitive)
+    pub fn name_glob(mut self, pattern: impl Into<String>) -> Self {
+        self.name_glob = Some(pattern.into());
+        self
+    }
+
+    /// Require a tag; repeat for several (all must match)
+    pub fn tag(self, tag: impl Into<String>) -> Self {
+        self.tags(TagExpr::tag(tag))
+    }
+
+    /// Require a boolean tag expression; combined with other tag filters by AND
+    pub fn tags(mut self, expr: TagExpr) -> Self {
+        self.tags.push(expr);
+        self
+    }
+
+    /// Plaintext size at least `bytes`
+    pub fn min_size(mut self, bytes: u64) -> Self {
+        self.min_size = Some(bytes);
+        self
+    }
+
+    /// Plaintext size at most `bytes`
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Added at or after `at`
+    pub fn created_after(mut self, at: DateTime<Utc>) -> Self {
+        self.created_after = Some(at);
+        self
+    }
+
+    /// Added strictly before `at`
+    pub fn created_before(mut self, at: DateTime<Utc>) -> Self {
+        self.created_before = Some(at);
+        self
+    }
+
+    /// Last rotated at or after `at` (never-rotated files are excluded)
+    pub fn rotated_after(mut self, at: DateTime<Utc>) -> Self {
+        self.rotated_after = Some(at);
+        self
+    }
+
+    /// Last rotated strictly before `at` (never-rotated files are excluded)
+    pub fn rotated_before(mut self, at: DateTime<Utc>) -> Self {
+        self.rotated_before = Some(at);
+        self
+    }
+
+    pub fn encryption_algo(mut self, algo: EncryptionAlgorithm) -> Self {
+        self.encryption_algo = Some(algo);
+        self
+    }
+
+    pub fn filename_style(mut self, style: impl Into<String>) -> Self {
+        self.filename_style = Some(style.into());
+        self
+    }
+
+    /// Sort column (ascending unless [`FileQuery::descending`])
+    pub fn order_by(mut self, key: SortKey) -> Self {
+        self.sort = key;
+        self
+    }
+
+    pub fn descending(mut self) -> Self {
+        self.descending = true;
+        self
+    }
+
+    /// Skip the first `rows` matches
+    pub fn offset(mut self, rows: u64) -> Self {
+        self.offset = rows;
+        self
+    }
+
+    /// Return at most `rows` matches
+    pub fn limit(mut self, rows: u64) -> Self {
+        self.limit = Some(rows);
+        self
+    }
+
+    /// Zero-based page `page` of `per_page` rows
+    pub fn page(self, page: u64, per_page: u64) -> Self {
+        self.offset(page * per_page).limit(per_page)
+    }
+
+    /// Rows fetched per round-trip while iterating (default 256)
+    pub fn page_size(mut self, rows: usize) -> Self {
+        self.page_size = rows.max(1);
+        self
+    }
+
+    /// Lazily iterate the matches
+    pub fn run<'c>(&self, conn: &'c Connection) -> FileRecordIter<'c> {
+        FileRecordIter {
+            conn,
+            query: self.clone(),
+            buffer: VecDeque::new(),
+            next_offset: self.offset,
+            remaining: self.limit,
+            exhausted: false,
+        }
+    }
+
+    /// Collect every match
+    pub fn fetch_all(&self, conn: &Connection) -> Result<Vec<FileRecord>> {
+        self.run(conn).collect()
+    }
+
+    /// Number of matches (ignores offset / limit)
+    pub fn count(&self, conn: &Connection) -> Result<u64> {
+        let (filter, params) = self.where_clause();
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM files{filter}"),
+            params_from_iter(params.iter()),
+            |row| row.get(0),
+        )?;
+        Ok(count as u64)
+    }
+
+    fn where_clause(&self) -> (String, Vec<Value>) {
+        let mut clauses = Vec::new();
+        let mut params = Vec::new();
+        let mut push = |clause: &str, value: Value| {
+            params.push(value);
+            clauses.push(clause.replace('?', &format!("?{}", params.len())));
+        };
+
+        if let Some(glob) = &self.name_glob {
+            push("display_name GLOB ?", Value::Text(glob.clone()));
+        }
+        if let Some(min) = self.min_size {
+            push("plaintext_size >= ?", Value::Integer(min as i64));
+        }
+        if let Some(max) = self.max_size {
+            push("plaintext_size <= ?", Value::Integer(max as i64));
+        }
+        if let Some(at) = &self.created_after {
+            push("created_at >= ?", Value::Text(sql_datetime(at)));
+        }
+        if let Some(at) = &self.created_before {
+            push("created_at < ?", Value::Text(sql_datetime(at)));
+        }
+        if let Some(at) = &self.rotated_after {
+            push("rotated_at >= ?", Value::Text(sql_datetime(at)));
+        }
+        if let Some(at) = &self.rotated_before {
+            push("rotated_at < ?", Value::Text(sql_datetime(at)));
+        }
+        if let Some(algo) = self.encryption_algo {
+            push("encryption_algo = ?", Value::Text(algo.as_str().into()));
+        }
+        if let Some(style) = &self.filename_style {
+            push("filename_style = ?", Value::Text(style.clone()));
+        }
+
+        // Tag expressions number their own placeholders
+        for expr in &self.tags {
+            clauses.push(expr.to_sql(&mut params));
+        }
+
+        if clauses.is_empty() {
+            (String::new(), params)
+        } else {
+            (format!(" WHERE {}", clauses.join(" AND ")), params)
+        }
+    }
+
+    fn fetch_page(&self, conn: &Connection, offset: u64, rows: u64) -> Result<Vec<FileRecord>> {
+        let (filter, params) = self.where_clause();
+        let direction = if self.descending { "DESC" } else { "ASC" };
+        let sql = format!(
+            "SELECT {RECORD_COLUMNS} FROM files{filter} \
+             ORDER BY {column} {direction}, file_id {direction} \
+             LIMIT {rows} OFFSET {offset}",
+            column = self.sort.column(),
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(params.iter()))?;
+        let mut page = Vec::new();
+        while let Some(row) = rows.next()? {
+            page.push(record_from_row(row)?);
+        }
+        Ok(page)
+    }
+}
+
+/// Lazy, paginated results of a [`FileQuery`]
+pub struct FileRecordIter<'c> {
+    conn: &'c Connection,
+    query: FileQuery,
+    buffer: VecDeque<FileRecord>,
+    next_offset: u64,
+    remaining: Option<u64>,
+    exhausted: bool,
+}
+
+impl Iterator for FileRecordIter<'_> {
+    type Item = Result<FileRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty() && !self.exhausted {
+            let want = self.query.page_size as u64;
+            let rows = self.remaining.map_or(want, |left| left.min(want));
+            if rows == 0 {
+                self.exhausted = true;
+                return None;
+            }
+
+            match self.query.fetch_page(self.conn, self.next_offset, rows) {
+                Ok(page) => {
+                    let fetched = page.len() as u64;
+                    self.exhausted = fetched < rows;
+                    self.next_offset += fetched;
+                    if let Some(left) = self.remaining.as_mut() {
+                        *left -= fetched;
+                    }
+                    self.buffer.extend(page);
+                }
+                Err(e) => {
+                    self.exhausted = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.buffer.pop_front().map(Ok)
+    }
+}
+
+fn record_from_row(row: &Row<'_>) -> Result<FileRecord> {
+    let created_at: String = row.get(5)?;
+    let rotated_at: Option<String> = row.get(6)?;
+    let algo: String = row.get(7)?;
+    let tags: Option<String> = row.get(10)?;
+
+    Ok(FileRecord {
+        file_id: row.get(0)?,
+        content_hash: row.get(1)?,
+        display_name: row.get(2)?,
+        current_path: PathBuf::from(row.get::<_, String>(3)?),
+        plaintext_size: row.get::<_, i64>(4)? as u64,
+        created_at: parse_sql_datetime(&created_at)?,
+        rotated_at: rotated_at.as_deref().map(parse_sql_datetime).transpose()?,
+        encryption_algo: algo.parse()?,
+        filename_style: row.get(8)?,
+        id_length_hex: row.get::<_, i64>(9)? as u64,
+        tags: split_tags(tags.as_deref()),
+        note: row.get(11)?,
+    })
+}
+
+fn split_tags(tags: Option<&str>) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .unwrap_or_default()
+        .split(TAG_SEPARATOR)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect();
+    tags.sort();
+    tags
+}
+
+fn sql_datetime(at: &DateTime<Utc>) -> String {
+    at.format(SQL_DATETIME_FORMAT).to_string()
+}
+
+/// Parse `datetime('now')` output (UTC); RFC 3339 is accepted too
+fn parse_sql_datetime(value: &str) -> Result<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, SQL_DATETIME_FORMAT)
+        .map(|naive| naive.and_utc())
+        .or_else(|_| DateTime::parse_from_rfc3339(value).map(|dt| dt.with_timezone(&Utc)))
+        .map_err(|_| CoreError::Config(format!("unreadable timestamp in index: {value:?}")))
+}
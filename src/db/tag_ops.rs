@@ -0,0 +1,257 @@
+// src/db/tag_ops.rs
+//! Tags on index entries
+//!
+//! Tags live in `tags` (one row per name, case-insensitive) and `file_tags`
+//! (one row per file/tag pair). A tag is deleted once no file, live or in
+//! the trash, carries it.
+//!
+//! [`TagExpr`] expresses AND/OR/NOT tag queries for [`FileQuery::tags`]:
+//!
+//! ```
+//! use encrypted_file_vault::db::tag_ops::TagExpr;
+//!
+//! // work AND (tax OR invoice) AND NOT draft
+//! let expr = TagExpr::tag("work")
+//!     .and(TagExpr::tag("tax").or(TagExpr::tag("invoice")))
+//!     .and(TagExpr::tag("draft").not());
+//! # let _ = expr;
+//! ```
+//!
+//! [`FileQuery::tags`]: crate::db::query::FileQuery::tags
+
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::CoreError;
+use crate::Result;
+
+/// A tag and how many live files carry it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagCount {
+    pub name: String,
+    pub files: u64,
+}
+
+/// Boolean tag expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagExpr {
+    /// File carries this tag
+    Tag(String),
+    /// Every sub-expression holds
+    All(Vec<TagExpr>),
+    /// At least one sub-expression holds
+    Any(Vec<TagExpr>),
+    /// The sub-expression does not hold
+    Not(Box<TagExpr>),
+}
+
+impl TagExpr {
+    pub fn tag(name: impl Into<String>) -> Self {
+        TagExpr::Tag(name.into())
+    }
+
+    pub fn and(self, other: TagExpr) -> Self {
+        match self {
+            TagExpr::All(mut all) => {
+                all.push(other);
+                TagExpr::All(all)
+            }
+            first => TagExpr::All(vec![first, other]),
+        }
+    }
+
+    pub fn or(self, other: TagExpr) -> Self {
+        match self {
+            TagExpr::Any(mut any) => {
+                any.push(other);
+                TagExpr::Any(any)
+            }
+            first => TagExpr::Any(vec![first, other]),
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        TagExpr::Not(Box::new(self))
+    }
+
+    /// SQL condition over `files.file_id`, appending its parameters to `params`
+    ///
+    /// Placeholders are numbered (`?N`) continuing from `params.len()`.
+    pub(crate) fn to_sql(&self, params: &mut Vec<Value>) -> String {
+        match self {
+            TagExpr::Tag(name) => {
+                params.push(Value::Text(name.trim().to_owned()));
+                format!(
+                    "EXISTS (SELECT 1 FROM file_tags ft JOIN tags t ON t.id = ft.tag_id \
+                     WHERE ft.file_id = files.file_id AND t.name = ?{})",
+                    params.len()
+                )
+            }
+            TagExpr::All(parts) => join_sql(parts, " AND ", "1", params),
+            TagExpr::Any(parts) => join_sql(parts, " OR ", "0", params),
+            TagExpr::Not(inner) => format!("NOT {}", inner.to_sql(params)),
+        }
+    }
+}
+
+fn join_sql(parts: &[TagExpr], op: &str, empty: &str, params: &mut Vec<Value>) -> String {
+    if parts.is_empty() {
+        return empty.to_owned();
+    }
+    let sql: Vec<String> = parts.iter().map(|p| p.to_sql(params)).collect();
+    format!("({})", sql.join(op))
+}
+
+/// Trim a tag name and reject empty names or names with control characters
+pub fn normalize_tag(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().any(char::is_control) {
+        return Err(CoreError::PolicyViolation(format!(
+            "invalid tag name {name:?}"
+        )));
+    }
+    Ok(name)
+}
+
+/// Tag a file; returns how many of `tags` were new to it
+///
+/// Fails with `CoreError::NotFound` if `file_id` is not in the index.
+pub fn add_tags(conn: &Connection, file_id: &str, tags: &[&str]) -> Result<usize> {
+    let names = tags
+        .iter()
+        .map(|t| normalize_tag(t))
+        .collect::<Result<Vec<_>>>()?;
+    require_file(conn, file_id)?;
+
+    let tx = conn.unchecked_transaction()?;
+    let mut added = 0;
+    for name in names {
+        tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [name])?;
+        added += tx.execute(
+            "INSERT OR IGNORE INTO file_tags (file_id, tag_id) \
+             SELECT ?1, id FROM tags WHERE name = ?2",
+            params![file_id, name],
+        )?;
+    }
+    tx.commit()?;
+    Ok(added)
+}
+
+/// Untag a file; returns how many of `tags` it actually carried
+pub fn remove_tags(conn: &Connection, file_id: &str, tags: &[&str]) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut removed = 0;
+    for name in tags {
+        removed += tx.execute(
+            "DELETE FROM file_tags WHERE file_id = ?1 \
+             AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+            params![file_id, name.trim()],
+        )?;
+    }
+    prune_unused_tags(&tx)?;
+    tx.commit()?;
+    Ok(removed)
+}
+
+/// Drop every tag from a file (used when its entry is deleted for good)
+///
+/// Runs no transaction of its own, so it can join the caller's.
+pub fn clear_tags(conn: &Connection, file_id: &str) -> rusqlite::Result<usize> {
+    let removed = conn.execute("DELETE FROM file_tags WHERE file_id = ?1", [file_id])?;
+    prune_unused_tags(conn)?;
+    Ok(removed)
+}
+
+/// Rename a tag everywhere; renaming onto an existing tag merges the two
+///
+/// Fails with `CoreError::TagNotFound` if no tag is called `from`.
+pub fn rename_tag(conn: &Connection, from: &str, to: &str) -> Result<()> {
+    let to = normalize_tag(to)?;
+    let tx = conn.unchecked_transaction()?;
+
+    let from_id: i64 = tx
+        .query_row(
+            "SELECT id FROM tags WHERE name = ?1",
+            [from.trim()],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| CoreError::TagNotFound(from.trim().to_string()))?;
+    let to_id: Option<i64> = tx
+        .query_row("SELECT id FROM tags WHERE name = ?1", [to], |row| {
+            row.get(0)
+        })
+        .optional()?;
+
+    match to_id {
+        // Same tag (possibly a change of case only)
+        Some(id) if id == from_id => {
+            tx.execute("UPDATE tags SET name = ?1 WHERE id = ?2", params![to, id])?;
+        }
+        Some(id) => {
+            tx.execute(
+                "INSERT OR IGNORE INTO file_tags (file_id, tag_id) \
+                 SELECT file_id, ?1 FROM file_tags WHERE tag_id = ?2",
+                params![id, from_id],
+            )?;
+            tx.execute("DELETE FROM file_tags WHERE tag_id = ?1", [from_id])?;
+            tx.execute("DELETE FROM tags WHERE id = ?1", [from_id])?;
+        }
+        None => {
+            tx.execute(
+                "UPDATE tags SET name = ?1 WHERE id = ?2",
+                params![to, from_id],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// A file's tags, sorted by name
+pub fn file_tags(conn: &Connection, file_id: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT t.name FROM file_tags ft JOIN tags t ON t.id = ft.tag_id \
+         WHERE ft.file_id = ?1 ORDER BY t.name",
+    )?;
+    let rows = stmt.query_map([file_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Every tag with the number of live (not trashed) files carrying it
+pub fn list_tags(conn: &Connection) -> rusqlite::Result<Vec<TagCount>> {
+    let mut stmt = conn.prepare(
+        "SELECT t.name, COUNT(f.file_id) FROM tags t \
+         LEFT JOIN file_tags ft ON ft.tag_id = t.id \
+         LEFT JOIN files f ON f.file_id = ft.file_id \
+         GROUP BY t.id ORDER BY t.name",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(TagCount {
+            name: row.get(0)?,
+            files: row.get::<_, i64>(1)? as u64,
+        })
+    })?;
+    rows.collect()
+}
+
+fn require_file(conn: &Connection, file_id: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM files WHERE file_id = ?1)",
+        [file_id],
+        |row| row.get(0),
+    )?;
+    if exists {
+        Ok(())
+    } else {
+        Err(CoreError::NotFound(file_id.to_string()))
+    }
+}
+
+fn prune_unused_tags(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM file_tags)",
+        [],
+    )
+}
@@ -1,72 +1,54 @@
 // src/vault.rs
-use crate::consts::DB_KDF_ITERATIONS;
-use rusqlite::{Connection, Result};
-use std::{env, fs, path::Path};
-
+use crate::aliases::VaultKey32;
+use crate::config::{ConfigField, ConfigLoader};
+use crate::db::keying::{apply_raw_key, parse_key_hex};
+use crate::db::migrations::{self, VAULT_MIGRATIONS};
+use crate::Result;
+use rusqlite::Connection;
+use std::{fs, path::Path};
+
+/// Open the vault DB named by the layered config / `EFV_VAULT_DB` / `EFV_VAULT_KEY`
+///
+/// Keys are 64 hex characters. Prefer [`open_vault_db_at`]
+/// (or [`crate::Vault::open`]) with explicit values.
 pub fn open_vault_db() -> Result<Connection> {
-    let config = crate::config::load();
-
-    // Allow full test isolation via env vars
-    let db_path = env::var("EFV_VAULT_DB").unwrap_or_else(|_| config.paths.vault_db.clone());
-
-    if let Some(parent) = Path::new(&db_path).parent() {
-        let _ = fs::create_dir_all(parent);
-    }
+    let (db_path, key) = configured_vault_db()?;
+    open_vault_db_at(db_path, &key)
+}
 
-    let conn = Connection::open(&db_path)?;
+/// Path and key [`open_vault_db`] would use
+///
+/// The loaded config must pass [`crate::config::Config::validate_for_build`].
+pub fn configured_vault_db() -> Result<(String, VaultKey32)> {
+    let loaded = ConfigLoader::new().load()?;
+    loaded.config.validate_for_build()?;
 
-    let key: &str = if config.features.use_dev_keys {
-        config.keys.vault_key.as_str()
-    } else {
-        Box::leak(
-            std::env::var("EFV_VAULT_KEY")
-                .expect("EFV_VAULT_KEY required")
-                .into_boxed_str(),
-        )
-    };
+    let db_path = loaded.config.paths.vault_db.clone();
+    let key = VaultKey32::new(*parse_key_hex(loaded.key_hex(ConfigField::VaultKey)?)?);
 
-    conn.execute_batch(&format!("PRAGMA key = '{key}';"))?;
-    conn.execute_batch(&format!(
-        r#"
-        PRAGMA cipher_page_size = 4096;
-        PRAGMA kdf_iter = {DB_KDF_ITERATIONS};
-        PRAGMA cipher_hmac_algorithm = HMAC_SHA512;
-        PRAGMA cipher_kdf_algorithm = PBKDF2_HMAC_SHA512;
-        PRAGMA cipher_plaintext_header_size = 0;
+    Ok((db_path, key))
+}
 
-        CREATE TABLE IF NOT EXISTS keys (
-            file_id TEXT PRIMARY KEY,
-            password_blob BLOB NOT NULL,
-            created_at TEXT NOT NULL,
-            rotated_at TEXT
-        );
+/// Open (or create) the vault DB at an explicit path with a raw 256-bit key
+pub fn open_vault_db_at<P: AsRef<Path>>(db_path: P, key: &VaultKey32) -> Result<Connection> {
+    let db_path = db_path.as_ref();
 
-        CREATE TABLE IF NOT EXISTS key_history (
-            file_id TEXT NOT NULL,
-            version INTEGER NOT NULL,
-            password_blob BLOB NOT NULL,
-            created_at TEXT NOT NULL DEFAULT (datetime('now')),
-            superseded_at TEXT,
-            note TEXT,
-            PRIMARY KEY (file_id, version)
-        );
+    if let Some(parent) = db_path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
 
-        CREATE INDEX IF NOT EXISTS idx_key_history_file_id ON key_history(file_id);
+    let mut conn = Connection::open(db_path)?;
+    apply_raw_key(&conn, key.expose_secret())?;
 
-        -- Back-fill history for legacy rows
-        INSERT OR IGNORE INTO key_history (file_id, version, password_blob, created_at)
-        SELECT file_id, 1, password_blob, created_at FROM keys;
+    migrations::migrate(&mut conn, &VAULT_MIGRATIONS)?;
 
-        -- Keep `keys` table always in sync with latest version
-        CREATE TRIGGER IF NOT EXISTS sync_current_key_after_insert
-        AFTER INSERT ON key_history
-        WHEN NEW.superseded_at IS NULL
-        BEGIN
-            INSERT OR REPLACE INTO keys (file_id, password_blob, created_at, rotated_at)
-            VALUES (NEW.file_id, NEW.password_blob, NEW.created_at, NULL);
-        END;
-        "#
-    ))?;
+    // Back-fill history for legacy rows written straight into `keys`
+    // (idempotent, so it runs on every open rather than as a migration)
+    conn.execute(
+        "INSERT OR IGNORE INTO key_history (file_id, version, password_blob, created_at)
+         SELECT file_id, 1, password_blob, created_at FROM keys",
+        [],
+    )?;
 
     Ok(conn)
 }
@@ -1,29 +1,51 @@
 //! Vault database operations and high-level workflows
 
-use std::path::Path;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use crate::aliases::{FilePassword, PlainText};
+use crate::aliases::FilePassword;
+use crate::atomic_write::{rename_durable, rename_durable_noclobber, write_atomic, RenameError};
 use crate::consts::{DEFAULT_FILENAME_STYLE, DEFAULT_ID_LENGTH_HEX};
 use crate::crypto::rotate_key_streaming;
-use crate::db::index_db_ops::{store_file_entry, FileEntry};
+use crate::db::index_db_ops::{
+    add_file_ref, delete_file_entry, file_id_exists, find_by_content_hash, get_file_entry,
+    get_trash_entry, list_file_refs, move_to_trash, remove_file_ref, restore_from_trash,
+    store_file_entry, FileEntry,
+};
+use crate::enums::{DedupPolicy, RemoveMode};
 use crate::error::CoreError;
-use crate::file_ops::encrypt_file;
+use crate::file_id::{self, FileIdHasher};
+use crate::file_ops::{decrypt_stream, encrypt_file_hashed};
 use crate::key_ops::{generate_key, Key};
-use crate::util::blake3_hex;
-use rusqlite::{params, Connection};
+use crate::legacy::upgrade::upgrade_stream_hashed;
+use crate::naming::{self, NamingPolicy};
+use crate::stream::HashingWriter;
+use rusqlite::{params, Connection, Transaction};
 use secure_gate::SecureConversionsExt; // ← FIXED: needed for .to_hex()
 
 use crate::Result;
 
 /// Store a new key blob into key_history (triggers keep keys table in sync)
 pub fn store_key_blob(conn: &mut Connection, file_id: &str, key: &Key) -> rusqlite::Result<()> {
+    store_key_version(conn, file_id, key, None)
+}
+
+/// [`store_key_blob`] with an explicit `key_history` note
+/// (default: "initial" for the first version, "update" after that)
+pub(crate) fn store_key_version(
+    conn: &mut Connection,
+    file_id: &str,
+    key: &Key,
+    note: Option<&str>,
+) -> rusqlite::Result<()> {
     let tx = conn.transaction()?;
     let version: i64 = tx.query_row(
         "SELECT COALESCE(MAX(version), 0) + 1 FROM key_history WHERE file_id = ?1",
         [file_id],
         |row| row.get(0),
     )?;
-    let note = if version == 1 { "initial" } else { "update" };
+    let note = note.unwrap_or(if version == 1 { "initial" } else { "update" });
     tx.execute(
         "INSERT INTO key_history (file_id, version, password_blob, note)
          VALUES (?1, ?2, ?3, ?4)",
@@ -33,7 +55,248 @@ pub fn store_key_blob(conn: &mut Connection, file_id: &str, key: &Key) -> rusqli
     Ok(())
 }
 
+/// Load the current key for a file from the `keys` table
+pub fn load_current_key(conn: &Connection, file_id: &str) -> rusqlite::Result<Key> {
+    let bytes: [u8; 32] = conn.query_row(
+        "SELECT password_blob FROM keys WHERE file_id = ?1",
+        [file_id],
+        |row| row.get(0),
+    )?;
+    Ok(Key::new(bytes))
+}
+
+/// The current key of a file as the AES Crypt password string
+pub fn load_current_password(conn: &Connection, file_id: &str) -> Result<FilePassword> {
+    let key = load_current_key(conn, file_id).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => CoreError::MissingKey(file_id.to_string()),
+        e => CoreError::Sql(e),
+    })?;
+    Ok(FilePassword::new(key.expose_secret().to_hex()))
+}
+
+/// Decrypt a stored file into `writer`; returns the plaintext size
+///
+/// Looks up the current key and path, stream-decrypts, and checks the
+/// BLAKE3 of the output against `content_hash`. On a mismatch the writer has
+/// already received the (bad) bytes — callers writing somewhere durable
+/// should use [`extract_file`], which never leaves them behind.
+pub fn extract_to_writer<W: Write>(
+    vault_conn: &Connection,
+    index_conn: &Connection,
+    file_id: &str,
+    writer: W,
+) -> Result<u64> {
+    let entry = get_file_entry(index_conn, file_id)?
+        .ok_or_else(|| CoreError::NotFound(file_id.to_string()))?;
+    let password = load_current_password(vault_conn, file_id)?;
+
+    let input = File::open(&entry.current_path)?;
+    let mut output = HashingWriter::new(writer);
+    let size = decrypt_stream(input, &mut output, &password)?;
+
+    let actual = FileIdHasher::load(vault_conn)?.content_hash(&output.hash_hex());
+    if actual != entry.content_hash {
+        return Err(CoreError::IntegrityMismatch {
+            file_id: file_id.to_string(),
+            expected: entry.content_hash,
+            actual,
+        });
+    }
+    Ok(size)
+}
+
+/// Decrypt a stored file to `dest` (atomically); returns the plaintext size
+///
+/// `dest` only appears once the content hash has been verified.
+pub fn extract_file<P: AsRef<Path>>(
+    vault_conn: &Connection,
+    index_conn: &Connection,
+    file_id: &str,
+    dest: P,
+) -> Result<u64> {
+    write_atomic(dest, |output| {
+        extract_to_writer(vault_conn, index_conn, file_id, output)
+    })
+}
+
+/// What `remove_file` destroyed (or set aside)
+#[derive(Debug, Clone)]
+pub struct RemovalReport {
+    pub file_id: String,
+    pub mode: RemoveMode,
+    pub display_name: String,
+    /// The entry now sits in `trash` and can be restored
+    pub moved_to_trash: bool,
+    /// Ciphertext file that was deleted (`None` if kept or already gone)
+    pub ciphertext_removed: Option<PathBuf>,
+    /// Index rows deleted from `files` / `trash`
+    pub index_rows_removed: usize,
+    /// `key_history` versions purged (crypto-shred only)
+    pub key_versions_purged: usize,
+    /// Both databases were VACUUMed so purged pages are gone from disk
+    pub vacuumed: bool,
+}
+
+/// Remove a file from the vault — see [`RemoveMode`]
+///
+/// Works on live and soft-deleted (trashed) entries alike. Each destructive
+/// step is idempotent, so re-running after a crash finishes the job:
+///
+/// 1. Crypto-shred: purge `keys`, `key_history` and any journaled rotation
+///    with `secure_delete` on, then VACUUM — the ciphertext is now unreadable.
+/// 2. Delete the ciphertext file.
+/// 3. Delete the index row (from `files` and `trash`).
+///
+/// A hard delete is refused while more than one source references the
+/// ciphertext (see [`remove_reference`]); a crypto-shred destroys it for
+/// every reference.
+pub fn remove_file(
+    vault_conn: &Connection,
+    index_conn: &Connection,
+    file_id: &str,
+    mode: RemoveMode,
+) -> Result<RemovalReport> {
+    let entry = match get_file_entry(index_conn, file_id)? {
+        Some(entry) => entry,
+        None => get_trash_entry(index_conn, file_id)?
+            .ok_or_else(|| CoreError::NotFound(file_id.to_string()))?,
+    };
+
+    let mut report = RemovalReport {
+        file_id: file_id.to_string(),
+        mode,
+        display_name: entry.display_name.clone(),
+        moved_to_trash: false,
+        ciphertext_removed: None,
+        index_rows_removed: 0,
+        key_versions_purged: 0,
+        vacuumed: false,
+    };
+
+    if mode == RemoveMode::SoftDelete {
+        report.moved_to_trash =
+            move_to_trash(index_conn, file_id)? || get_trash_entry(index_conn, file_id)?.is_some();
+        record_audit(vault_conn, "soft-delete", Some(file_id))?;
+        return Ok(report);
+    }
+
+    if mode == RemoveMode::HardDelete {
+        let refs = list_file_refs(index_conn, file_id)?.len();
+        if refs > 1 {
+            return Err(CoreError::PolicyViolation(format!(
+                "{file_id} is still referenced from {refs} sources — remove them with remove_reference, or crypto-shred"
+            )));
+        }
+    }
+
+    if mode == RemoveMode::CryptoShred {
+        report.key_versions_purged = purge_keys(vault_conn, file_id)?;
+    }
+
+    match std::fs::remove_file(&entry.current_path) {
+        Ok(()) => report.ciphertext_removed = Some(entry.current_path.clone()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(CoreError::Io(e)),
+    }
+
+    report.index_rows_removed = delete_file_entry(index_conn, file_id)?;
+
+    if mode == RemoveMode::CryptoShred {
+        // The index may hold the key as known_password_hex
+        index_conn.execute_batch("VACUUM;")?;
+        report.vacuumed = true;
+    }
+
+    let event = match mode {
+        RemoveMode::CryptoShred => "crypto-shred",
+        _ => "hard-delete",
+    };
+    record_audit(vault_conn, event, Some(file_id))?;
+
+    Ok(report)
+}
+
+/// Drop one source reference to a stored file
+///
+/// The ciphertext stays while other references remain (`Ok(None)`); removing
+/// the last one hard-deletes the file and returns its report. A source the
+/// file was never added from is `CoreError::ReferenceNotFound`.
+pub fn remove_reference(
+    vault_conn: &Connection,
+    index_conn: &Connection,
+    file_id: &str,
+    source_path: &str,
+) -> Result<Option<RemovalReport>> {
+    let refs = list_file_refs(index_conn, file_id)?;
+    if !refs.iter().any(|r| r.source_path == source_path) {
+        return Err(CoreError::ReferenceNotFound {
+            file_id: file_id.to_string(),
+            source_path: source_path.to_string(),
+        });
+    }
+
+    if refs.len() == 1 {
+        return remove_file(vault_conn, index_conn, file_id, RemoveMode::HardDelete).map(Some);
+    }
+    remove_file_ref(index_conn, file_id, source_path)?;
+    record_audit(vault_conn, "reference-removed", Some(file_id))?;
+    Ok(None)
+}
+
+/// Delete every stored key version of a file, then VACUUM; returns the
+/// versions purged
+///
+/// `secure_delete` is on for the purge and the VACUUM only; the connection's
+/// previous setting is put back afterwards, on failure too.
+fn purge_keys(vault_conn: &Connection, file_id: &str) -> rusqlite::Result<usize> {
+    let previous: i64 = vault_conn.pragma_query_value(None, "secure_delete", |row| row.get(0))?;
+    vault_conn.pragma_update(None, "secure_delete", true)?;
+
+    let purged = (|| -> rusqlite::Result<usize> {
+        let tx = vault_conn.unchecked_transaction()?;
+        let purged = tx.execute("DELETE FROM key_history WHERE file_id = ?1", [file_id])?;
+        tx.execute("DELETE FROM keys WHERE file_id = ?1", [file_id])?;
+        tx.execute("DELETE FROM rotation_journal WHERE file_id = ?1", [file_id])?;
+        tx.commit()?;
+        vault_conn.execute_batch("VACUUM;")?;
+        Ok(purged)
+    })();
+
+    let restored = vault_conn.pragma_update(None, "secure_delete", previous);
+    let purged = purged?;
+    restored?;
+    Ok(purged)
+}
+
+/// Append an entry to `audit_log`
+pub fn record_audit(conn: &Connection, event: &str, detail: Option<&str>) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO audit_log (event, detail) VALUES (?1, ?2)",
+        params![event, detail],
+    )?;
+    Ok(())
+}
+
+/// Where `rotate_key_in_vault` stages the re-encrypted file before the swap
+pub fn staged_rotation_path(encrypted_path: &Path) -> PathBuf {
+    let mut staged = encrypted_path.as_os_str().to_owned();
+    staged.push(".rotate-pending");
+    PathBuf::from(staged)
+}
+
 /// Full vault-aware key rotation: re-encrypts file + updates key_history atomically
+///
+/// Two-phase and journaled, so the file and `key_history` never disagree:
+///
+/// 1. Re-encrypt into a staged file next to the original (fsynced).
+/// 2. Record the pending key in `rotation_journal` and commit.
+/// 3. Rename the staged file over the original (directory fsynced).
+/// 4. Commit the new `key_history` version and clear the journal in one transaction.
+///
+/// A crash at any point is repaired by [`recover_interrupted_rotations`]. If
+/// the rename in step 3 fails, the journal row is deleted before the staged
+/// file; if it succeeds but the directory fsync fails, the new key is still
+/// committed before the I/O error is returned.
 pub fn rotate_key_in_vault<P: AsRef<Path>>(
     encrypted_path: P,
     vault_conn: &mut Connection,
@@ -43,59 +306,203 @@ pub fn rotate_key_in_vault<P: AsRef<Path>>(
     note: Option<&str>,
 ) -> Result<Key> {
     let path = encrypted_path.as_ref();
-    let temp_path = path.with_extension("tmp-rotate");
+    let path_str = utf8_path(path)?;
+    let staged_path = staged_rotation_path(path);
+    let staged_str = utf8_path(&staged_path)?;
+
+    let current_version = current_key_version(vault_conn, file_id)?;
+    if current_version == 0 {
+        return Err(CoreError::MissingKey(file_id.to_string()));
+    }
 
+    // Phase 1: staged ciphertext under the new key
     let new_key = {
         let input = std::fs::File::open(path)?;
-        let output = std::fs::File::create(&temp_path)?;
-        rotate_key_streaming(input, output, old_password)?
+        write_atomic(&staged_path, |output| {
+            rotate_key_streaming(input, output.try_clone()?, old_password)
+        })?
     };
 
-    std::fs::rename(&temp_path, path)?;
+    // Phase 2: journal the pending key before the original is replaced
+    if let Err(e) = vault_conn.execute(
+        "INSERT INTO rotation_journal
+            (file_id, encrypted_path, staged_path, from_version, new_password_blob, note)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            file_id,
+            path_str,
+            staged_str,
+            current_version,
+            new_key.expose_secret() as &[u8],
+            note.unwrap_or("rotation")
+        ],
+    ) {
+        let _ = std::fs::remove_file(&staged_path);
+        return Err(CoreError::Sql(e));
+    }
 
-    let tx = vault_conn.transaction().map_err(CoreError::Sql)?;
-    let current_version: i64 = tx.query_row(
-        "SELECT COALESCE(MAX(version), 0) FROM key_history WHERE file_id = ?1",
+    // Phase 3: swap — from here on the journal holds the only copy of the new key
+    match rename_durable(&staged_path, path) {
+        Ok(()) => {}
+        Err(RenameError::Rename(e)) => {
+            // The original is untouched and still under the old key. The
+            // journal row goes first: a row whose staged file is gone would
+            // be rolled forward by recovery. A staged file left behind is
+            // swept up by the next recovery.
+            vault_conn.execute("DELETE FROM rotation_journal WHERE file_id = ?1", [file_id])?;
+            let _ = std::fs::remove_file(&staged_path);
+            return Err(CoreError::Io(e));
+        }
+        Err(RenameError::Sync(e)) => {
+            // Only the directory fsync failed: the file is already under the new key
+            finish_rotation(vault_conn, index_conn, file_id)?;
+            return Err(CoreError::Io(e));
+        }
+    }
+
+    // Phase 4: promote the journaled key into key_history
+    finish_rotation(vault_conn, index_conn, file_id)?;
+
+    Ok(new_key)
+}
+
+/// What startup recovery did with one interrupted rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryOutcome {
+    /// The swap never happened — staged file removed, old key still current
+    RolledBack,
+    /// The swap happened — journaled key committed to `key_history`
+    RolledForward,
+    /// A staged file with no journal row (crash before the journal commit)
+    /// was deleted
+    StagedFileRemoved,
+}
+
+#[derive(Debug, Clone)]
+pub struct RotationRecovery {
+    pub file_id: String,
+    pub outcome: RecoveryOutcome,
+}
+
+/// Finish or undo every rotation left in `rotation_journal` by a crash
+///
+/// Run this on startup, before any other vault operation. The staged file is
+/// the tie-breaker: if it still exists the rename never happened and the
+/// rotation is rolled back; if it is gone the original now holds the new
+/// ciphertext and the journaled key is committed. Staged files of indexed
+/// or trashed entries that have no journal row left are deleted.
+pub fn recover_interrupted_rotations(
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+) -> Result<Vec<RotationRecovery>> {
+    let pending: Vec<(String, String)> = {
+        let mut stmt = vault_conn.prepare("SELECT file_id, staged_path FROM rotation_journal")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    let mut recovered = Vec::with_capacity(pending.len());
+
+    for (file_id, staged_path) in pending {
+        let staged_path = Path::new(&staged_path);
+
+        let outcome = if staged_path.exists() {
+            // Journal row first, so a crash here cannot turn into a roll-forward
+            vault_conn.execute(
+                "DELETE FROM rotation_journal WHERE file_id = ?1",
+                [&file_id],
+            )?;
+            std::fs::remove_file(staged_path)?;
+            RecoveryOutcome::RolledBack
+        } else {
+            finish_rotation(vault_conn, index_conn, &file_id)?;
+            RecoveryOutcome::RolledForward
+        };
+
+        recovered.push(RotationRecovery { file_id, outcome });
+    }
+
+    // Every journaled rotation is settled, so any staged file left is an orphan
+    let stored: Vec<(String, String)> = {
+        let mut stmt = index_conn.prepare(
+            "SELECT file_id, current_path FROM files
+             UNION ALL SELECT file_id, current_path FROM trash",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    for (file_id, current_path) in stored {
+        let staged_path = staged_rotation_path(Path::new(&current_path));
+        if staged_path.is_file() {
+            std::fs::remove_file(&staged_path)?;
+            recovered.push(RotationRecovery {
+                file_id,
+                outcome: RecoveryOutcome::StagedFileRemoved,
+            });
+        }
+    }
+
+    Ok(recovered)
+}
+
+/// Commit the journaled key and stamp the file's `rotated_at`
+fn finish_rotation(
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+    file_id: &str,
+) -> Result<()> {
+    let tx = vault_conn.transaction()?;
+    commit_journaled_rotation(&tx, file_id)?;
+    tx.commit()?;
+    index_conn.execute(
+        "UPDATE files SET rotated_at = datetime('now') WHERE file_id = ?1",
         [file_id],
-        |row| row.get(0),
     )?;
-    if current_version == 0 {
-        return Err(CoreError::Sql(rusqlite::Error::QueryReturnedNoRows));
-    }
-    let new_version = current_version + 1;
+    Ok(())
+}
 
+/// Supersede the current key, insert the journaled one, clear the journal row
+///
+/// The key bytes are copied SQL-side and never pass through Rust memory.
+fn commit_journaled_rotation(tx: &Transaction<'_>, file_id: &str) -> rusqlite::Result<()> {
     tx.execute(
         "UPDATE key_history SET superseded_at = datetime('now')
-         WHERE file_id = ?1 AND version = ?2",
-        params![file_id, current_version],
-    )
-    .map_err(CoreError::Sql)?;
-
+         WHERE file_id = ?1
+           AND version = (SELECT from_version FROM rotation_journal WHERE file_id = ?1)",
+        [file_id],
+    )?;
     tx.execute(
         "INSERT INTO key_history (file_id, version, password_blob, note)
-         VALUES (?1, ?2, ?3, ?4)",
-        params![
-            file_id,
-            new_version,
-            new_key.expose_secret() as &[u8],
-            note.unwrap_or("rotation")
-        ],
-    )
-    .map_err(CoreError::Sql)?;
-
-    tx.commit().map_err(CoreError::Sql)?;
+         SELECT file_id, from_version + 1, new_password_blob, note
+         FROM rotation_journal WHERE file_id = ?1",
+        [file_id],
+    )?;
+    tx.execute("DELETE FROM rotation_journal WHERE file_id = ?1", [file_id])?;
+    Ok(())
+}
 
-    index_conn
-        .execute(
-            "UPDATE files SET rotated_at = datetime('now') WHERE file_id = ?1",
-            [file_id],
-        )
-        .map_err(CoreError::Sql)?;
+fn current_key_version(conn: &Connection, file_id: &str) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM key_history WHERE file_id = ?1",
+        [file_id],
+        |row| row.get(0),
+    )
+}
 
-    Ok(new_key)
+fn utf8_path(path: &Path) -> Result<&str> {
+    path.to_str()
+        .ok_or_else(|| CoreError::InvalidPath(path.to_path_buf()))
 }
 
 /// Add a new file to the vault: encrypt, store key, index metadata
+///
+/// The plaintext is streamed once — hashed, counted and encrypted together —
+/// so it never sits in memory as a whole. It is encrypted to a staging file
+/// next to `encrypted_path` and only moved there once it is known to be new
+/// content; if the vault already holds it, the existing entry is returned
+/// with the source recorded as another reference
+/// ([`DedupPolicy::ReuseExisting`]) and `encrypted_path` is not written.
+/// An existing file at `encrypted_path` is never replaced.
 pub fn add_file<P: AsRef<Path>>(
     plaintext_path: P,
     encrypted_path: P,
@@ -104,32 +511,376 @@ pub fn add_file<P: AsRef<Path>>(
     filename_style: Option<&str>,
     id_length_hex: Option<u64>,
 ) -> Result<FileEntry> {
-    let plaintext = PlainText::new(std::fs::read(plaintext_path.as_ref())?);
-    let key = generate_key();
-    let password = FilePassword::new(key.expose_secret().to_hex());
+    add_file_with_dedup(
+        plaintext_path.as_ref(),
+        encrypted_path.as_ref(),
+        vault_conn,
+        index_conn,
+        filename_style,
+        id_length_hex,
+        DedupPolicy::default(),
+    )
+}
+
+/// [`add_file`] with an explicit [`DedupPolicy`]
+pub fn add_file_with_dedup(
+    plaintext_path: &Path,
+    encrypted_path: &Path,
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+    filename_style: Option<&str>,
+    id_length_hex: Option<u64>,
+    dedup: DedupPolicy,
+) -> Result<FileEntry> {
+    let staging_dir = encrypted_path.parent().unwrap_or(Path::new(""));
+    let placement = Placement {
+        path: encrypted_path.to_path_buf(),
+        filename_style: filename_style.unwrap_or(DEFAULT_FILENAME_STYLE).to_string(),
+        id_length_hex: id_length_hex.unwrap_or(DEFAULT_ID_LENGTH_HEX as u64),
+    };
+    ingest(
+        Incoming::plaintext(plaintext_path, false, dedup)?,
+        staging_dir,
+        vault_conn,
+        index_conn,
+        encrypt_to_staging(plaintext_path),
+        |_, _, _| Ok(placement),
+    )
+}
+
+/// How [`store_file`] names, identifies and deduplicates new files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoreOptions {
+    pub naming: NamingPolicy,
+    /// Salt the file id with the canonical plaintext path, so identical
+    /// files added from different places get distinct ids
+    pub salt_with_path: bool,
+    pub dedup: DedupPolicy,
+}
+
+/// Add a file to the vault under a generated name
+///
+/// Like [`add_file`], but the ciphertext goes into `storage_dir` under a
+/// name from [`crate::naming`], and `options.dedup` decides what happens
+/// to content the vault already holds.
+pub fn store_file<P: AsRef<Path>, D: AsRef<Path>>(
+    plaintext_path: P,
+    storage_dir: D,
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+    options: &StoreOptions,
+) -> Result<FileEntry> {
+    let plaintext_path = plaintext_path.as_ref();
+    let storage_dir = storage_dir.as_ref();
+    ingest(
+        Incoming::plaintext(plaintext_path, options.salt_with_path, options.dedup)?,
+        storage_dir,
+        vault_conn,
+        index_conn,
+        encrypt_to_staging(plaintext_path),
+        named_in(storage_dir, &options.naming),
+    )
+}
 
-    encrypt_file(plaintext_path.as_ref(), encrypted_path.as_ref(), &password)?;
+/// Bring an AES Crypt file (legacy v0–v2, or v3 under a user password)
+/// into the vault under a fresh key
+///
+/// Each of `passwords` is tried in turn; if none works, the last one's
+/// error is returned (`CoreError::WrongPassword` for an empty list). The file is decrypted and re-encrypted in
+/// one streaming pass that also hashes the plaintext, then named and
+/// deduplicated like [`store_file`]. Its first `key_history` version is
+/// noted "legacy-upgrade"; the display name drops a trailing `.aes`. The
+/// original file is not touched.
+pub fn store_legacy_file<P: AsRef<Path>, D: AsRef<Path>>(
+    legacy_path: P,
+    storage_dir: D,
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+    options: &StoreOptions,
+    passwords: &[FilePassword],
+) -> Result<FileEntry> {
+    let legacy_path = legacy_path.as_ref();
+    let storage_dir = storage_dir.as_ref();
 
-    let file_id = blake3_hex(plaintext.expose_secret());
+    let mut incoming = Incoming::plaintext(legacy_path, options.salt_with_path, options.dedup)?;
+    if let Some(stem) = incoming.display_name.strip_suffix(".aes") {
+        if !stem.is_empty() {
+            incoming.display_name = stem.to_string();
+        }
+    }
+    incoming.key_note = Some("legacy-upgrade");
 
-    store_key_blob(vault_conn, &file_id, &key)?;
+    // Every candidate is tried: a wrong password is not always reported as
+    // one, so any failure moves on and the last one is returned
+    let upgrade_to_staging = |staging: &Path| {
+        let mut last_error = CoreError::WrongPassword;
+        for password in passwords {
+            let input = File::open(legacy_path)?;
+            match write_atomic(staging, |output| {
+                upgrade_stream_hashed(input, output, password)
+            }) {
+                Ok((key, plaintext_size, plaintext_hash)) => {
+                    return Ok(Staged {
+                        key,
+                        plaintext_size,
+                        plaintext_hash,
+                    })
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    };
 
-    let entry = FileEntry {
-        file_id: file_id.clone(),
-        content_hash: file_id,
-        display_name: plaintext_path
-            .as_ref()
+    ingest(
+        incoming,
+        storage_dir,
+        vault_conn,
+        index_conn,
+        upgrade_to_staging,
+        named_in(storage_dir, &options.naming),
+    )
+}
+
+/// Placement under a generated name in `storage_dir`
+fn named_in<'a>(
+    storage_dir: &'a Path,
+    policy: &'a NamingPolicy,
+) -> impl FnOnce(&Connection, &str, &str) -> Result<Placement> + 'a {
+    move |index_conn, file_id, display_name| {
+        let name = naming::allocate_name(index_conn, storage_dir, display_name, file_id, policy)?;
+        Ok(Placement {
+            path: name.path,
+            filename_style: policy.style.as_str().to_string(),
+            id_length_hex: name.id_length_hex,
+        })
+    }
+}
+
+/// A file on its way into the vault
+struct Incoming<'a> {
+    /// Where it is read from; recorded as its reference
+    source: &'a Path,
+    display_name: String,
+    salt_with_path: bool,
+    dedup: DedupPolicy,
+    /// `key_history` note for its first key (default "initial")
+    key_note: Option<&'a str>,
+}
+
+impl<'a> Incoming<'a> {
+    fn plaintext(source: &'a Path, salt_with_path: bool, dedup: DedupPolicy) -> Result<Self> {
+        let display_name = source
             .file_name()
-            .unwrap()
+            .ok_or_else(|| CoreError::InvalidPath(source.to_path_buf()))?
             .to_string_lossy()
-            .to_string(),
-        current_path: encrypted_path.as_ref().to_path_buf(),
-        plaintext_size: plaintext.expose_secret().len() as u64,
-        filename_style: filename_style.unwrap_or(DEFAULT_FILENAME_STYLE).to_string(),
-        id_length_hex: id_length_hex.unwrap_or(DEFAULT_ID_LENGTH_HEX as u64),
+            .to_string();
+        Ok(Self {
+            source,
+            display_name,
+            salt_with_path,
+            dedup,
+            key_note: None,
+        })
+    }
+}
+
+/// The ciphertext [`ingest`] had written to its staging file
+struct Staged {
+    key: Key,
+    plaintext_size: u64,
+    plaintext_hash: String,
+}
+
+/// Encrypt a plaintext under a fresh key, hashing it in the same pass
+fn encrypt_to_staging(plaintext_path: &Path) -> impl FnOnce(&Path) -> Result<Staged> + '_ {
+    move |staging| {
+        let key = generate_key();
+        let password = FilePassword::new(key.expose_secret().to_hex());
+        let (plaintext_size, plaintext_hash) =
+            encrypt_file_hashed(plaintext_path, staging, &password)?;
+        Ok(Staged {
+            key,
+            plaintext_size,
+            plaintext_hash,
+        })
+    }
+}
+
+/// Where a new ciphertext ends up and the naming metadata recorded for it
+struct Placement {
+    path: PathBuf,
+    filename_style: String,
+    id_length_hex: u64,
+}
+
+/// Write the ciphertext to a staging file in `staging_dir` with `stage`,
+/// apply the dedup policy, then store the key, place the file where `place`
+/// says (never over an existing one) and index it in one transaction
+fn ingest<S, F>(
+    incoming: Incoming<'_>,
+    staging_dir: &Path,
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+    stage: S,
+    place: F,
+) -> Result<FileEntry>
+where
+    S: FnOnce(&Path) -> Result<Staged>,
+    F: FnOnce(&Connection, &str, &str) -> Result<Placement>,
+{
+    let Incoming {
+        source,
+        display_name,
+        salt_with_path,
+        dedup,
+        key_note,
+    } = incoming;
+    let source_path = file_id::salt_path(source)?;
+    let hasher = FileIdHasher::load(vault_conn)?;
+
+    if !staging_dir.as_os_str().is_empty() {
+        std::fs::create_dir_all(staging_dir)?;
+    }
+    let staging = staging_dir.join(format!(
+        ".staging-{}.aes",
+        hex::encode(rand::random::<[u8; 8]>())
+    ));
+
+    let Staged {
+        key,
+        plaintext_size,
+        plaintext_hash,
+    } = stage(&staging)?;
+
+    let discard_staging = || {
+        let _ = std::fs::remove_file(&staging);
+    };
+
+    let decision = decide_placement(
+        index_conn,
+        &hasher,
+        &plaintext_hash,
+        &source_path,
+        &display_name,
+        salt_with_path,
+        dedup,
+    )
+    .inspect_err(|_| discard_staging())?;
+
+    let (file_id, salt) = match decision {
+        Decision::Existing(existing_id) => {
+            discard_staging();
+            return reuse_existing(index_conn, &existing_id, &source_path, &display_name);
+        }
+        Decision::New { file_id, salt } => (file_id, salt),
+    };
+
+    let placement =
+        place(index_conn, &file_id, &display_name).inspect_err(|_| discard_staging())?;
+    let content_hash = hasher.content_hash(&plaintext_hash);
+
+    // The key goes in before the ciphertext is placed, so a placed file
+    // always has its key; any later failure takes both back out
+    store_key_version(vault_conn, &file_id, &key, key_note).inspect_err(|_| discard_staging())?;
+    if let Err(e) = rename_durable_noclobber(&staging, &placement.path) {
+        if matches!(e, RenameError::Sync(_)) {
+            let _ = std::fs::remove_file(&placement.path);
+        }
+        discard_staging();
+        let _ = purge_keys(vault_conn, &file_id);
+        return Err(e.into());
+    }
+
+    let entry = FileEntry {
+        file_id,
+        content_hash,
+        display_name,
+        current_path: placement.path,
+        plaintext_size,
+        filename_style: placement.filename_style,
+        id_length_hex: placement.id_length_hex,
+        salted_with_path: salt.is_some(),
         known_password_hex: Some(key.expose_secret().to_hex()),
     };
 
-    store_file_entry(index_conn, &entry)?;
+    let indexed = index_conn
+        .unchecked_transaction()
+        .map_err(CoreError::from)
+        .and_then(|tx| {
+            store_file_entry(&tx, &entry)?;
+            add_file_ref(&tx, &entry.file_id, &source_path, &entry.display_name)?;
+            Ok(tx.commit()?)
+        });
+    if let Err(e) = indexed {
+        let _ = std::fs::remove_file(&entry.current_path);
+        let _ = purge_keys(vault_conn, &entry.file_id);
+        return Err(e);
+    }
     Ok(entry)
 }
+
+enum Decision {
+    /// Fresh content (or a copy): index under `file_id`
+    New {
+        file_id: String,
+        salt: Option<String>,
+    },
+    /// Already stored under this id; reuse it
+    Existing(String),
+}
+
+/// Apply `dedup` and pick the id for a just-encrypted plaintext
+fn decide_placement(
+    index_conn: &Connection,
+    hasher: &FileIdHasher,
+    plaintext_hash: &str,
+    source_path: &str,
+    display_name: &str,
+    salt_with_path: bool,
+    dedup: DedupPolicy,
+) -> Result<Decision> {
+    let mut salt = (salt_with_path && hasher.is_keyed()).then(|| source_path.to_string());
+
+    let content_hash = hasher.content_hash(plaintext_hash);
+    if let Some(existing) = find_by_content_hash(index_conn, &content_hash)? {
+        match dedup {
+            DedupPolicy::ReuseExisting => return Ok(Decision::Existing(existing.file_id)),
+            DedupPolicy::Reject => {
+                return Err(CoreError::DuplicateContent {
+                    display_name: display_name.to_string(),
+                    existing_id: existing.file_id,
+                })
+            }
+            DedupPolicy::StoreCopy if !hasher.is_keyed() => {
+                return Err(CoreError::PolicyViolation(
+                    "storing copies needs keyed file ids — open the vault with Vault::open to upgrade"
+                        .into(),
+                ))
+            }
+            DedupPolicy::StoreCopy => salt = Some(source_path.to_string()),
+        }
+    }
+
+    let mut file_id = hasher.file_id(plaintext_hash, salt.as_deref());
+    if salt.is_some() && file_id_exists(index_conn, &file_id)? {
+        // Another copy from the same source: make the id unique
+        let unique = format!("{source_path}#{}", hex::encode(rand::random::<[u8; 8]>()));
+        file_id = hasher.file_id(plaintext_hash, Some(&unique));
+        salt = Some(unique);
+    }
+    Ok(Decision::New { file_id, salt })
+}
+
+/// Record another source for stored content (restoring it from the trash
+/// if that is where it is) and return its entry
+fn reuse_existing(
+    index_conn: &Connection,
+    file_id: &str,
+    source_path: &str,
+    display_name: &str,
+) -> Result<FileEntry> {
+    restore_from_trash(index_conn, file_id)?;
+    add_file_ref(index_conn, file_id, source_path, display_name)?;
+    get_file_entry(index_conn, file_id)?.ok_or_else(|| CoreError::NotFound(file_id.to_string()))
+}
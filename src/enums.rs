@@ -18,14 +18,39 @@ pub enum EncryptionAlgorithm {
     // AES256GCM,
 }
 
-/// Future export formats (JSON, encrypted backup, etc.)
+impl EncryptionAlgorithm {
+    /// Name stored in `files.encryption_algo`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EncryptionAlgorithm::AESCryptV3 => "AESCryptV3",
+        }
+    }
+}
+
+impl std::str::FromStr for EncryptionAlgorithm {
+    type Err = crate::error::CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "AESCryptV3" => Ok(EncryptionAlgorithm::AESCryptV3),
+            other => Err(crate::error::CoreError::Config(format!(
+                "unknown encryption algorithm {other:?}"
+            ))),
+        }
+    }
+}
+
+/// Export formats (JSON, encrypted backup, etc.)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[non_exhaustive]
 pub enum ExportFormat {
-    #[default]
+    /// Plaintext JSON with every key in cleartext (`export::json`)
     JsonV1,
-    // EncryptedBackupV1,
-    // PortableVaultV1,
+    /// The JSON export encrypted under a backup passphrase (`export::backup`)
+    #[default]
+    EncryptedBackupV1,
+    /// Databases and ciphertext in one checksummed tar (`export::portable`)
+    PortableVaultV1,
 }
 
 /// Source of an import operation
@@ -36,3 +61,126 @@ pub enum ImportSource {
     DirectoryScan,
     BackupFile,
 }
+
+/// How the vault names ciphertext files on disk (`files.filename_style`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FilenameStyle {
+    /// `<stem>.<first N hex of id>.aes` — recognisable, id extended on collision
+    #[default]
+    Human,
+    /// `<full id>.aes`
+    Hash,
+    /// Random name, unrelated to the original filename or content
+    Opaque,
+}
+
+impl FilenameStyle {
+    /// Name stored in `files.filename_style`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FilenameStyle::Human => "human",
+            FilenameStyle::Hash => "hash",
+            FilenameStyle::Opaque => "opaque",
+        }
+    }
+}
+
+impl std::str::FromStr for FilenameStyle {
+    type Err = crate::error::CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(FilenameStyle::Human),
+            "hash" => Ok(FilenameStyle::Hash),
+            "opaque" => Ok(FilenameStyle::Opaque),
+            other => Err(crate::error::CoreError::Config(format!(
+                "unknown filename style {other:?}"
+            ))),
+        }
+    }
+}
+
+/// Text encoding of a 256-bit key in exports (`key_ops::password_representations`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyEncoding {
+    /// 64 lowercase hex characters — the AES Crypt password form
+    Hex,
+    /// Standard base64, padded
+    Base64,
+    /// URL-safe base64 without padding, as in JSON exports
+    #[default]
+    Base64Url,
+}
+
+impl KeyEncoding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KeyEncoding::Hex => "hex",
+            KeyEncoding::Base64 => "base64",
+            KeyEncoding::Base64Url => "base64url",
+        }
+    }
+}
+
+impl std::str::FromStr for KeyEncoding {
+    type Err = crate::error::CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" => Ok(KeyEncoding::Hex),
+            "base64" => Ok(KeyEncoding::Base64),
+            "base64url" => Ok(KeyEncoding::Base64Url),
+            other => Err(crate::error::CoreError::Config(format!(
+                "unknown key encoding {other:?}"
+            ))),
+        }
+    }
+}
+
+/// What adding a file does when the same content is already in the vault
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum DedupPolicy {
+    /// Keep the existing ciphertext and record the new source as another reference
+    #[default]
+    ReuseExisting,
+    /// Encrypt an independent copy under its own id and key
+    StoreCopy,
+    /// Fail with `CoreError::DuplicateContent`
+    Reject,
+}
+
+/// What a legacy import does with the original AES Crypt files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum OriginalsPolicy {
+    /// Leave them where they are
+    #[default]
+    Keep,
+    /// Delete each once its upgraded copy has been verified in the vault
+    Remove,
+}
+
+/// What a restore does with a backed-up entry whose id the vault already has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ConflictPolicy {
+    /// Keep the vault's entry and skip the backup's
+    #[default]
+    Skip,
+    /// Replace the vault's entry; the restored key becomes its newest version
+    Overwrite,
+    /// Restore the backup's entry under a new id, with its own ciphertext copy
+    KeepBoth,
+}
+
+/// How `remove_file` gets rid of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum RemoveMode {
+    /// Move the index entry to the trash; ciphertext and keys stay (restorable)
+    #[default]
+    SoftDelete,
+    /// Delete the ciphertext and the index entry; keys stay in the vault
+    HardDelete,
+    /// Hard delete, then purge every key version and VACUUM both databases
+    CryptoShred,
+}
@@ -1,23 +1,130 @@
 // src/error.rs
 //! Public error type for the entire crate
+//!
+//! Library code never panics on bad input, bad files or bad configuration —
+//! every failure comes back as a `CoreError` variant a caller can match on.
+
+use std::io::ErrorKind;
+use std::path::PathBuf;
 
 use aescrypt_rs::AescryptError;
 use thiserror::Error;
 
+use crate::atomic_write::RenameError;
+
 #[derive(Error, Debug)]
 pub enum CoreError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// A crypto failure that is neither a wrong password nor corrupt input
     #[error("Crypto operation failed: {0}")]
     Crypto(AescryptError),
 
+    #[error("Wrong password or key")]
+    WrongPassword,
+
+    #[error("Ciphertext is corrupt, truncated or not AES Crypt: {0}")]
+    CorruptCiphertext(String),
+
     #[error("Database error: {0}")]
     Sql(#[from] rusqlite::Error),
+
+    #[error("No file with id {0} in the vault")]
+    NotFound(String),
+
+    #[error("No file named {0:?} in the vault")]
+    NameNotFound(String),
+
+    #[error("No tag named {0:?} in the vault")]
+    TagNotFound(String),
+
+    #[error("{file_id} has no reference from {source_path}")]
+    ReferenceNotFound {
+        file_id: String,
+        source_path: String,
+    },
+
+    #[error("No key stored for file {0}")]
+    MissingKey(String),
+
+    #[error("Integrity check failed for {file_id}: expected BLAKE3 {expected}, got {actual}")]
+    IntegrityMismatch {
+        file_id: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("{} files are named {name:?} — use one of the file ids: {}", .candidates.len(), .candidates.join(", "))]
+    Ambiguous {
+        name: String,
+        candidates: Vec<String>,
+    },
+
+    #[error("{display_name:?} is already in the vault as {existing_id}")]
+    DuplicateContent {
+        display_name: String,
+        existing_id: String,
+    },
+
+    /// A backup or export file that is malformed or of an unknown format
+    #[error("Invalid backup: {0}")]
+    InvalidBackup(String),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("{database} schema version {found} is newer than this build supports ({supported})")]
+    SchemaTooNew {
+        database: &'static str,
+        found: u32,
+        supported: u32,
+    },
+
+    #[error("Policy violation: {0}")]
+    PolicyViolation(String),
+
+    #[error("Invalid path: {}", .0.display())]
+    InvalidPath(PathBuf),
+
+    #[error("Operation cancelled")]
+    Cancelled,
 }
 
+impl From<RenameError> for CoreError {
+    fn from(err: RenameError) -> Self {
+        CoreError::Io(err.into())
+    }
+}
+
+/// AES Crypt failures outside decryption (encrypting, converting)
+///
+/// Decryption goes through `crypto::decrypt_checked`, which sorts failures
+/// with [`CoreError::from_decrypt`] instead.
 impl From<AescryptError> for CoreError {
     fn from(err: AescryptError) -> Self {
-        CoreError::Crypto(err)
+        match err {
+            AescryptError::Io(e) => CoreError::Io(e),
+            other => CoreError::Crypto(other),
+        }
+    }
+}
+
+impl CoreError {
+    /// Sort a failed decryption into what a caller can act on
+    ///
+    /// AES Crypt reports a failed HMAC check as `AescryptError::Crypto` —
+    /// the check over the session key in v1–v3, and the trailing one over
+    /// the payload, which is the only password check a v0 file has. Those
+    /// are a wrong password; a payload damaged behind an intact header fails
+    /// the same check and reads as one too. Everything else the decryptor
+    /// rejects (a bad header, an unknown version, input that ends early) is
+    /// corrupt ciphertext, and other I/O errors stay I/O errors.
+    pub(crate) fn from_decrypt(err: AescryptError) -> Self {
+        match err {
+            AescryptError::Crypto(_) => CoreError::WrongPassword,
+            AescryptError::Io(e) if e.kind() != ErrorKind::UnexpectedEof => CoreError::Io(e),
+            other => CoreError::CorruptCiphertext(other.to_string()),
+        }
     }
 }
@@ -0,0 +1,118 @@
+// src/export/backup.rs
+//! Encrypted backups (`ExportFormat::EncryptedBackupV1`)
+//!
+//! The JSON export wrapped in AES Crypt v3 under a backup passphrase:
+//!
+//! ```text
+//! {"format":"efv-encrypted-backup-v1","kdf":"argon2id","params":{…},"salt":"…"}\n
+//! <AES Crypt v3 stream of the encrypted-file-vault-v1 JSON document>
+//! ```
+//!
+//! passphrase ─Argon2id(salt)→ 32 bytes ─BLAKE3 derive_key→ AES Crypt password
+//!
+//! The header line holds only the salt and KDF parameters. A wrong
+//! passphrase fails AES Crypt's HMAC check (`CoreError::WrongPassword`).
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::aliases::{FilePassword, SecureConversionsExt, UserPassphrase};
+use crate::atomic_write::write_atomic;
+use crate::error::CoreError;
+use crate::export::json::render_export;
+use crate::file_ops::{decrypt_stream, encrypt_stream};
+use crate::unlock::{self, KdfParams, KDF_NAME, SALT_LEN};
+use crate::Result;
+
+/// `format` of the header line of every encrypted backup
+pub const BACKUP_FORMAT: &str = "efv-encrypted-backup-v1";
+
+const BACKUP_KEY_CONTEXT: &str = "encrypted-file-vault 2026-10 backup AES Crypt password";
+
+/// Longest header line accepted when reading a backup
+const MAX_HEADER_LEN: u64 = 4096;
+
+/// Plaintext first line of a backup: salt + KDF parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BackupHeader {
+    format: String,
+    kdf: String,
+    params: KdfParams,
+    /// Standard base64
+    salt: String,
+}
+
+impl BackupHeader {
+    fn password(&self, passphrase: &UserPassphrase) -> Result<FilePassword> {
+        let salt = STANDARD
+            .decode(&self.salt)
+            .map_err(|e| CoreError::InvalidBackup(format!("invalid salt: {e}")))?;
+        let master = unlock::argon2id(passphrase, &salt, self.params)?;
+        let key = Zeroizing::new(blake3::derive_key(BACKUP_KEY_CONTEXT, master.as_slice()));
+        Ok(FilePassword::new(key.as_slice().to_hex()))
+    }
+}
+
+/// Export every entry and key to an encrypted backup at `path`
+///
+/// Same content as [`export_to_json`](crate::export::export_to_json), but
+/// nothing readable without the passphrase ever reaches the disk. Returns
+/// the number of exported files.
+pub fn export_encrypted_backup<P: AsRef<Path>>(
+    vault_conn: &Connection,
+    index_conn: &Connection,
+    path: P,
+    passphrase: &UserPassphrase,
+    params: KdfParams,
+) -> Result<usize> {
+    let (rendered, count) = render_export(vault_conn, index_conn)?;
+
+    let salt: [u8; SALT_LEN] = rand::random();
+    let header = BackupHeader {
+        format: BACKUP_FORMAT.into(),
+        kdf: KDF_NAME.into(),
+        params,
+        salt: STANDARD.encode(salt),
+    };
+    let password = header.password(passphrase)?;
+    let header_line = serde_json::to_string(&header).map_err(std::io::Error::from)?;
+
+    write_atomic(path, |file| -> Result<()> {
+        writeln!(file, "{header_line}")?;
+        encrypt_stream(rendered.as_slice(), file, &password)?;
+        Ok(())
+    })?;
+    Ok(count)
+}
+
+/// Decrypt the backup at `path` back into its JSON document
+pub(crate) fn read_encrypted_backup(
+    path: &Path,
+    passphrase: &UserPassphrase,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+
+    let mut line = String::new();
+    (&mut reader).take(MAX_HEADER_LEN).read_line(&mut line)?;
+    let header: BackupHeader = serde_json::from_str(line.trim_end()).map_err(|_| {
+        CoreError::InvalidBackup(format!("{} is not an encrypted backup", path.display()))
+    })?;
+    if header.format != BACKUP_FORMAT || header.kdf != KDF_NAME {
+        return Err(CoreError::InvalidBackup(format!(
+            "unsupported backup: format {:?}, kdf {:?}",
+            header.format, header.kdf
+        )));
+    }
+
+    let password = header.password(passphrase)?;
+    let mut document = Zeroizing::new(Vec::new());
+    decrypt_stream(reader, &mut *document, &password)?;
+    Ok(document)
+}
@@ -1,9 +1,273 @@
 // src/export/csv.rs
-// Future: export to CSV for general transfer/sharing
-use std::error::Error;
+//! CSV export for spreadsheets and audits
+//!
+//! One row per live entry, columns chosen by the caller. By default no key
+//! material is written at all; [`CsvKeys::Include`] appends each entry's
+//! current key in the chosen encoding, which is refused unless the caller
+//! passes `allow_insecure_export` (`features.allow_insecure_export`).
+//!
+//! Output is RFC 4180: CRLF line endings, and any field holding a comma,
+//! quote or line break is quoted with inner quotes doubled. Unless
+//! [`CsvExportOptions::neutralise_formulas`] is turned off, free-text cells
+//! (display name, tags, note) starting with `=`, `+`, `-`, `@`, tab or
+//! carriage return get a leading `'` so a spreadsheet shows them as text
+//! instead of running them as formulas.
 
-// Stub — implement when ready
-pub fn export_to_csv(path: &str) -> Result<(), Box<dyn Error>> {
-    // TODO: Query DBs, write CSV with csv::Writer
-    unimplemented!("CSV export coming soon");
+use std::path::Path;
+
+use rusqlite::Connection;
+use zeroize::Zeroizing;
+
+use crate::atomic_write::write_atomic;
+use crate::db::tag_ops;
+use crate::db::vault_db_ops::load_current_key;
+use crate::enums::KeyEncoding;
+use crate::error::CoreError;
+use crate::export::check_insecure_export;
+use crate::key_ops::{password_representations, PasswordRepr};
+use crate::Result;
+
+/// One metadata column of a CSV export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CsvColumn {
+    FileId,
+    DisplayName,
+    CurrentPath,
+    PlaintextSize,
+    CreatedAt,
+    RotatedAt,
+    ContentHash,
+    FilenameStyle,
+    /// Comma-separated, in the order `db::tag_ops::file_tags` returns them
+    Tags,
+    Note,
+}
+
+impl CsvColumn {
+    pub const ALL: [CsvColumn; 10] = [
+        CsvColumn::FileId,
+        CsvColumn::DisplayName,
+        CsvColumn::CurrentPath,
+        CsvColumn::PlaintextSize,
+        CsvColumn::CreatedAt,
+        CsvColumn::RotatedAt,
+        CsvColumn::ContentHash,
+        CsvColumn::FilenameStyle,
+        CsvColumn::Tags,
+        CsvColumn::Note,
+    ];
+
+    /// Header cell, also accepted by `str::parse`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CsvColumn::FileId => "file_id",
+            CsvColumn::DisplayName => "display_name",
+            CsvColumn::CurrentPath => "current_path",
+            CsvColumn::PlaintextSize => "plaintext_size",
+            CsvColumn::CreatedAt => "created_at",
+            CsvColumn::RotatedAt => "rotated_at",
+            CsvColumn::ContentHash => "content_hash",
+            CsvColumn::FilenameStyle => "filename_style",
+            CsvColumn::Tags => "tags",
+            CsvColumn::Note => "note",
+        }
+    }
+}
+
+impl std::str::FromStr for CsvColumn {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        CsvColumn::ALL
+            .into_iter()
+            .find(|column| column.as_str() == s)
+            .ok_or_else(|| CoreError::Config(format!("unknown CSV column {s:?}")))
+    }
+}
+
+/// Whether a CSV export carries key material
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CsvKeys {
+    /// Metadata only
+    #[default]
+    Omit,
+    /// A last `key_<encoding>` column with each entry's current key
+    Include(KeyEncoding),
+}
+
+/// What [`export_to_csv`] writes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvExportOptions {
+    /// Metadata columns in output order (any key column comes last); must
+    /// not be empty
+    pub columns: Vec<CsvColumn>,
+    pub keys: CsvKeys,
+    /// Prefix formula-like free-text cells with `'`; turn off when the CSV
+    /// is read by a program rather than a spreadsheet
+    pub neutralise_formulas: bool,
+}
+
+impl Default for CsvExportOptions {
+    /// Every metadata column, no keys, formulas neutralised
+    fn default() -> Self {
+        Self {
+            columns: CsvColumn::ALL.to_vec(),
+            keys: CsvKeys::Omit,
+            neutralise_formulas: true,
+        }
+    }
+}
+
+/// Export every live entry to a CSV file, ordered by display name
+///
+/// With [`CsvKeys::Include`] the file holds every key in cleartext — the
+/// same warning as [`export_to_json`](crate::export::export_to_json)
+/// applies, and it is refused with `CoreError::PolicyViolation` unless
+/// `allow_insecure_export` is set. Returns the number of rows written.
+pub fn export_to_csv<P: AsRef<Path>>(
+    vault_conn: &Connection,
+    index_conn: &Connection,
+    path: P,
+    options: &CsvExportOptions,
+    allow_insecure_export: bool,
+) -> Result<usize> {
+    if options.keys != CsvKeys::Omit {
+        check_insecure_export(allow_insecure_export)?;
+    }
+    if options.columns.is_empty() {
+        return Err(CoreError::Config(
+            "CSV export needs at least one column".into(),
+        ));
+    }
+
+    let mut header: Vec<&str> = options.columns.iter().map(|c| c.as_str()).collect();
+    if let CsvKeys::Include(encoding) = options.keys {
+        header.push(key_header(encoding));
+    }
+
+    let mut stmt = index_conn.prepare(
+        "SELECT file_id, display_name, current_path, plaintext_size, created_at, rotated_at, \
+         content_hash, filename_style, note \
+         FROM files ORDER BY display_name, file_id",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(Row {
+                file_id: row.get(0)?,
+                display_name: row.get(1)?,
+                current_path: row.get(2)?,
+                plaintext_size: row.get(3)?,
+                created_at: row.get(4)?,
+                rotated_at: row.get(5)?,
+                content_hash: row.get(6)?,
+                filename_style: row.get(7)?,
+                note: row.get(8)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    write_atomic(path, |file| -> Result<()> {
+        let mut writer = csv::WriterBuilder::new()
+            .terminator(csv::Terminator::CRLF)
+            .from_writer(file);
+        writer.write_record(&header).map_err(std::io::Error::from)?;
+
+        for row in &rows {
+            let mut record: Vec<Zeroizing<String>> = Vec::with_capacity(header.len());
+            for column in &options.columns {
+                record.push(Zeroizing::new(row.field(
+                    index_conn,
+                    *column,
+                    options.neutralise_formulas,
+                )?));
+            }
+            if let CsvKeys::Include(encoding) = options.keys {
+                let key = load_current_key(vault_conn, &row.file_id).map_err(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => {
+                        CoreError::MissingKey(row.file_id.clone())
+                    }
+                    other => other.into(),
+                })?;
+                record.push(encode_key(password_representations(&key), encoding));
+            }
+            writer
+                .write_record(record.iter().map(|field| field.as_bytes()))
+                .map_err(std::io::Error::from)?;
+        }
+        writer.flush()?;
+        Ok(())
+    })?;
+    Ok(rows.len())
+}
+
+/// One `files` row
+struct Row {
+    file_id: String,
+    display_name: String,
+    current_path: String,
+    plaintext_size: i64,
+    created_at: String,
+    rotated_at: Option<String>,
+    content_hash: String,
+    filename_style: String,
+    note: Option<String>,
+}
+
+impl Row {
+    fn field(
+        &self,
+        index_conn: &Connection,
+        column: CsvColumn,
+        neutralise: bool,
+    ) -> Result<String> {
+        let free_text = |cell: String| if neutralise { as_text(cell) } else { cell };
+        Ok(match column {
+            CsvColumn::FileId => self.file_id.clone(),
+            CsvColumn::DisplayName => free_text(self.display_name.clone()),
+            CsvColumn::CurrentPath => self.current_path.clone(),
+            CsvColumn::PlaintextSize => self.plaintext_size.to_string(),
+            CsvColumn::CreatedAt => self.created_at.clone(),
+            CsvColumn::RotatedAt => self.rotated_at.clone().unwrap_or_default(),
+            CsvColumn::ContentHash => self.content_hash.clone(),
+            CsvColumn::FilenameStyle => self.filename_style.clone(),
+            CsvColumn::Tags => free_text(tag_ops::file_tags(index_conn, &self.file_id)?.join(",")),
+            CsvColumn::Note => free_text(self.note.clone().unwrap_or_default()),
+        })
+    }
+}
+
+/// Keep a spreadsheet from evaluating a free-text cell as a formula
+fn as_text(cell: String) -> String {
+    if cell.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{cell}")
+    } else {
+        cell
+    }
+}
+
+fn key_header(encoding: KeyEncoding) -> &'static str {
+    match encoding {
+        KeyEncoding::Hex => "key_hex",
+        KeyEncoding::Base64 => "key_base64",
+        KeyEncoding::Base64Url => "key_base64url",
+    }
+}
+
+/// The chosen representation; the others are wiped too
+fn encode_key(repr: PasswordRepr, encoding: KeyEncoding) -> Zeroizing<String> {
+    let PasswordRepr {
+        hex,
+        base64,
+        base64url_no_pad,
+    } = repr;
+    let (hex, base64, base64url) = (
+        Zeroizing::new(hex),
+        Zeroizing::new(base64),
+        Zeroizing::new(base64url_no_pad),
+    );
+    match encoding {
+        KeyEncoding::Hex => hex,
+        KeyEncoding::Base64 => base64,
+        KeyEncoding::Base64Url => base64url,
+    }
 }
@@ -1,23 +1,50 @@
 // src/export/json.rs
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use chrono::Utc;
-use secure_gate::dynamic_alias;
-use serde_json::json;
-use std::error::Error;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::Path;
+use zeroize::{Zeroize, Zeroizing};
 
-use crate::db::{index_db_conn::open_index_db, vault_db_conn::open_vault_db};
+use crate::aliases::PasswordBlob;
+use crate::atomic_write::write_atomic;
+use crate::db::tag_ops;
+use crate::export::check_insecure_export;
+use crate::file_id::FileIdHasher;
+use crate::Result;
 
-// Your alias — perfect
-dynamic_alias!(PasswordBlob, Vec<u8>);
+/// `export_format` of documents written by [`export_to_json`]
+pub const JSON_EXPORT_FORMAT: &str = "encrypted-file-vault-v1";
 
 /// Export all file metadata + passwords to a portable JSON file using Base64URL encoding.
 ///
 /// SECURITY WARNING: This file contains every password in cleartext.
-/// Protect it like nuclear launch codes.
-pub fn export_to_json(path: &str) -> Result<(), Box<dyn Error>> {
-    let index_conn = open_index_db()?;
-    let vault_conn = open_vault_db()?;
+/// Protect it like nuclear launch codes — or write an encrypted backup
+/// instead ([`export_encrypted_backup`](crate::export::export_encrypted_backup)).
+/// Refused with `CoreError::PolicyViolation` unless `allow_insecure_export`
+/// is set.
+///
+/// Returns the number of exported files.
+pub fn export_to_json<P: AsRef<Path>>(
+    vault_conn: &Connection,
+    index_conn: &Connection,
+    path: P,
+    allow_insecure_export: bool,
+) -> Result<usize> {
+    check_insecure_export(allow_insecure_export)?;
+    let (rendered, count) = render_export(vault_conn, index_conn)?;
+
+    write_atomic(path.as_ref(), |file| file.write_all(&rendered))?;
+
+    Ok(count)
+}
 
+/// The export document and the number of files in it
+pub(crate) fn render_export(
+    vault_conn: &Connection,
+    index_conn: &Connection,
+) -> Result<(Zeroizing<Vec<u8>>, usize)> {
     let mut stmt = index_conn.prepare(
         r#"
         SELECT 
@@ -27,7 +54,6 @@ pub fn export_to_json(path: &str) -> Result<(), Box<dyn Error>> {
             f.plaintext_size,
             f.created_at,
             f.rotated_at,
-            f.tags,
             f.note,
             f.content_hash,
             f.filename_style,
@@ -45,11 +71,10 @@ pub fn export_to_json(path: &str) -> Result<(), Box<dyn Error>> {
             row.get::<_, i64>(3)?,            // plaintext_size
             row.get::<_, String>(4)?,         // created_at
             row.get::<_, Option<String>>(5)?, // rotated_at
-            row.get::<_, Option<String>>(6)?, // tags
-            row.get::<_, Option<String>>(7)?, // note
-            row.get::<_, String>(8)?,         // content_hash
-            row.get::<_, String>(9)?,         // filename_style
-            row.get::<_, i64>(10)?,           // id_length
+            row.get::<_, Option<String>>(6)?, // note
+            row.get::<_, String>(7)?,         // content_hash
+            row.get::<_, String>(8)?,         // filename_style
+            row.get::<_, i64>(9)?,            // id_length
         ))
     })?;
 
@@ -63,13 +88,14 @@ pub fn export_to_json(path: &str) -> Result<(), Box<dyn Error>> {
             plaintext_size,
             created_at,
             rotated_at,
-            tags,
             note,
             content_hash,
             filename_style,
             id_length,
         ) = row?;
 
+        let tags = tag_ops::file_tags(index_conn, &file_id)?;
+
         // Fetch raw BLOB → convert directly into your secure-gate alias
         let password_blob: PasswordBlob = vault_conn.query_row(
             "SELECT password_blob FROM keys WHERE file_id = ?1",
@@ -80,37 +106,95 @@ pub fn export_to_json(path: &str) -> Result<(), Box<dyn Error>> {
             },
         )?;
 
-        // expose_secret() works because ExposeSecret is in scope
-        let password_b64 = URL_SAFE_NO_PAD.encode(password_blob.expose_secret());
-
-        files.push(json!({
-            "file_id": file_id,
-            "content_hash": content_hash,
-            "display_name": display_name,
-            "current_path": current_path,
-            "plaintext_size_bytes": plaintext_size,
-            "password_base64url": password_b64,
-            "created_at": created_at,
-            "rotated_at": rotated_at,
-            "tags": tags,
-            "note": note,
-            "filename_style": filename_style,
-            "id_length_hex_chars": id_length,
-        }));
+        let password_base64url = URL_SAFE_NO_PAD.encode(password_blob.expose_secret());
+
+        files.push(ExportedFile {
+            file_id,
+            content_hash,
+            display_name,
+            current_path,
+            plaintext_size_bytes: plaintext_size,
+            password_base64url,
+            created_at,
+            rotated_at,
+            tags,
+            note,
+            filename_style,
+            id_length_hex_chars: id_length,
+        });
+    }
+
+    let count = files.len();
+    let export = ExportDocument {
+        export_format: JSON_EXPORT_FORMAT,
+        exported_at: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        exporter_version: env!("CARGO_PKG_VERSION"),
+        file_id_secret_check: FileIdHasher::load(vault_conn)?.secret_check(),
+        total_files: count,
+        warning: "THIS FILE CONTAINS ALL PASSWORDS IN PLAINTEXT. ENCRYPT OR DELETE IMMEDIATELY AFTER USE.",
+        files,
+    };
+
+    let mut rendered = SecretBuffer::default();
+    serde_json::to_writer_pretty(&mut rendered, &export).map_err(std::io::Error::from)?;
+    Ok((rendered.0, count))
+}
+
+/// An `encrypted-file-vault-v1` document
+#[derive(Serialize)]
+struct ExportDocument {
+    export_format: &'static str,
+    exported_at: String,
+    exporter_version: &'static str,
+    /// Lets a restore tell whether the ids and content hashes below are
+    /// valid in the target vault
+    file_id_secret_check: Option<String>,
+    total_files: usize,
+    warning: &'static str,
+    files: Vec<ExportedFile>,
+}
+
+#[derive(Serialize)]
+struct ExportedFile {
+    file_id: String,
+    content_hash: String,
+    display_name: String,
+    current_path: String,
+    plaintext_size_bytes: i64,
+    password_base64url: String,
+    created_at: String,
+    rotated_at: Option<String>,
+    tags: Vec<String>,
+    note: Option<String>,
+    filename_style: String,
+    id_length_hex_chars: i64,
+}
+
+impl Drop for ExportedFile {
+    fn drop(&mut self) {
+        self.password_base64url.zeroize();
     }
+}
 
-    let export = json!({
-        "export_format": "encrypted-file-vault-v1",
-        "exported_at": Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-        "exporter_version": env!("CARGO_PKG_VERSION"),
-        "total_files": files.len(),
-        "warning": "THIS FILE CONTAINS ALL PASSWORDS IN PLAINTEXT. ENCRYPT OR DELETE IMMEDIATELY AFTER USE.",
-        "files": files
-    });
+/// Growable output buffer that zeroizes every allocation it outgrows
+#[derive(Default)]
+struct SecretBuffer(Zeroizing<Vec<u8>>);
 
-    std::fs::write(path, serde_json::to_string_pretty(&export)?)?;
-    println!("Exported {} file(s) → {}", files.len(), path);
-    println!("SECURITY: This file is extremely sensitive — encrypt it now!");
+impl Write for SecretBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let needed = self.0.len() + buf.len();
+        if needed > self.0.capacity() {
+            // Copy into a fresh allocation instead of letting Vec realloc,
+            // so the old one is wiped when it drops
+            let mut grown = Zeroizing::new(Vec::with_capacity(needed.max(2 * self.0.capacity())));
+            grown.extend_from_slice(&self.0);
+            self.0 = grown;
+        }
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
 
-    Ok(())
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
@@ -1,13 +1,35 @@
 // src/export/mod.rs
 //! Export utilities for encrypted-file-vault
 //!
-//! Supports multiple formats: JSON, CSV, Bitwarden, etc.
-//! All exports are insecure by design (plaintext passwords) — warn users heavily.
+//! Supports multiple formats: JSON, encrypted backup, portable archive, CSV,
+//! Bitwarden, etc.
+//! Plaintext exports are insecure by design (every key in cleartext) — warn
+//! users heavily, and prefer [`export_encrypted_backup`].
 
+pub use backup::export_encrypted_backup;
+pub use csv::{export_to_csv, CsvColumn, CsvExportOptions, CsvKeys};
 pub use json::export_to_json;
-// pub use csv::export_to_csv;         // Future
+pub use portable::export_portable;
 // pub use bitwarden::export_bitwarden; // Future
 
+pub mod backup;
+pub mod csv;
 pub mod json;
-// mod csv;          // Future
+pub mod portable;
 // mod bitwarden;    // Future
+
+use crate::error::CoreError;
+use crate::Result;
+
+/// Refuse a plaintext key export unless the caller opted in
+/// (`features.allow_insecure_export`)
+pub(crate) fn check_insecure_export(allow_insecure_export: bool) -> Result<()> {
+    if allow_insecure_export {
+        return Ok(());
+    }
+    Err(CoreError::PolicyViolation(
+        "plaintext key export is disabled — use export_encrypted_backup, or opt in with \
+         allow_insecure_export"
+            .into(),
+    ))
+}
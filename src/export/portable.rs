@@ -0,0 +1,213 @@
+// src/export/portable.rs
+//! Portable vault archives (`ExportFormat::PortableVaultV1`)
+//!
+//! One uncompressed tar holding everything needed to move a vault to
+//! another machine:
+//!
+//! ```text
+//! manifest.json        format, BLAKE3 of every other member, entry → object
+//! vault-header.json    passphrase vaults only
+//! vault.db, index.db   the SQLCipher databases, still encrypted
+//! objects/<blake3>     every ciphertext (live and trashed), named by its BLAKE3
+//! ```
+//!
+//! Nothing in the archive is readable without the vault keys. The manifest
+//! comes first so an import can check each member as it streams past; see
+//! [`import::portable`](crate::import::portable).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use rusqlite::{Connection, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use tar::{Builder, EntryType, Header};
+
+use crate::atomic_write::write_atomic;
+use crate::consts::{INDEX_DB_FILE_NAME, VAULT_DB_FILE_NAME, VAULT_HEADER_FILE_NAME};
+use crate::error::CoreError;
+use crate::stream::{HashingReader, HashingWriter};
+use crate::Result;
+
+/// `format` of every portable archive manifest
+pub const PORTABLE_FORMAT: &str = "efv-portable-vault-v1";
+
+/// First member of every archive
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Archive directory holding the ciphertext
+pub const OBJECTS_DIR: &str = "objects";
+
+/// `manifest.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    pub format: String,
+    pub created_at: String,
+    pub exporter_version: String,
+    /// Every other member → BLAKE3 of its contents
+    pub members: BTreeMap<String, String>,
+    pub entries: Vec<ManifestEntry>,
+}
+
+/// Where one index entry's ciphertext is in the archive
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ManifestEntry {
+    pub file_id: String,
+    /// `objects/<blake3>`
+    pub object: String,
+    /// File name the ciphertext had in the exporting vault
+    pub name: String,
+}
+
+/// Write the whole vault — databases, header and ciphertext — to one archive
+///
+/// Both databases are held under a write lock while the archive is
+/// written, so the copy is consistent. Fails with a `NotFound` I/O error if
+/// an entry's ciphertext is missing. Returns the number of entries
+/// (including trashed ones).
+pub fn export_portable<P: AsRef<Path>>(
+    vault_conn: &Connection,
+    index_conn: &Connection,
+    path: P,
+) -> Result<usize> {
+    let _vault_lock = Transaction::new_unchecked(vault_conn, TransactionBehavior::Immediate)?;
+    let _index_lock = Transaction::new_unchecked(index_conn, TransactionBehavior::Immediate)?;
+
+    let vault_db = database_path(vault_conn)?;
+    let index_db = database_path(index_conn)?;
+    let header = vault_db
+        .parent()
+        .map(|dir| dir.join(VAULT_HEADER_FILE_NAME))
+        .filter(|header| header.is_file());
+
+    // Member name, source file and BLAKE3, in archive order
+    let mut sources: Vec<(String, PathBuf, String)> = Vec::new();
+    for (name, source) in header
+        .map(|header| (VAULT_HEADER_FILE_NAME, header))
+        .into_iter()
+        .chain([
+            (VAULT_DB_FILE_NAME, vault_db),
+            (INDEX_DB_FILE_NAME, index_db),
+        ])
+    {
+        let hash = hash_file(&source)?;
+        sources.push((name.to_string(), source, hash));
+    }
+
+    let mut entries = Vec::new();
+    let mut objects: HashMap<PathBuf, String> = HashMap::new();
+    let mut stmt = index_conn.prepare(
+        "SELECT file_id, current_path FROM files \
+         UNION ALL SELECT file_id, current_path FROM trash ORDER BY file_id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            PathBuf::from(row.get::<_, String>(1)?),
+        ))
+    })?;
+    for row in rows {
+        let (file_id, current_path) = row?;
+        if !current_path.is_file() {
+            return Err(CoreError::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "ciphertext of {file_id} is missing: {}",
+                    current_path.display()
+                ),
+            )));
+        }
+        let object = match objects.get(&current_path) {
+            Some(object) => object.clone(),
+            None => {
+                let hash = hash_file(&current_path)?;
+                let object = format!("{OBJECTS_DIR}/{hash}");
+                sources.push((object.clone(), current_path.clone(), hash));
+                objects.insert(current_path.clone(), object.clone());
+                object
+            }
+        };
+        let name = current_path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        entries.push(ManifestEntry {
+            file_id,
+            object,
+            name,
+        });
+    }
+
+    let manifest = Manifest {
+        format: PORTABLE_FORMAT.into(),
+        created_at: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        exporter_version: env!("CARGO_PKG_VERSION").into(),
+        members: sources
+            .iter()
+            .map(|(name, _, hash)| (name.clone(), hash.clone()))
+            .collect(),
+        entries,
+    };
+    let rendered = serde_json::to_vec_pretty(&manifest).map_err(io::Error::from)?;
+
+    write_atomic(path, |file| -> Result<()> {
+        let mut tar = Builder::new(file);
+        tar.append_data(
+            &mut member_header(rendered.len() as u64),
+            MANIFEST_NAME,
+            rendered.as_slice(),
+        )?;
+        for (name, source, hash) in &sources {
+            append_file(&mut tar, name, source, hash)?;
+        }
+        tar.finish()?;
+        Ok(())
+    })?;
+    Ok(manifest.entries.len())
+}
+
+fn database_path(conn: &Connection) -> Result<PathBuf> {
+    match conn.path() {
+        Some(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+        _ => Err(CoreError::PolicyViolation(
+            "in-memory databases cannot be exported".into(),
+        )),
+    }
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let mut hasher = HashingWriter::new(io::sink());
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.hash_hex())
+}
+
+fn member_header(size: u64) -> Header {
+    let mut header = Header::new_gnu();
+    header.set_entry_type(EntryType::Regular);
+    header.set_size(size);
+    header.set_mode(0o600);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    header
+}
+
+/// Stream `source` into the archive, checking it still has BLAKE3 `hash`
+fn append_file<W: Write>(
+    tar: &mut Builder<W>,
+    name: &str,
+    source: &Path,
+    hash: &str,
+) -> Result<()> {
+    let file = File::open(source)?;
+    let size = file.metadata()?.len();
+    let mut reader = HashingReader::new(file.take(size));
+    tar.append_data(&mut member_header(size), name, &mut reader)?;
+    if reader.hash_hex() != hash {
+        return Err(CoreError::Io(io::Error::other(format!(
+            "{} changed during export",
+            source.display()
+        ))));
+    }
+    Ok(())
+}
@@ -0,0 +1,250 @@
+// src/file_id.rs
+//! Keyed file ids and content hashes
+//!
+//! Ids used to be the plain BLAKE3 of the plaintext, so anyone holding the
+//! index could confirm whether the vault contains a known document. Both
+//! values are now keyed with a per-vault secret kept in `vault_meta`
+//! (inside the encrypted vault.db):
+//!
+//! - `content_hash = BLAKE3-keyed(secret, "content" ‖ blake3(plaintext))` —
+//!   the same for identical files, used for dedup and extraction checks
+//! - `file_id = BLAKE3-keyed(secret, "file-id" ‖ blake3(plaintext) [‖ path])`
+//!   — salted with the original path when `salted_with_path` is set, so
+//!   identical files added from different places get distinct ids
+//!
+//! Vaults created before keyed ids are upgraded by [`upgrade_file_ids`],
+//! which `Vault::open` runs automatically.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use zeroize::Zeroizing;
+
+use crate::aliases::FileIdSecret32;
+use crate::error::CoreError;
+use crate::Result;
+
+/// `vault_meta` row holding the 32-byte id secret
+pub const FILE_ID_SECRET: &str = "file_id_secret";
+
+/// `vault_meta` row naming the id scheme in use (absent: plain BLAKE3)
+pub const FILE_ID_SCHEME: &str = "file_id_scheme";
+
+/// Scheme value once ids are keyed
+pub const KEYED_SCHEME: &str = "keyed-blake3-v1";
+
+/// Domain separators, so an id never equals a content hash
+const ID_DOMAIN: &[u8] = b"efv file-id v1\0";
+const CONTENT_DOMAIN: &[u8] = b"efv content v1\0";
+const CHECK_DOMAIN: &[u8] = b"efv secret check v1\0";
+
+/// Derives ids and content hashes from a plaintext BLAKE3
+pub struct FileIdHasher {
+    /// `None` for a vault still on plain-BLAKE3 ids
+    secret: Option<FileIdSecret32>,
+}
+
+impl FileIdHasher {
+    /// Hasher for the scheme `vault_conn` is on
+    pub fn load(vault_conn: &Connection) -> Result<Self> {
+        if meta_text(vault_conn, FILE_ID_SCHEME)?.as_deref() != Some(KEYED_SCHEME) {
+            return Ok(Self { secret: None });
+        }
+        Ok(Self::keyed(load_secret(vault_conn)?))
+    }
+
+    pub fn keyed(secret: FileIdSecret32) -> Self {
+        Self {
+            secret: Some(secret),
+        }
+    }
+
+    pub fn is_keyed(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    /// Id for a plaintext with BLAKE3 `plaintext_hash`, optionally salted
+    /// with its original path (see [`salt_path`])
+    pub fn file_id(&self, plaintext_hash: &str, salt: Option<&str>) -> String {
+        let Some(secret) = &self.secret else {
+            return plaintext_hash.to_string();
+        };
+        let mut hasher = blake3::Hasher::new_keyed(secret.expose_secret());
+        hasher.update(ID_DOMAIN);
+        hasher.update(plaintext_hash.as_bytes());
+        if let Some(path) = salt {
+            hasher.update(&(path.len() as u64).to_le_bytes());
+            hasher.update(path.as_bytes());
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// Content hash stored in the index for a plaintext with BLAKE3 `plaintext_hash`
+    pub fn content_hash(&self, plaintext_hash: &str) -> String {
+        let Some(secret) = &self.secret else {
+            return plaintext_hash.to_string();
+        };
+        let mut hasher = blake3::Hasher::new_keyed(secret.expose_secret());
+        hasher.update(CONTENT_DOMAIN);
+        hasher.update(plaintext_hash.as_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// Fingerprint of the id secret (`None` on plain-BLAKE3 ids): two
+    /// vaults compute the same ids exactly when their checks are equal
+    pub fn secret_check(&self) -> Option<String> {
+        let secret = self.secret.as_ref()?;
+        let mut hasher = blake3::Hasher::new_keyed(secret.expose_secret());
+        hasher.update(CHECK_DOMAIN);
+        Some(hasher.finalize().to_hex().to_string())
+    }
+}
+
+/// Canonical form of a plaintext path used as an id salt
+pub fn salt_path(plaintext_path: &Path) -> Result<String> {
+    let canonical = plaintext_path.canonicalize()?;
+    canonical
+        .to_str()
+        .map(str::to_owned)
+        .ok_or(CoreError::InvalidPath(canonical))
+}
+
+/// Rewrite plain-BLAKE3 ids to keyed ids in both databases
+///
+/// The old → new mapping is journaled in vault.db (`file_id_remap`) in the
+/// same transaction that rewrites the keys and switches the scheme; index.db
+/// is rewritten from the journal afterwards and the journal cleared. A crash
+/// at any point is finished by the next call. Returns the number of ids
+/// rewritten (0 when already keyed).
+///
+/// Run with no key rotation in flight (`Vault::open` recovers those first).
+/// Existing entries are keyed without a path salt. Ciphertext names that
+/// embed the old id keep it until the next `Vault::relayout`.
+pub fn upgrade_file_ids(vault_conn: &mut Connection, index_conn: &Connection) -> Result<usize> {
+    let pending: i64 =
+        vault_conn.query_row("SELECT COUNT(*) FROM file_id_remap", [], |row| row.get(0))?;
+    if pending == 0 && meta_text(vault_conn, FILE_ID_SCHEME)?.as_deref() == Some(KEYED_SCHEME) {
+        return Ok(0);
+    }
+
+    if pending == 0 {
+        journal_remap(vault_conn, index_conn)?;
+    }
+    apply_remap(vault_conn, index_conn)
+}
+
+/// Phase 1: compute new ids, rewrite vault.db and journal the mapping
+fn journal_remap(vault_conn: &mut Connection, index_conn: &Connection) -> Result<()> {
+    let in_flight: bool = vault_conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM rotation_journal)",
+        [],
+        |row| row.get(0),
+    )?;
+    if in_flight {
+        return Err(CoreError::PolicyViolation(
+            "finish interrupted key rotations before upgrading file ids".into(),
+        ));
+    }
+
+    let hasher = FileIdHasher::keyed(load_secret(vault_conn)?);
+
+    let mut stmt = index_conn.prepare(
+        "SELECT file_id, content_hash FROM files UNION SELECT file_id, content_hash FROM trash",
+    )?;
+    let remap = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let tx = vault_conn.transaction()?;
+    for (old_id, plain_hash) in &remap {
+        let new_id = hasher.file_id(plain_hash, None);
+        tx.execute(
+            "INSERT INTO file_id_remap (old_id, new_id, new_content_hash) VALUES (?1, ?2, ?3)",
+            params![old_id, new_id, hasher.content_hash(plain_hash)],
+        )?;
+        tx.execute(
+            "UPDATE keys SET file_id = ?1 WHERE file_id = ?2",
+            params![new_id, old_id],
+        )?;
+        tx.execute(
+            "UPDATE key_history SET file_id = ?1 WHERE file_id = ?2",
+            params![new_id, old_id],
+        )?;
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO vault_meta (name, value) VALUES (?1, ?2)",
+        params![FILE_ID_SCHEME, KEYED_SCHEME],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Phase 2: rewrite index.db from the journal, then clear it
+fn apply_remap(vault_conn: &Connection, index_conn: &Connection) -> Result<usize> {
+    let mut stmt =
+        vault_conn.prepare("SELECT old_id, new_id, new_content_hash FROM file_id_remap")?;
+    let remap = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    // Re-running is harmless: rows already rewritten no longer match `old_id`
+    let tx = index_conn.unchecked_transaction()?;
+    for (old_id, new_id, content_hash) in &remap {
+        for table in ["files", "trash"] {
+            tx.execute(
+                &format!(
+                    "UPDATE {table} SET file_id = ?1, content_hash = ?2, salted_with_path = 0 \
+                     WHERE file_id = ?3"
+                ),
+                params![new_id, content_hash, old_id],
+            )?;
+        }
+        for table in [
+            "file_tags",
+            "file_refs",
+            "import_journal",
+            "relayout_journal",
+        ] {
+            tx.execute(
+                &format!("UPDATE {table} SET file_id = ?1 WHERE file_id = ?2"),
+                params![new_id, old_id],
+            )?;
+        }
+    }
+    tx.commit()?;
+
+    vault_conn.execute("DELETE FROM file_id_remap", [])?;
+    Ok(remap.len())
+}
+
+fn load_secret(vault_conn: &Connection) -> Result<FileIdSecret32> {
+    let raw: Zeroizing<Vec<u8>> = vault_conn
+        .query_row(
+            "SELECT value FROM vault_meta WHERE name = ?1",
+            [FILE_ID_SECRET],
+            |row| row.get(0).map(Zeroizing::new),
+        )
+        .optional()?
+        .ok_or_else(|| CoreError::MissingKey(FILE_ID_SECRET.to_string()))?;
+    let secret = <[u8; 32]>::try_from(raw.as_slice())
+        .map_err(|_| CoreError::Config("file id secret must be 32 bytes".into()))?;
+    Ok(FileIdSecret32::new(secret))
+}
+
+fn meta_text(vault_conn: &Connection, name: &str) -> rusqlite::Result<Option<String>> {
+    vault_conn
+        .query_row(
+            "SELECT value FROM vault_meta WHERE name = ?1",
+            [name],
+            |row| row.get(0),
+        )
+        .optional()
+}
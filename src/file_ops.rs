@@ -1,48 +1,115 @@
 // src/file_ops.rs
 //! File-level encryption/decryption operations
 //!
-//! This module handles encryption and decryption with file I/O,
-//! building on the pure crypto primitives from crypto.rs.
+//! This module handles streaming encryption and decryption with file I/O.
+//! Memory use is capped at `STREAM_BUFFER_SIZE` per stream; the in-memory
+//! primitives in crypto.rs remain for small buffers.
 //! Also includes AES-Crypt file detection utilities.
 
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::Path;
 
-use crate::aliases::{CypherText, FilePassword, PlainText};
-use crate::crypto::{decrypt_to_vec, encrypt_to_vec};
+use aescrypt_rs::encrypt;
+
+use crate::aliases::FilePassword;
+use crate::atomic_write::write_atomic;
+use crate::consts::{RANDOM_KEY_KDF_ITERATIONS, STREAM_BUFFER_SIZE};
+use crate::crypto::decrypt_checked;
 use crate::error::CoreError;
+use crate::stream::{CountingReader, HashingReader};
 
 /// Encrypt a file on disk using AES-Crypt v3
 ///
-/// Reads the plaintext file, encrypts it in-memory, writes the ciphertext.
+/// Streams the plaintext through the encryptor with bounded memory
+/// (`STREAM_BUFFER_SIZE` per side), so file size does not matter.
+/// The ciphertext is written atomically — `output_path` is either the
+/// complete result or left exactly as it was.
 /// Returns the plaintext size in bytes.
 pub fn encrypt_file<P: AsRef<Path>>(
     input_path: P,
     output_path: P,
     password: &FilePassword,
 ) -> Result<u64, CoreError> {
-    let plaintext = PlainText::new(std::fs::read(input_path.as_ref())?);
-    let ciphertext = encrypt_to_vec(&plaintext, password)?;
-    std::fs::write(output_path.as_ref(), ciphertext.expose_secret())?;
+    let input = File::open(input_path.as_ref())?;
+    write_atomic(output_path, |output| {
+        encrypt_stream(input, output, password)
+    })
+}
 
-    let plaintext_size_bytes = plaintext.expose_secret().len() as u64;
-    Ok(plaintext_size_bytes)
+/// Encrypt a file on disk and BLAKE3-hash its plaintext in the same pass
+///
+/// The source is read exactly once: every chunk goes to the hasher, the
+/// byte counter and the encryptor together.
+/// Returns `(plaintext_size, plaintext_blake3_hex)`.
+pub fn encrypt_file_hashed<P: AsRef<Path>>(
+    input_path: P,
+    output_path: P,
+    password: &FilePassword,
+) -> Result<(u64, String), CoreError> {
+    let mut input = HashingReader::new(File::open(input_path.as_ref())?);
+    let plaintext_size = write_atomic(output_path, |output| {
+        encrypt_stream(&mut input, output, password)
+    })?;
+    Ok((plaintext_size, input.hash_hex()))
 }
 
 /// Decrypt an AES-Crypt file on disk
 ///
-/// Reads the ciphertext file, decrypts it in-memory, writes the plaintext.
+/// Streams the ciphertext through the decryptor with bounded memory.
+/// The plaintext is written atomically, so a wrong password or corrupt
+/// ciphertext never leaves a partial file behind.
 /// Returns the plaintext size in bytes.
 pub fn decrypt_file<P: AsRef<Path>>(
     input_path: P,
     output_path: P,
     password: &FilePassword,
 ) -> Result<u64, CoreError> {
-    let ciphertext = CypherText::new(std::fs::read(input_path.as_ref())?);
-    let plaintext = decrypt_to_vec(&ciphertext, password)?;
-    std::fs::write(output_path.as_ref(), plaintext.expose_secret())?;
+    let input = File::open(input_path.as_ref())?;
+    write_atomic(output_path, |output| {
+        decrypt_stream(input, output, password)
+    })
+}
+
+/// Encrypt any reader into any writer using AES-Crypt v3
+///
+/// Returns the number of plaintext bytes consumed.
+pub fn encrypt_stream<R: Read, W: Write>(
+    input: R,
+    output: W,
+    password: &FilePassword,
+) -> Result<u64, CoreError> {
+    let mut reader = CountingReader::new(BufReader::with_capacity(STREAM_BUFFER_SIZE, input));
+    let mut writer = BufWriter::with_capacity(STREAM_BUFFER_SIZE, output);
+
+    encrypt(
+        &mut reader,
+        &mut writer,
+        password,
+        RANDOM_KEY_KDF_ITERATIONS,
+    )?;
+    writer.flush()?;
+
+    Ok(reader.bytes_read())
+}
+
+/// Decrypt any AES-Crypt reader into any writer
+///
+/// A wrong password fails with `CoreError::WrongPassword`, damaged or
+/// non-AES Crypt input with `CoreError::CorruptCiphertext`.
+/// Returns the number of plaintext bytes produced.
+pub fn decrypt_stream<R: Read, W: Write>(
+    input: R,
+    output: W,
+    password: &FilePassword,
+) -> Result<u64, CoreError> {
+    let reader = BufReader::with_capacity(STREAM_BUFFER_SIZE, input);
+    let mut writer = BufWriter::with_capacity(STREAM_BUFFER_SIZE, output);
+
+    let plaintext_size = decrypt_checked(reader, &mut writer, password)?;
+    writer.flush()?;
 
-    let plaintext_size_bytes = plaintext.expose_secret().len() as u64;
-    Ok(plaintext_size_bytes)
+    Ok(plaintext_size)
 }
 
 /// Check if data is an AES-Crypt file (any version)
@@ -0,0 +1,40 @@
+// src/import/backup.rs
+//! Restoring an encrypted backup (`ImportSource::BackupFile`)
+//!
+//! Decrypts a backup written by
+//! [`export_encrypted_backup`](crate::export::export_encrypted_backup) in
+//! memory and restores the JSON document inside exactly like
+//! [`import_from_json`](crate::import::import_from_json).
+
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use crate::aliases::UserPassphrase;
+use crate::export::backup::read_encrypted_backup;
+use crate::import::json::{restore_document, JsonImportOptions};
+use crate::import::ImportReport;
+use crate::Result;
+
+/// Restore every entry of the encrypted backup at `path`
+///
+/// A wrong passphrase fails with `CoreError::WrongPassword` and a file that
+/// is not a backup with `CoreError::InvalidBackup`; nothing is restored then.
+pub fn import_encrypted_backup<P: AsRef<Path>>(
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+    path: P,
+    passphrase: &UserPassphrase,
+    options: &JsonImportOptions,
+) -> Result<ImportReport> {
+    let path = path.as_ref();
+    let document = read_encrypted_backup(path, passphrase)?;
+    restore_document(
+        vault_conn,
+        index_conn,
+        &document,
+        path,
+        "backup-restore",
+        options,
+    )
+}
@@ -0,0 +1,335 @@
+// src/import/directory.rs
+//! Recursive directory import (`ImportSource::DirectoryScan`)
+//!
+//! Walks a tree, encrypts every regular file into the storage directory and
+//! records its path inside the tree on its `file_refs` row. Files whose
+//! content the vault already holds are not stored again; they get a
+//! `file_refs` row (with their relative path) on the existing entry.
+//!
+//! Each finished file is journaled in index.db (`import_journal`). Running
+//! an interrupted import of the same root again skips what the journal says
+//! is done — unless the file changed on disk since — instead of encrypting
+//! it a second time. The journal is cleared once an import completes.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use rusqlite::{params, Connection};
+
+use crate::db::index_db_ops::{add_file_ref, file_id_exists, set_ref_relative_path};
+use crate::db::vault_db_ops::{record_audit, store_file, StoreOptions};
+use crate::enums::{DedupPolicy, ImportSource};
+use crate::error::CoreError;
+use crate::file_id;
+use crate::import::{
+    relative_path, FailedFile, ImportReport, ImportedFile, OwnFiles, SkipReason, SkippedFile,
+};
+use crate::Result;
+
+/// What [`import_directory`] picks up
+///
+/// Globs are matched against the path relative to the root, with `/`
+/// separators; `*` also matches `/`, so `*.pdf` matches at any depth. A
+/// directory matching an exclude glob is not descended into.
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryImportOptions {
+    /// Only import files matching one of these globs (every file if empty)
+    pub include: Vec<String>,
+    /// Leave out files and directories matching any of these globs
+    pub exclude: Vec<String>,
+    /// Honour `.gitignore` files and `.git/info/exclude`; `.git` itself is skipped
+    pub respect_gitignore: bool,
+    /// Leave out dot-files and dot-directories
+    pub skip_hidden: bool,
+    /// Import what symbolic links point to (otherwise links are ignored)
+    pub follow_symlinks: bool,
+    /// Checked before each file; once set the import stops with
+    /// `CoreError::Cancelled` and can be resumed by running it again
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+/// Encrypt every file under `root` into `storage_dir`
+///
+/// Files are named per `store.naming`; content already in the vault is
+/// skipped whatever `store.dedup` says. Problems with single files end up
+/// in [`ImportReport::failed`]; only database errors and cancellation abort
+/// the import (which can then be resumed). The vault's own databases and
+/// storage directory are never imported, even when they sit inside `root`.
+pub fn import_directory(
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+    root: &Path,
+    storage_dir: &Path,
+    store: &StoreOptions,
+    options: &DirectoryImportOptions,
+) -> Result<ImportReport> {
+    let root = root.canonicalize()?;
+    if !root.is_dir() {
+        return Err(CoreError::InvalidPath(root));
+    }
+    let root_key = root
+        .to_str()
+        .ok_or_else(|| CoreError::InvalidPath(root.clone()))?
+        .to_owned();
+
+    let include = glob_set(&options.include)?;
+    let exclude = glob_set(&options.exclude)?;
+
+    let own = OwnFiles::of(vault_conn, index_conn, storage_dir)?;
+
+    let run = ImportRun {
+        root_key: &root_key,
+        storage_dir,
+        store: StoreOptions {
+            dedup: DedupPolicy::Reject,
+            ..*store
+        },
+        journal: load_journal(index_conn, &root_key)?,
+    };
+
+    let walk_root = root.clone();
+    let walk_exclude = exclude.clone();
+    let skip_git = options.respect_gitignore;
+    let mut walker = WalkBuilder::new(&root);
+    walker
+        .standard_filters(false)
+        .hidden(options.skip_hidden)
+        .git_ignore(options.respect_gitignore)
+        .git_exclude(options.respect_gitignore)
+        .parents(options.respect_gitignore)
+        .require_git(false)
+        .follow_links(options.follow_symlinks)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+            if own.contains(entry.path()) || (skip_git && entry.file_name() == ".git") {
+                return false;
+            }
+            match relative_path(&walk_root, entry.path()) {
+                Some(rel) => !walk_exclude.is_match(rel),
+                None => true,
+            }
+        });
+
+    let mut report = ImportReport::new(ImportSource::DirectoryScan);
+    for result in walker.build() {
+        if let Some(cancel) = &options.cancel {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(CoreError::Cancelled);
+            }
+        }
+
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(err) => {
+                report.failed.push(FailedFile {
+                    relative_path: error_path(&root, &err),
+                    source_id: None,
+                    error: CoreError::Io(std::io::Error::other(err)),
+                });
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+
+        let path = entry.path();
+        let Some(rel) = relative_path(&root, path) else {
+            report.failed.push(FailedFile {
+                relative_path: path.to_string_lossy().into_owned(),
+                source_id: None,
+                error: CoreError::InvalidPath(path.to_path_buf()),
+            });
+            continue;
+        };
+        if exclude.is_match(&rel) || (!options.include.is_empty() && !include.is_match(&rel)) {
+            continue;
+        }
+
+        match run.import_one(vault_conn, index_conn, path, &rel) {
+            Ok(Outcome::Added(file_id)) => report.added.push(ImportedFile {
+                relative_path: rel,
+                source_id: None,
+                file_id,
+            }),
+            Ok(Outcome::Skipped(reason)) => report.skipped.push(SkippedFile {
+                relative_path: rel,
+                source_id: None,
+                reason,
+            }),
+            Err(e @ CoreError::Sql(_)) => return Err(e),
+            Err(error) => report.failed.push(FailedFile {
+                relative_path: rel,
+                source_id: None,
+                error,
+            }),
+        }
+    }
+
+    index_conn.execute("DELETE FROM import_journal WHERE root = ?1", [&root_key])?;
+    record_audit(
+        vault_conn,
+        "directory-import",
+        Some(&format!(
+            "{root_key}: {} added, {} skipped, {} failed",
+            report.added.len(),
+            report.skipped.len(),
+            report.failed.len()
+        )),
+    )?;
+    Ok(report)
+}
+
+/// State shared by every file of one import
+struct ImportRun<'a> {
+    root_key: &'a str,
+    storage_dir: &'a Path,
+    store: StoreOptions,
+    journal: HashMap<String, JournalEntry>,
+}
+
+enum Outcome {
+    Added(String),
+    Skipped(SkipReason),
+}
+
+/// A file an earlier run finished with
+struct JournalEntry {
+    size: u64,
+    modified_ns: Option<i64>,
+    outcome: String,
+    file_id: String,
+}
+
+impl ImportRun<'_> {
+    /// Store one file unless the journal or the index already has it
+    fn import_one(
+        &self,
+        vault_conn: &mut Connection,
+        index_conn: &Connection,
+        path: &Path,
+        rel: &str,
+    ) -> Result<Outcome> {
+        let metadata = std::fs::metadata(path)?;
+        let size = metadata.len();
+        let modified_ns = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos() as i64);
+
+        if let Some(done) = self.journal.get(rel) {
+            let unchanged =
+                done.size == size && modified_ns.is_some() && done.modified_ns == modified_ns;
+            if unchanged && file_id_exists(index_conn, &done.file_id)? {
+                let file_id = done.file_id.clone();
+                return Ok(Outcome::Skipped(match done.outcome.as_str() {
+                    "duplicate" => SkipReason::Duplicate {
+                        existing_id: file_id,
+                    },
+                    _ => SkipReason::AlreadyImported { file_id },
+                }));
+            }
+        }
+
+        let (outcome, file_id) =
+            match store_file(path, self.storage_dir, vault_conn, index_conn, &self.store) {
+                Ok(entry) => {
+                    set_ref_relative_path(
+                        index_conn,
+                        &entry.file_id,
+                        &file_id::salt_path(path)?,
+                        rel,
+                    )?;
+                    ("added", entry.file_id)
+                }
+                Err(CoreError::DuplicateContent {
+                    existing_id,
+                    display_name,
+                }) => {
+                    // Record where this copy sits in the tree before the
+                    // journal can skip it on a re-run
+                    let source_path = file_id::salt_path(path)?;
+                    add_file_ref(index_conn, &existing_id, &source_path, &display_name)?;
+                    set_ref_relative_path(index_conn, &existing_id, &source_path, rel)?;
+                    ("duplicate", existing_id)
+                }
+                Err(e) => return Err(e),
+            };
+
+        index_conn.execute(
+            "INSERT OR REPLACE INTO import_journal \
+             (root, relative_path, size, modified_ns, outcome, file_id) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                self.root_key,
+                rel,
+                size as i64,
+                modified_ns,
+                outcome,
+                &file_id
+            ],
+        )?;
+        Ok(match outcome {
+            "added" => Outcome::Added(file_id),
+            _ => Outcome::Skipped(SkipReason::Duplicate {
+                existing_id: file_id,
+            }),
+        })
+    }
+}
+
+fn load_journal(index_conn: &Connection, root_key: &str) -> Result<HashMap<String, JournalEntry>> {
+    let mut stmt = index_conn.prepare(
+        "SELECT relative_path, size, modified_ns, outcome, file_id FROM import_journal \
+         WHERE root = ?1",
+    )?;
+    let rows = stmt.query_map([root_key], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            JournalEntry {
+                size: row.get::<_, i64>(1)? as u64,
+                modified_ns: row.get(2)?,
+                outcome: row.get(3)?,
+                file_id: row.get(4)?,
+            },
+        ))
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| CoreError::Config(format!("invalid glob {pattern:?}: {e}")))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| CoreError::Config(format!("invalid globs: {e}")))
+}
+
+/// Best guess at which path a walk error is about
+fn error_path(root: &Path, err: &ignore::Error) -> String {
+    match err {
+        ignore::Error::WithPath { path, .. } => {
+            relative_path(root, path).unwrap_or_else(|| path.display().to_string())
+        }
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_path(root, err)
+        }
+        ignore::Error::Loop { child, .. } => {
+            relative_path(root, child).unwrap_or_else(|| child.display().to_string())
+        }
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,365 @@
+// src/import/json.rs
+//! Restoring a JSON export (`ImportSource::BackupFile`)
+//!
+//! Reads the `encrypted-file-vault-v1` document written by
+//! [`export_to_json`](crate::export::export_to_json) and rebuilds each
+//! entry's `keys` / `key_history` and `files` rows, with its tags and note.
+//! The export holds keys and metadata only: ciphertext is expected at each
+//! entry's exported `current_path`.
+//!
+//! With verification on, every key is checked by decrypting its ciphertext
+//! and hashing the plaintext. That also lets an entry exported from a vault
+//! with a different file-id secret (say, after vault.db itself was lost) be
+//! re-keyed to this vault's ids and content hashes. Unverified entries are
+//! restored exactly as exported, so verification is skipped only when the
+//! export's `file_id_secret_check` shows it came from a vault with the same
+//! id secret; otherwise every entry is verified regardless.
+
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use rusqlite::{params, Connection};
+use secure_gate::SecureConversionsExt;
+use serde::Deserialize;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::aliases::FilePassword;
+use crate::atomic_write::write_atomic;
+use crate::consts::DEFAULT_ID_LENGTH_HEX;
+use crate::db::index_db_ops::{
+    add_file_ref, file_id_exists, get_file_entry, get_trash_entry, path_in_use, restore_from_trash,
+    store_file_entry, FileEntry,
+};
+use crate::db::tag_ops;
+use crate::db::vault_db_ops::{record_audit, store_key_version};
+use crate::enums::{ConflictPolicy, FilenameStyle, ImportSource};
+use crate::error::CoreError;
+use crate::export::json::JSON_EXPORT_FORMAT;
+use crate::file_id::FileIdHasher;
+use crate::file_ops::decrypt_stream;
+use crate::import::{FailedFile, ImportReport, ImportedFile, SkipReason, SkippedFile};
+use crate::key_ops::Key;
+use crate::naming::{self, NamingPolicy};
+use crate::stream::HashingWriter;
+use crate::Result;
+
+/// `key_history` note on every restored key
+const RESTORED_NOTE: &str = "restored";
+
+/// How [`import_from_json`] restores entries
+///
+/// `ConflictPolicy::Overwrite` always verifies: the restored key becomes
+/// the entry's current one, and the ciphertext it replaces is deleted
+/// unless the export points at the same file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonImportOptions {
+    pub conflict: ConflictPolicy,
+    /// Decrypt each entry's ciphertext with its key before restoring it;
+    /// forced on for `Overwrite` and for exports from a vault with another
+    /// id secret
+    pub verify: bool,
+}
+
+impl Default for JsonImportOptions {
+    fn default() -> Self {
+        Self {
+            conflict: ConflictPolicy::default(),
+            verify: true,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonExport {
+    export_format: String,
+    /// `FileIdHasher::secret_check` of the exporting vault; absent in older exports
+    #[serde(default)]
+    file_id_secret_check: Option<String>,
+    total_files: Option<usize>,
+    files: Vec<ExportedFile>,
+}
+
+#[derive(Deserialize)]
+struct ExportedFile {
+    file_id: String,
+    content_hash: String,
+    display_name: String,
+    current_path: PathBuf,
+    plaintext_size_bytes: u64,
+    password_base64url: String,
+    created_at: Option<String>,
+    rotated_at: Option<String>,
+    #[serde(default)]
+    tags: Option<ExportedTags>,
+    note: Option<String>,
+    filename_style: Option<String>,
+    id_length_hex_chars: Option<u64>,
+}
+
+impl Drop for ExportedFile {
+    fn drop(&mut self) {
+        self.password_base64url.zeroize();
+    }
+}
+
+/// Tags are an array since normalized tags; older exports wrote the
+/// comma-separated column
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExportedTags {
+    List(Vec<String>),
+    Text(String),
+}
+
+/// Restore every entry of the JSON export at `path`
+///
+/// Fails with `CoreError::InvalidBackup` if the document is not a complete
+/// `encrypted-file-vault-v1` export; nothing is restored then. Problems
+/// with single entries (bad key, missing or undecryptable ciphertext) end
+/// up in [`ImportReport::failed`], and only database errors abort part-way.
+pub fn import_from_json<P: AsRef<Path>>(
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+    path: P,
+    options: &JsonImportOptions,
+) -> Result<ImportReport> {
+    let path = path.as_ref();
+    let raw = Zeroizing::new(std::fs::read(path)?);
+    restore_document(vault_conn, index_conn, &raw, path, "json-restore", options)
+}
+
+/// Restore the export document `raw`, read from `path`
+pub(crate) fn restore_document(
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+    raw: &[u8],
+    path: &Path,
+    audit_action: &str,
+    options: &JsonImportOptions,
+) -> Result<ImportReport> {
+    let export: JsonExport =
+        serde_json::from_slice(raw).map_err(|e| CoreError::InvalidBackup(e.to_string()))?;
+
+    if export.export_format != JSON_EXPORT_FORMAT {
+        return Err(CoreError::InvalidBackup(format!(
+            "unsupported export format {:?} (expected {JSON_EXPORT_FORMAT:?})",
+            export.export_format
+        )));
+    }
+    if let Some(total) = export.total_files {
+        if total != export.files.len() {
+            return Err(CoreError::InvalidBackup(format!(
+                "export lists {total} files but contains {} — truncated?",
+                export.files.len()
+            )));
+        }
+    }
+
+    let hasher = FileIdHasher::load(vault_conn)?;
+    // Exported ids and content hashes are only valid under the same secret,
+    // and an overwrite must not make an unchecked key current
+    let options = &JsonImportOptions {
+        verify: options.verify
+            || options.conflict == ConflictPolicy::Overwrite
+            || export.file_id_secret_check != hasher.secret_check(),
+        ..*options
+    };
+    let mut report = ImportReport::new(ImportSource::BackupFile);
+    for file in &export.files {
+        match restore_entry(vault_conn, index_conn, &hasher, file, options) {
+            Ok(Restored::Added(file_id)) => report.added.push(ImportedFile {
+                relative_path: String::new(),
+                source_id: Some(file.file_id.clone()),
+                file_id,
+            }),
+            Ok(Restored::Conflict(existing_id)) => report.skipped.push(SkippedFile {
+                relative_path: String::new(),
+                source_id: Some(file.file_id.clone()),
+                reason: SkipReason::Conflict { existing_id },
+            }),
+            Err(e @ CoreError::Sql(_)) => return Err(e),
+            Err(error) => report.failed.push(FailedFile {
+                relative_path: String::new(),
+                source_id: Some(file.file_id.clone()),
+                error,
+            }),
+        }
+    }
+
+    record_audit(
+        vault_conn,
+        audit_action,
+        Some(&format!(
+            "{}: {} restored, {} skipped, {} failed",
+            path.display(),
+            report.added.len(),
+            report.skipped.len(),
+            report.failed.len()
+        )),
+    )?;
+    Ok(report)
+}
+
+enum Restored {
+    Added(String),
+    /// Skipped: the vault already has this id
+    Conflict(String),
+}
+
+fn restore_entry(
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+    hasher: &FileIdHasher,
+    file: &ExportedFile,
+    options: &JsonImportOptions,
+) -> Result<Restored> {
+    let key = decode_key(file)?;
+    let tags = exported_tags(file)?;
+    let filename_style = match &file.filename_style {
+        Some(style) => style
+            .parse::<FilenameStyle>()
+            .map_err(|_| CoreError::InvalidBackup(format!("unknown filename style {style:?}")))?,
+        None => FilenameStyle::default(),
+    };
+
+    let mut entry = FileEntry {
+        file_id: file.file_id.clone(),
+        content_hash: file.content_hash.clone(),
+        display_name: file.display_name.clone(),
+        current_path: file.current_path.clone(),
+        plaintext_size: file.plaintext_size_bytes,
+        filename_style: filename_style.as_str().to_string(),
+        id_length_hex: file
+            .id_length_hex_chars
+            .unwrap_or(DEFAULT_ID_LENGTH_HEX as u64),
+        salted_with_path: false,
+        known_password_hex: None,
+    };
+
+    let mut plaintext_hash = None;
+    if options.verify {
+        let password = FilePassword::new(key.expose_secret().to_hex());
+        let mut output = HashingWriter::new(io::sink());
+        entry.plaintext_size =
+            decrypt_stream(File::open(&file.current_path)?, &mut output, &password)?;
+        let hash = output.hash_hex();
+
+        // Exported from a vault with another id secret (or plain ids)
+        let content_hash = hasher.content_hash(&hash);
+        if content_hash != entry.content_hash {
+            entry.file_id = hasher.file_id(&hash, None);
+            entry.content_hash = content_hash;
+        }
+        plaintext_hash = Some(hash);
+    }
+
+    let mut replaced = None;
+    if file_id_exists(index_conn, &entry.file_id)? {
+        match options.conflict {
+            ConflictPolicy::Skip => return Ok(Restored::Conflict(entry.file_id)),
+            ConflictPolicy::Overwrite => {
+                // Same id, so its references and tags stay; a trashed entry comes back
+                let existing = match get_file_entry(index_conn, &entry.file_id)? {
+                    Some(existing) => existing,
+                    None => get_trash_entry(index_conn, &entry.file_id)?
+                        .ok_or_else(|| CoreError::NotFound(entry.file_id.clone()))?,
+                };
+                restore_from_trash(index_conn, &entry.file_id)?;
+                entry.salted_with_path = existing.salted_with_path;
+                replaced = Some(existing.current_path);
+            }
+            ConflictPolicy::KeepBoth => {
+                keep_both(index_conn, hasher, &mut entry, plaintext_hash.as_deref())?;
+            }
+        }
+    }
+
+    store_key_version(vault_conn, &entry.file_id, &key, Some(RESTORED_NOTE))?;
+    store_file_entry(index_conn, &entry)?;
+    if let Some(old_path) = replaced {
+        // The overwritten ciphertext, unless the export points at it too
+        if old_path != entry.current_path && !path_in_use(index_conn, &old_path)? {
+            match std::fs::remove_file(&old_path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+    }
+    index_conn.execute(
+        "UPDATE files SET created_at = COALESCE(?2, created_at), rotated_at = ?3, note = ?4 \
+         WHERE file_id = ?1",
+        params![entry.file_id, file.created_at, file.rotated_at, file.note],
+    )?;
+    let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+    tag_ops::add_tags(index_conn, &entry.file_id, &tags)?;
+    add_file_ref(index_conn, &entry.file_id, "", &entry.display_name)?;
+
+    Ok(Restored::Added(entry.file_id))
+}
+
+/// Give `entry` a fresh id and its own copy of the ciphertext, so it never
+/// shares a file with the entry it conflicts with
+fn keep_both(
+    index_conn: &Connection,
+    hasher: &FileIdHasher,
+    entry: &mut FileEntry,
+    plaintext_hash: Option<&str>,
+) -> Result<()> {
+    if !hasher.is_keyed() {
+        return Err(CoreError::PolicyViolation(
+            "keeping both copies needs keyed file ids — open the vault with Vault::open to upgrade"
+                .into(),
+        ));
+    }
+    let base = plaintext_hash.unwrap_or(&entry.content_hash);
+    let salt = format!("restored#{}", hex::encode(rand::random::<[u8; 8]>()));
+    entry.file_id = hasher.file_id(base, Some(&salt));
+    entry.salted_with_path = true;
+
+    let dir = entry
+        .current_path
+        .parent()
+        .unwrap_or(Path::new(""))
+        .to_path_buf();
+    let policy = NamingPolicy::default();
+    let copy = naming::allocate_name(
+        index_conn,
+        &dir,
+        &entry.display_name,
+        &entry.file_id,
+        &policy,
+    )?;
+    let mut source = File::open(&entry.current_path)?;
+    write_atomic(&copy.path, |output| io::copy(&mut source, output))?;
+
+    entry.current_path = copy.path;
+    entry.filename_style = policy.style.as_str().to_string();
+    entry.id_length_hex = copy.id_length_hex;
+    Ok(())
+}
+
+fn decode_key(file: &ExportedFile) -> Result<Key> {
+    let bytes = Zeroizing::new(
+        URL_SAFE_NO_PAD
+            .decode(file.password_base64url.trim())
+            .map_err(|e| CoreError::InvalidBackup(format!("key of {}: {e}", file.file_id)))?,
+    );
+    let key = <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| {
+        CoreError::InvalidBackup(format!("key of {} is not 32 bytes", file.file_id))
+    })?;
+    Ok(Key::new(key))
+}
+
+fn exported_tags(file: &ExportedFile) -> Result<Vec<String>> {
+    let tags: Vec<String> = match &file.tags {
+        None => Vec::new(),
+        Some(ExportedTags::List(tags)) => tags.clone(),
+        Some(ExportedTags::Text(text)) => text.split(',').map(str::to_owned).collect(),
+    };
+    tags.iter()
+        .filter(|t| !t.trim().is_empty())
+        .map(|t| tag_ops::normalize_tag(t).map(str::to_owned))
+        .collect()
+}
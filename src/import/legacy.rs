@@ -0,0 +1,203 @@
+// src/import/legacy.rs
+//! Legacy AES Crypt ingestion (`ImportSource::LegacyAescrypt`)
+//!
+//! Takes one AES Crypt file or a tree of them, plus the passwords they may
+//! be under, and brings each into the vault with [`store_legacy_file`]:
+//! upgraded to v3 under a fresh key in one streaming pass that also hashes
+//! the plaintext, keyed in `key_history` with the note "legacy-upgrade",
+//! and indexed. Files that are not AES Crypt are skipped; one that none of
+//! the passwords opens fails with `CoreError::WrongPassword`.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use ignore::WalkBuilder;
+use rusqlite::Connection;
+
+use crate::aliases::FilePassword;
+use crate::db::vault_db_ops::{extract_to_writer, record_audit, store_legacy_file, StoreOptions};
+use crate::enums::{DedupPolicy, ImportSource, OriginalsPolicy};
+use crate::error::CoreError;
+use crate::file_ops::is_aescrypt_file;
+use crate::import::{
+    relative_path, FailedFile, ImportReport, ImportedFile, OwnFiles, SkipReason, SkippedFile,
+};
+use crate::Result;
+
+/// Upgrade and store the AES Crypt file at `path`, or every one under it
+///
+/// Content already in the vault is skipped. With
+/// [`OriginalsPolicy::Remove`], an original (added or skipped as a
+/// duplicate) is deleted only after the vault copy has been decrypted and
+/// matched against its content hash. Per-file problems end up in
+/// [`ImportReport::failed`]; only database errors abort the import.
+pub fn import_legacy(
+    vault_conn: &mut Connection,
+    index_conn: &Connection,
+    path: &Path,
+    storage_dir: &Path,
+    store: &StoreOptions,
+    passwords: &[FilePassword],
+    originals: OriginalsPolicy,
+) -> Result<ImportReport> {
+    let path = path.canonicalize()?;
+    let own = OwnFiles::of(vault_conn, index_conn, storage_dir)?;
+    let run = LegacyRun {
+        storage_dir,
+        store: StoreOptions {
+            dedup: DedupPolicy::Reject,
+            ..*store
+        },
+        passwords,
+        originals,
+    };
+
+    let mut report = ImportReport::new(ImportSource::LegacyAescrypt);
+    if path.is_dir() {
+        let mut walker = WalkBuilder::new(&path);
+        walker
+            .standard_filters(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| !own.contains(entry.path()));
+
+        for result in walker.build() {
+            let entry = match result {
+                Ok(entry) => entry,
+                Err(err) => {
+                    report.failed.push(FailedFile {
+                        relative_path: String::new(),
+                        source_id: None,
+                        error: CoreError::Io(io::Error::other(err)),
+                    });
+                    continue;
+                }
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let rel = relative_path(&path, entry.path())
+                .unwrap_or_else(|| entry.path().to_string_lossy().into_owned());
+            run.import_one(vault_conn, index_conn, entry.path(), rel, &mut report)?;
+        }
+    } else {
+        let rel = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        run.import_one(vault_conn, index_conn, &path, rel, &mut report)?;
+    }
+
+    record_audit(
+        vault_conn,
+        "legacy-import",
+        Some(&format!(
+            "{}: {} added, {} skipped, {} failed, {} originals removed",
+            path.display(),
+            report.added.len(),
+            report.skipped.len(),
+            report.failed.len(),
+            report.removed_sources.len()
+        )),
+    )?;
+    Ok(report)
+}
+
+/// Settings shared by every file of one legacy import
+struct LegacyRun<'a> {
+    storage_dir: &'a Path,
+    store: StoreOptions,
+    passwords: &'a [FilePassword],
+    originals: OriginalsPolicy,
+}
+
+impl LegacyRun<'_> {
+    /// Import one file into `report`; only database errors are returned
+    fn import_one(
+        &self,
+        vault_conn: &mut Connection,
+        index_conn: &Connection,
+        file: &Path,
+        rel: String,
+        report: &mut ImportReport,
+    ) -> Result<()> {
+        match has_aescrypt_magic(file) {
+            Ok(true) => {}
+            Ok(false) => {
+                report.skipped.push(SkippedFile {
+                    relative_path: rel,
+                    source_id: None,
+                    reason: SkipReason::NotAescrypt,
+                });
+                return Ok(());
+            }
+            Err(error) => {
+                report.failed.push(FailedFile {
+                    relative_path: rel,
+                    source_id: None,
+                    error,
+                });
+                return Ok(());
+            }
+        }
+
+        let stored = store_legacy_file(
+            file,
+            self.storage_dir,
+            vault_conn,
+            index_conn,
+            &self.store,
+            self.passwords,
+        );
+        let file_id = match stored {
+            Ok(entry) => {
+                report.added.push(ImportedFile {
+                    relative_path: rel.clone(),
+                    source_id: None,
+                    file_id: entry.file_id.clone(),
+                });
+                entry.file_id
+            }
+            Err(CoreError::DuplicateContent { existing_id, .. }) => {
+                report.skipped.push(SkippedFile {
+                    relative_path: rel.clone(),
+                    source_id: None,
+                    reason: SkipReason::Duplicate {
+                        existing_id: existing_id.clone(),
+                    },
+                });
+                existing_id
+            }
+            Err(e @ CoreError::Sql(_)) => return Err(e),
+            Err(error) => {
+                report.failed.push(FailedFile {
+                    relative_path: rel,
+                    source_id: None,
+                    error,
+                });
+                return Ok(());
+            }
+        };
+
+        if self.originals == OriginalsPolicy::Remove {
+            let removed = extract_to_writer(vault_conn, index_conn, &file_id, io::sink())
+                .and_then(|_| Ok(std::fs::remove_file(file)?));
+            match removed {
+                Ok(()) => report.removed_sources.push(rel),
+                Err(e @ CoreError::Sql(_)) => return Err(e),
+                Err(error) => report.failed.push(FailedFile {
+                    relative_path: rel,
+                    source_id: None,
+                    error,
+                }),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn has_aescrypt_magic(file: &Path) -> Result<bool> {
+    let mut magic = Vec::with_capacity(4);
+    File::open(file)?.take(4).read_to_end(&mut magic)?;
+    Ok(is_aescrypt_file(&magic))
+}
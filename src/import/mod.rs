@@ -0,0 +1,136 @@
+// src/import/mod.rs
+//! Bringing existing files into the vault
+//!
+//! Every importer into an open vault returns an [`ImportReport`] listing
+//! what was added, skipped and failed, so one bad file never aborts a whole
+//! import. [`portable`] instead unpacks a whole vault into a new directory.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+
+use crate::enums::ImportSource;
+use crate::error::CoreError;
+use crate::Result;
+
+pub mod backup;
+pub mod directory;
+pub mod json;
+pub mod legacy;
+pub mod portable;
+
+pub use backup::import_encrypted_backup;
+pub use directory::{import_directory, DirectoryImportOptions};
+pub use json::{import_from_json, JsonImportOptions};
+pub use legacy::import_legacy;
+pub use portable::{import_portable, PortableImport, PortableKey};
+
+/// Outcome of an import, file by file
+#[derive(Debug)]
+pub struct ImportReport {
+    pub source: ImportSource,
+    pub added: Vec<ImportedFile>,
+    pub skipped: Vec<SkippedFile>,
+    pub failed: Vec<FailedFile>,
+    /// Source files deleted after import (`OriginalsPolicy::Remove`)
+    pub removed_sources: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn new(source: ImportSource) -> Self {
+        Self {
+            source,
+            added: Vec::new(),
+            skipped: Vec::new(),
+            failed: Vec::new(),
+            removed_sources: Vec::new(),
+        }
+    }
+
+    /// Every file was added or skipped
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// A file now stored in the vault
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedFile {
+    /// Its `/`-separated path inside the imported directory; empty for
+    /// entries restored from a backup
+    pub relative_path: String,
+    /// Its id in the exported vault, for entries restored from a backup
+    pub source_id: Option<String>,
+    pub file_id: String,
+}
+
+/// A file left out, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedFile {
+    pub relative_path: String,
+    pub source_id: Option<String>,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The vault already holds this content
+    Duplicate { existing_id: String },
+    /// Added by an earlier, interrupted run of the same import
+    AlreadyImported { file_id: String },
+    /// Not an AES Crypt file
+    NotAescrypt,
+    /// A backed-up entry whose id the vault already has (`ConflictPolicy::Skip`)
+    Conflict { existing_id: String },
+}
+
+/// A file that could not be imported
+#[derive(Debug)]
+pub struct FailedFile {
+    pub relative_path: String,
+    pub source_id: Option<String>,
+    pub error: CoreError,
+}
+
+/// The vault's storage directory and database files, which an import skips
+struct OwnFiles {
+    storage_dir: PathBuf,
+    databases: Vec<PathBuf>,
+}
+
+impl OwnFiles {
+    fn of(vault_conn: &Connection, index_conn: &Connection, storage_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(storage_dir)?;
+        Ok(Self {
+            storage_dir: storage_dir.canonicalize()?,
+            databases: [vault_conn.path(), index_conn.path()]
+                .into_iter()
+                .flatten()
+                .filter(|p| !p.is_empty())
+                .map(|p| Path::new(p).canonicalize().unwrap_or_else(|_| p.into()))
+                .collect(),
+        })
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        if path.starts_with(&self.storage_dir) {
+            return true;
+        }
+        // The database itself and its -wal / -journal / backup siblings
+        let path = path.to_string_lossy();
+        self.databases
+            .iter()
+            .any(|db| path.starts_with(db.to_string_lossy().as_ref()))
+    }
+}
+
+/// `path` relative to `root`, `/`-separated; `None` if not valid UTF-8
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let parts = path
+        .strip_prefix(root)
+        .ok()?
+        .components()
+        .map(|c| c.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(parts.join("/"))
+}
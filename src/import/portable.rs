@@ -0,0 +1,304 @@
+// src/import/portable.rs
+//! Unpacking a portable vault archive
+//!
+//! Reverses [`export_portable`](crate::export::export_portable): every
+//! member is streamed into a staging directory inside the destination and
+//! checked against the manifest's BLAKE3 as it is written. Only once the
+//! whole archive has checked out is each entry's `current_path` rewritten
+//! (in the staged index.db) to its new home under `<dest>/files`, and the
+//! databases and ciphertext moved into place. Both staged databases are
+//! unlocked first, so a bad archive or a wrong key leaves the destination as
+//! it was.
+//!
+//! Ciphertext keeps the file name it had in the exporting vault where it
+//! is free, and its content-addressed name otherwise.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use rusqlite::params;
+use tar::{Archive, EntryType};
+
+use crate::aliases::{IndexKey32, UserPassphrase, VaultKey32};
+use crate::atomic_write::{rename_durable, ATOMIC_TEMP_PREFIX};
+use crate::consts::{
+    INDEX_DB_FILE_NAME, STORAGE_DIR_NAME, VAULT_DB_FILE_NAME, VAULT_HEADER_FILE_NAME,
+};
+use crate::db::index_db_conn::open_index_db_at;
+use crate::db::keying;
+use crate::error::CoreError;
+use crate::export::portable::{Manifest, MANIFEST_NAME, OBJECTS_DIR, PORTABLE_FORMAT};
+use crate::stream::HashingWriter;
+use crate::unlock::VaultHeader;
+use crate::Result;
+
+/// Largest manifest read into memory
+const MAX_MANIFEST_LEN: u64 = 64 * 1024 * 1024;
+
+/// Prefix of the staging directory inside the destination
+const STAGING_PREFIX: &str = ".efv-portable-";
+
+/// Members other than ciphertext, in the order they are moved into place:
+/// vault.db last, so once it is there the vault is complete
+const VAULT_FILES: [&str; 3] = [
+    VAULT_HEADER_FILE_NAME,
+    INDEX_DB_FILE_NAME,
+    VAULT_DB_FILE_NAME,
+];
+
+/// Keys that open the archived databases
+#[derive(Clone, Copy)]
+pub enum PortableKey<'a> {
+    /// Raw keys of a vault opened with `Vault::open`
+    Raw {
+        vault_key: &'a VaultKey32,
+        index_key: &'a IndexKey32,
+    },
+    /// Passphrase of a vault with a `vault-header.json`
+    Passphrase(&'a UserPassphrase),
+}
+
+/// Outcome of [`import_portable`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortableImport {
+    /// Where the databases now are
+    pub dest_dir: PathBuf,
+    /// Each entry's new `current_path`, by file id (live and trashed)
+    pub relocated: Vec<(String, PathBuf)>,
+}
+
+/// Unpack the portable archive at `archive` into `dest_dir`
+///
+/// `dest_dir` is created if needed and must not already hold a vault.
+/// A member that is missing, unexpected or fails its checksum is reported
+/// as `CoreError::InvalidBackup`, and keys that do not unlock the archived
+/// vault.db as `CoreError::WrongPassword`. Open the result with `Vault::open` or
+/// `Vault::unlock` — or use `Vault::import_portable`, which does both.
+pub fn import_portable(
+    archive: &Path,
+    dest_dir: &Path,
+    key: PortableKey<'_>,
+) -> Result<PortableImport> {
+    fs::create_dir_all(dest_dir)?;
+    let dest_dir = dest_dir.canonicalize()?;
+    for name in VAULT_FILES {
+        if dest_dir.join(name).exists() {
+            return Err(CoreError::Config(format!(
+                "a vault already exists in {}",
+                dest_dir.display()
+            )));
+        }
+    }
+
+    // Removed on any early return
+    let staging = tempfile::Builder::new()
+        .prefix(STAGING_PREFIX)
+        .tempdir_in(&dest_dir)?;
+    let manifest = unpack(archive, staging.path())?;
+
+    let storage_dir = dest_dir.join(STORAGE_DIR_NAME);
+    let placed = place_objects(&manifest, &storage_dir)?;
+    let relocated: Vec<(String, PathBuf)> = manifest
+        .entries
+        .iter()
+        .map(|entry| (entry.file_id.clone(), placed[&entry.object].clone()))
+        .collect();
+
+    let (vault_key, index_key) = match key {
+        PortableKey::Raw {
+            vault_key,
+            index_key,
+        } => (
+            VaultKey32::new(*vault_key.expose_secret()),
+            IndexKey32::new(*index_key.expose_secret()),
+        ),
+        PortableKey::Passphrase(passphrase) => {
+            VaultHeader::load(staging.path())?.derive_keys(passphrase)?
+        }
+    };
+    if !keying::opens_with(
+        &staging.path().join(VAULT_DB_FILE_NAME),
+        vault_key.expose_secret(),
+    ) {
+        return Err(CoreError::WrongPassword);
+    }
+    relocate(
+        &staging.path().join(INDEX_DB_FILE_NAME),
+        &index_key,
+        &relocated,
+    )?;
+
+    fs::create_dir_all(&storage_dir)?;
+    for (object, target) in &placed {
+        rename_durable(staging.path().join(object), target)?;
+    }
+    for name in VAULT_FILES {
+        let staged = staging.path().join(name);
+        if staged.exists() {
+            rename_durable(staged, dest_dir.join(name))?;
+        }
+    }
+
+    Ok(PortableImport {
+        dest_dir,
+        relocated,
+    })
+}
+
+/// Stream every member into `staging`, checking it against the manifest
+fn unpack(archive: &Path, staging: &Path) -> Result<Manifest> {
+    let mut archive = Archive::new(File::open(archive)?);
+    let mut members = archive.entries()?;
+
+    let mut first = members
+        .next()
+        .ok_or_else(|| CoreError::InvalidBackup("empty archive".into()))??;
+    if first.path_bytes().as_ref() != MANIFEST_NAME.as_bytes() {
+        return Err(CoreError::InvalidBackup(format!(
+            "archive does not start with {MANIFEST_NAME}"
+        )));
+    }
+    let mut raw = Vec::new();
+    (&mut first).take(MAX_MANIFEST_LEN).read_to_end(&mut raw)?;
+    let manifest: Manifest =
+        serde_json::from_slice(&raw).map_err(|e| CoreError::InvalidBackup(e.to_string()))?;
+    check_manifest(&manifest)?;
+
+    fs::create_dir_all(staging.join(OBJECTS_DIR))?;
+    let mut pending: BTreeMap<&str, &str> = manifest
+        .members
+        .iter()
+        .map(|(name, hash)| (name.as_str(), hash.as_str()))
+        .collect();
+
+    for member in members {
+        let mut member = member?;
+        let name = String::from_utf8_lossy(&member.path_bytes()).into_owned();
+        let Some(expected) = pending.remove(name.as_str()) else {
+            return Err(CoreError::InvalidBackup(format!(
+                "unexpected or repeated member {name:?}"
+            )));
+        };
+        if member.header().entry_type() != EntryType::Regular {
+            return Err(CoreError::InvalidBackup(format!(
+                "{name} is not a regular file"
+            )));
+        }
+
+        let mut output = HashingWriter::new(File::create(staging.join(&name))?);
+        io::copy(&mut member, &mut output)?;
+        if output.hash_hex() != expected {
+            return Err(CoreError::InvalidBackup(format!(
+                "checksum mismatch for {name}"
+            )));
+        }
+        output.into_inner().sync_all()?;
+    }
+
+    if let Some(name) = pending.keys().next() {
+        return Err(CoreError::InvalidBackup(format!(
+            "archive is missing {name}"
+        )));
+    }
+    Ok(manifest)
+}
+
+/// Refuse unknown formats and member names that could escape the staging dir
+fn check_manifest(manifest: &Manifest) -> Result<()> {
+    if manifest.format != PORTABLE_FORMAT {
+        return Err(CoreError::InvalidBackup(format!(
+            "unsupported archive format {:?} (expected {PORTABLE_FORMAT:?})",
+            manifest.format
+        )));
+    }
+    for name in [VAULT_DB_FILE_NAME, INDEX_DB_FILE_NAME] {
+        if !manifest.members.contains_key(name) {
+            return Err(CoreError::InvalidBackup(format!(
+                "archive is missing {name}"
+            )));
+        }
+    }
+    for (name, hash) in &manifest.members {
+        let known = VAULT_FILES.contains(&name.as_str())
+            || name
+                .strip_prefix(OBJECTS_DIR)
+                .and_then(|rest| rest.strip_prefix('/'))
+                .is_some_and(|object| object == hash && is_blake3_hex(object));
+        if !known {
+            return Err(CoreError::InvalidBackup(format!(
+                "unexpected member {name:?} in manifest"
+            )));
+        }
+    }
+    for entry in &manifest.entries {
+        if !entry.object.starts_with(OBJECTS_DIR) || !manifest.members.contains_key(&entry.object) {
+            return Err(CoreError::InvalidBackup(format!(
+                "entry {} points at unknown object {:?}",
+                entry.file_id, entry.object
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn is_blake3_hex(text: &str) -> bool {
+    text.len() == 64 && text.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Final path of every object: its old file name if free, else `<blake3>.aes`
+fn place_objects(manifest: &Manifest, storage_dir: &Path) -> Result<HashMap<String, PathBuf>> {
+    let mut placed = HashMap::new();
+    let mut taken = HashSet::new();
+    for entry in &manifest.entries {
+        if placed.contains_key(&entry.object) {
+            continue;
+        }
+        let hash = &entry.object[OBJECTS_DIR.len() + 1..];
+        let fallback = format!("{hash}.aes");
+        let name = [entry.name.as_str(), fallback.as_str()]
+            .into_iter()
+            .find(|name| {
+                is_plain_file_name(name)
+                    && !taken.contains(*name)
+                    && !storage_dir.join(name).exists()
+            })
+            .ok_or_else(|| {
+                CoreError::Config(format!(
+                    "{} already holds a file named {fallback}",
+                    storage_dir.display()
+                ))
+            })?;
+        taken.insert(name.to_string());
+        placed.insert(entry.object.clone(), storage_dir.join(name));
+    }
+    Ok(placed)
+}
+
+fn is_plain_file_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.starts_with(ATOMIC_TEMP_PREFIX)
+        && Path::new(name).file_name() == Some(name.as_ref())
+}
+
+/// Point every entry of the staged index at its new ciphertext path
+fn relocate(index_db: &Path, key: &IndexKey32, relocated: &[(String, PathBuf)]) -> Result<()> {
+    let conn = open_index_db_at(index_db, key)?;
+    let tx = conn.unchecked_transaction()?;
+    for (file_id, path) in relocated {
+        let path = path
+            .to_str()
+            .ok_or_else(|| CoreError::InvalidPath(path.clone()))?;
+        for table in ["files", "trash"] {
+            tx.execute(
+                &format!("UPDATE {table} SET current_path = ?2 WHERE file_id = ?1"),
+                params![file_id, path],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
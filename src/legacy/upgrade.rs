@@ -1,28 +1,81 @@
 // src/legacy/upgrade.rs
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{self, BufReader, BufWriter, Read, Write};
 use std::path::Path;
 
 use crate::aliases::{
     FileKey32, FilePassword, RandomFileKey32, SecureConversionsExt, SecureRandomExt,
 };
+use crate::atomic_write::write_atomic;
+use crate::crypto::decrypt_checked;
+use crate::error::CoreError;
+use crate::file_ops::encrypt_stream;
+use crate::key_ops::generate_key;
+use crate::stream::HashingReader;
 use aescrypt_rs::convert::convert_to_v3_ext;
 use aescrypt_rs::AescryptError;
 
 /// Upgrade a legacy AES-Crypt v0–v2 file → v3 using a fresh random key
+///
+/// The v3 output is written atomically; on failure `output_path` is untouched.
 pub fn upgrade_from_legacy(
     input_path: &Path,
     output_path: &Path,
     legacy_password: &FilePassword,
 ) -> Result<FileKey32, AescryptError> {
     let input = BufReader::new(File::open(input_path)?);
-    let output = BufWriter::new(File::create(output_path)?);
 
     let random_key = RandomFileKey32::new();
     let new_password = FilePassword::new(random_key.expose_secret().to_hex());
     let new_key = FileKey32::new(**random_key);
 
-    convert_to_v3_ext(input, output, legacy_password, Some(&new_password), 600_000)?;
+    write_atomic(output_path, |file| {
+        let mut output = BufWriter::new(file);
+        convert_to_v3_ext(
+            input,
+            &mut output,
+            legacy_password,
+            Some(&new_password),
+            600_000,
+        )?;
+        output.flush()?;
+        Ok::<_, AescryptError>(())
+    })?;
 
     Ok(new_key)
 }
+
+/// Re-encrypt an AES Crypt stream (v0–v3) as v3 under a fresh random key,
+/// BLAKE3-hashing the plaintext on the way through
+///
+/// The decryptor runs on a worker thread and feeds the encryptor through a
+/// pipe, so the plaintext is never held whole. Returns the new key, the
+/// plaintext size and its BLAKE3 hex. A failure part-way leaves a truncated
+/// `output` behind — write it with `write_atomic`.
+pub fn upgrade_stream_hashed<R: Read + Send + 'static, W: Write>(
+    input: R,
+    output: W,
+    legacy_password: &FilePassword,
+) -> Result<(FileKey32, u64, String), CoreError> {
+    let new_key = generate_key();
+    let new_password = FilePassword::new(new_key.expose_secret().to_hex());
+
+    let (plaintext_reader, plaintext_writer) = pipe::pipe();
+    let legacy_password = legacy_password.clone();
+    let decrypt_thread =
+        std::thread::spawn(move || decrypt_checked(input, plaintext_writer, &legacy_password));
+
+    let mut plaintext = HashingReader::new(plaintext_reader);
+    let encrypted = encrypt_stream(&mut plaintext, output, &new_password);
+    let plaintext_hash = plaintext.hash_hex();
+    // Unblocks the decryptor if the encryptor stopped reading early
+    drop(plaintext);
+
+    // A decrypt error (wrong password, corrupt input) explains any encrypt error
+    decrypt_thread
+        .join()
+        .map_err(|_| CoreError::Io(io::Error::other("decrypt worker panicked")))??;
+    let plaintext_size = encrypted?;
+
+    Ok((new_key, plaintext_size, plaintext_hash))
+}
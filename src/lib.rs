@@ -8,17 +8,24 @@
 //! - Full secure-gate v0.5.8 integration
 
 pub mod aliases;
+pub mod atomic_write;
 pub mod config;
 pub mod consts;
 pub mod crypto;
 pub mod db;
 pub mod enums;
 pub mod export;
+pub mod file_id;
 pub mod file_ops;
+pub mod import;
 pub mod key_ops;
 pub mod legacy;
+pub mod naming;
 pub mod rotation;
+pub mod stream;
+pub mod unlock;
 pub mod util;
+pub mod vault;
 
 // Optional: flatter access (recommended)
 pub use legacy::upgrade::upgrade_from_legacy;
@@ -36,8 +43,9 @@ pub use db::vault_db_ops::add_file;
 
 // pub use core::{PasswordRepr, Result as CoreResult};
 pub use error::CoreError;
-pub use export::export_to_json;
+pub use export::{export_encrypted_backup, export_to_json};
 pub use key_ops::PasswordRepr;
+pub use vault::Vault;
 // pub use key_ops::Result as CoreResult;
 
 pub use db::{index_db_conn, index_db_ops, vault_db_conn, vault_db_ops};
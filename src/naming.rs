@@ -0,0 +1,417 @@
+// src/naming.rs
+//! On-disk names for ciphertext files
+//!
+//! | Style    | Name                                  |
+//! |----------|---------------------------------------|
+//! | `human`  | `<stem>.<first N hex of id>.aes`      |
+//! | `hash`   | `<full 64-hex id>.aes`                |
+//! | `opaque` | `<32 random hex>.aes`                 |
+//!
+//! A `human` name whose short id is already taken by another file is retried
+//! with a longer prefix (4 hex at a time) until it is unique; the length used
+//! is what gets recorded in `files.id_length`.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::consts::{DEFAULT_ID_LENGTH_HEX, MAX_ID_LENGTH_HEX, MIN_ID_LENGTH_HEX};
+use crate::enums::FilenameStyle;
+use crate::error::CoreError;
+use crate::Result;
+
+/// Extension of every ciphertext file
+pub const CIPHERTEXT_EXTENSION: &str = "aes";
+
+/// Longest stem kept from the original filename
+const MAX_STEM_CHARS: usize = 96;
+
+/// Hex characters added per collision retry
+const ID_LENGTH_STEP: u64 = 4;
+
+/// `vault_meta` rows holding the saved [`NamingPolicy`]
+pub const NAMING_STYLE_META: &str = "naming_style";
+pub const NAMING_ID_LENGTH_META: &str = "naming_id_length";
+
+/// Which naming scheme new (or relaid-out) files get
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamingPolicy {
+    pub style: FilenameStyle,
+    /// Id prefix length for `human` names (8..=64)
+    pub id_length_hex: u64,
+}
+
+impl Default for NamingPolicy {
+    fn default() -> Self {
+        Self {
+            style: FilenameStyle::Human,
+            id_length_hex: DEFAULT_ID_LENGTH_HEX as u64,
+        }
+    }
+}
+
+impl NamingPolicy {
+    pub fn new(style: FilenameStyle, id_length_hex: u64) -> Result<Self> {
+        if !(MIN_ID_LENGTH_HEX..=MAX_ID_LENGTH_HEX).contains(&id_length_hex) {
+            return Err(CoreError::Config(format!(
+                "id length must be {MIN_ID_LENGTH_HEX}..={MAX_ID_LENGTH_HEX} hex characters, got {id_length_hex}"
+            )));
+        }
+        Ok(Self {
+            style,
+            id_length_hex,
+        })
+    }
+
+    /// The policy saved in `vault_meta`, or the default if none was saved
+    pub fn load(vault_conn: &Connection) -> Result<Self> {
+        let meta = |name: &str| {
+            vault_conn
+                .query_row(
+                    "SELECT value FROM vault_meta WHERE name = ?1",
+                    [name],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()
+        };
+        match (meta(NAMING_STYLE_META)?, meta(NAMING_ID_LENGTH_META)?) {
+            (Some(style), Some(id_length_hex)) => {
+                let id_length_hex = id_length_hex.parse().map_err(|_| {
+                    CoreError::Config(format!("invalid saved id length {id_length_hex:?}"))
+                })?;
+                Self::new(style.parse()?, id_length_hex)
+            }
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Save the policy in `vault_meta`, where [`NamingPolicy::load`] finds it
+    pub fn save(&self, vault_conn: &Connection) -> Result<()> {
+        let tx = vault_conn.unchecked_transaction()?;
+        for (name, value) in [
+            (NAMING_STYLE_META, self.style.as_str().to_string()),
+            (NAMING_ID_LENGTH_META, self.id_length_hex.to_string()),
+        ] {
+            tx.execute(
+                "INSERT OR REPLACE INTO vault_meta (name, value) VALUES (?1, ?2)",
+                params![name, value],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// A chosen on-disk name and the id length it encodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocatedName {
+    pub path: PathBuf,
+    pub id_length_hex: u64,
+}
+
+/// Pick a free path in `dir` for `file_id` under `policy`
+///
+/// A path counts as taken when another index entry (live or trashed) points
+/// at it, or when something unindexed already exists there. A path already
+/// recorded for `file_id` itself is free, so re-adding the same content or
+/// re-running a relayout is idempotent.
+pub fn allocate_name(
+    index_conn: &Connection,
+    dir: &Path,
+    display_name: &str,
+    file_id: &str,
+    policy: &NamingPolicy,
+) -> Result<AllocatedName> {
+    match policy.style {
+        FilenameStyle::Human => {
+            let stem = sanitized_stem(display_name);
+            let mut length = policy.id_length_hex;
+            loop {
+                let prefix = file_id.get(..length as usize).unwrap_or(file_id);
+                let path = dir.join(format!("{stem}.{prefix}.{CIPHERTEXT_EXTENSION}"));
+                if !path_taken(index_conn, &path, file_id)? {
+                    return Ok(AllocatedName {
+                        path,
+                        id_length_hex: length,
+                    });
+                }
+                if length >= MAX_ID_LENGTH_HEX {
+                    return Err(taken(&path));
+                }
+                length = (length + ID_LENGTH_STEP).min(MAX_ID_LENGTH_HEX);
+            }
+        }
+        FilenameStyle::Hash => {
+            let path = dir.join(format!("{file_id}.{CIPHERTEXT_EXTENSION}"));
+            if path_taken(index_conn, &path, file_id)? {
+                return Err(taken(&path));
+            }
+            Ok(AllocatedName {
+                path,
+                id_length_hex: file_id.len() as u64,
+            })
+        }
+        FilenameStyle::Opaque => loop {
+            let name = hex::encode(rand::random::<[u8; 16]>());
+            let path = dir.join(format!("{name}.{CIPHERTEXT_EXTENSION}"));
+            if !path_taken(index_conn, &path, file_id)? {
+                return Ok(AllocatedName {
+                    path,
+                    id_length_hex: 0,
+                });
+            }
+        },
+    }
+}
+
+/// Is `path` used by anything other than `file_id`?
+fn path_taken(index_conn: &Connection, path: &Path, file_id: &str) -> Result<bool> {
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| CoreError::InvalidPath(path.to_path_buf()))?;
+
+    let owner: Option<String> = index_conn
+        .query_row(
+            "SELECT file_id FROM files WHERE current_path = ?1 \
+             UNION ALL SELECT file_id FROM trash WHERE current_path = ?1 \
+             LIMIT 1",
+            [path_str],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(match owner {
+        Some(owner) => owner != file_id,
+        None => path.exists(),
+    })
+}
+
+fn taken(path: &Path) -> CoreError {
+    CoreError::PolicyViolation(format!(
+        "{} is already used by another file",
+        path.display()
+    ))
+}
+
+/// Filename stem safe on every platform; never empty
+fn sanitized_stem(display_name: &str) -> String {
+    let stem = Path::new(display_name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let cleaned: String = stem
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(MAX_STEM_CHARS)
+        .collect();
+
+    let cleaned = cleaned.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if cleaned.is_empty() {
+        "file".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// Outcome of [`relayout`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayoutReport {
+    /// Files renamed, including moves finished from an interrupted relayout
+    pub renamed: usize,
+    /// Files already named per the policy
+    pub unchanged: usize,
+    /// Ids whose ciphertext was not found on disk; left as they are
+    pub missing: Vec<String>,
+}
+
+/// Rename every indexed ciphertext (live and trashed) to match `policy`
+///
+/// Files stay in their current directory; `current_path`, `filename_style`
+/// and `id_length` are updated with each rename. Files that are already
+/// `opaque` keep their random name when the policy is `opaque` too.
+///
+/// Each file's target is recorded in `relayout_journal` before it is
+/// renamed, and the row update clears that entry. A re-run after an
+/// interruption first finishes every journaled move, whatever the style,
+/// then carries on with the new policy.
+pub fn relayout(index_conn: &Connection, policy: &NamingPolicy) -> Result<RelayoutReport> {
+    // Files moved here already count as renamed, whatever happens below
+    let finished = finish_journaled_moves(index_conn)?;
+    let mut report = RelayoutReport {
+        renamed: finished.len(),
+        ..RelayoutReport::default()
+    };
+
+    let mut rows = Vec::new();
+    for table in ["files", "trash"] {
+        let mut stmt = index_conn.prepare(&format!(
+            "SELECT file_id, display_name, current_path, filename_style, id_length FROM {table} \
+             ORDER BY file_id"
+        ))?;
+        let table_rows = stmt.query_map([], |row| {
+            Ok((
+                table,
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                PathBuf::from(row.get::<_, String>(2)?),
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)? as u64,
+            ))
+        })?;
+        for row in table_rows {
+            rows.push(row?);
+        }
+    }
+
+    for (table, file_id, display_name, current, style, id_length) in rows {
+        if !current.exists() {
+            report.missing.push(file_id);
+            continue;
+        }
+
+        if policy.style == FilenameStyle::Opaque && style == FilenameStyle::Opaque.as_str() {
+            if !finished.contains(&file_id) {
+                report.unchanged += 1;
+            }
+            continue;
+        }
+
+        let dir = current.parent().unwrap_or(Path::new("")).to_path_buf();
+        let target = allocate_name(index_conn, &dir, &display_name, &file_id, policy)?;
+        if target.path == current {
+            if style != policy.style.as_str() || id_length != target.id_length_hex {
+                update_location(index_conn, table, &file_id, &target, policy.style.as_str())?;
+            }
+            if !finished.contains(&file_id) {
+                report.unchanged += 1;
+            }
+            continue;
+        }
+
+        index_conn.execute(
+            "INSERT OR REPLACE INTO relayout_journal \
+             (file_id, source_table, from_path, target_path, filename_style, id_length) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                file_id,
+                table,
+                path_str(&current)?,
+                path_str(&target.path)?,
+                policy.style.as_str(),
+                target.id_length_hex as i64
+            ],
+        )?;
+        crate::atomic_write::rename_durable_noclobber(&current, &target.path)?;
+        finish_move(index_conn, table, &file_id, &target, policy.style.as_str())?;
+        if !finished.contains(&file_id) {
+            report.renamed += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// Complete the moves an interrupted relayout recorded; returns the ids that
+/// ended up under their journaled name
+fn finish_journaled_moves(index_conn: &Connection) -> Result<HashSet<String>> {
+    let pending = index_conn
+        .prepare(
+            "SELECT file_id, source_table, from_path, target_path, filename_style, id_length \
+             FROM relayout_journal ORDER BY file_id",
+        )?
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                PathBuf::from(row.get::<_, String>(2)?),
+                PathBuf::from(row.get::<_, String>(3)?),
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)? as u64,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut finished = HashSet::new();
+    for (file_id, table, from, target, style, id_length_hex) in pending {
+        // Only ever the two tables relayout itself journals
+        let table = match table.as_str() {
+            "files" => "files",
+            "trash" => "trash",
+            other => {
+                return Err(CoreError::PolicyViolation(format!(
+                    "relayout_journal names unknown table {other}"
+                )))
+            }
+        };
+        let name = AllocatedName {
+            path: target,
+            id_length_hex,
+        };
+
+        match (from.exists(), name.path.exists()) {
+            // Renamed, row not yet updated
+            (false, true) => {}
+            // Not renamed yet
+            (true, false) => crate::atomic_write::rename_durable_noclobber(&from, &name.path)?,
+            // Nothing left to move, or the target was taken since: leave the
+            // file where its row says it is
+            _ => {
+                index_conn.execute(
+                    "DELETE FROM relayout_journal WHERE file_id = ?1",
+                    [&file_id],
+                )?;
+                continue;
+            }
+        }
+        finish_move(index_conn, table, &file_id, &name, &style)?;
+        finished.insert(file_id);
+    }
+    Ok(finished)
+}
+
+/// Point the row at its new name and drop the journal entry, atomically
+fn finish_move(
+    index_conn: &Connection,
+    table: &str,
+    file_id: &str,
+    name: &AllocatedName,
+    style: &str,
+) -> Result<()> {
+    let tx = index_conn.unchecked_transaction()?;
+    update_location(&tx, table, file_id, name, style)?;
+    tx.execute("DELETE FROM relayout_journal WHERE file_id = ?1", [file_id])?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn path_str(path: &Path) -> Result<&str> {
+    path.to_str()
+        .ok_or_else(|| CoreError::InvalidPath(path.to_path_buf()))
+}
+
+fn update_location(
+    conn: &Connection,
+    table: &str,
+    file_id: &str,
+    name: &AllocatedName,
+    style: &str,
+) -> Result<()> {
+    conn.execute(
+        &format!(
+            "UPDATE {table} SET current_path = ?1, filename_style = ?2, id_length = ?3 \
+             WHERE file_id = ?4"
+        ),
+        rusqlite::params![
+            path_str(&name.path)?,
+            style,
+            name.id_length_hex as i64,
+            file_id
+        ],
+    )?;
+    Ok(())
+}
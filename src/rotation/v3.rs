@@ -5,25 +5,29 @@
 //! batch rotators, and `rotate_key_in_vault`.
 
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Write};
 use std::path::Path;
 
 use crate::aliases::{
     FileKey32, FilePassword, RandomFileKey32, SecureConversionsExt, SecureRandomExt,
 };
-use aescrypt_rs::{decrypt, encrypt};
+use crate::atomic_write::write_atomic;
+use aescrypt_rs::{decrypt, encrypt, AescryptError};
 
 /// Rotate the encryption key on an existing v3 file using the **current known key**
 ///
 /// This is the secure, production-grade rotation path when you already have
 /// the current `FileKey32` in hex form (from the vault DB).
 ///
+/// The re-encrypted file is written atomically, so `output_path` may be the
+/// same as `input_path`.
+///
 /// Returns the new random `FileKey32` that must be stored in the vault.
 pub fn rotate_key(
     input_path: &Path,
     output_path: &Path,
     current_key_hex: &FilePassword,
-) -> Result<FileKey32, aescrypt_rs::AescryptError> {
+) -> Result<FileKey32, AescryptError> {
     let input = BufReader::new(File::open(input_path)?);
 
     // Decrypt to secure temporary file
@@ -39,14 +43,18 @@ pub fn rotate_key(
     let new_key = FileKey32::new(**random_key);
 
     let decrypted_reopen = temp_decrypted.reopen()?;
-    let final_output = BufWriter::new(File::create(output_path)?);
-
-    encrypt(
-        BufReader::new(decrypted_reopen),
-        final_output,
-        &new_password,
-        600_000,
-    )?;
+
+    write_atomic(output_path, |file| {
+        let mut final_output = BufWriter::new(file);
+        encrypt(
+            BufReader::new(decrypted_reopen),
+            &mut final_output,
+            &new_password,
+            600_000,
+        )?;
+        final_output.flush()?;
+        Ok::<_, AescryptError>(())
+    })?;
 
     Ok(new_key)
 }
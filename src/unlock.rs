@@ -0,0 +1,150 @@
+// src/unlock.rs
+//! Passphrase-based vault unlock
+//!
+//! A small plaintext header (`vault-header.json`) next to the databases holds
+//! the per-vault salt and Argon2id parameters — never any key material.
+//!
+//! passphrase ─Argon2id(salt)→ 32-byte master ─BLAKE3 derive_key→ `VaultKey32`
+//!                                                              └→ `IndexKey32`
+//!
+//! The two subkeys are independent: learning one says nothing about the other.
+
+use std::io::Write;
+use std::path::Path;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::aliases::{IndexKey32, UserPassphrase, VaultKey32};
+use crate::atomic_write::write_atomic;
+use crate::consts::{
+    ARGON2_MAX_M_COST_KIB, ARGON2_MAX_T_COST, ARGON2_M_COST_KIB, ARGON2_P_COST, ARGON2_T_COST,
+    VAULT_HEADER_FILE_NAME,
+};
+use crate::error::CoreError;
+use crate::Result;
+
+/// `format` value written into every header
+pub const HEADER_FORMAT: &str = "efv-vault-header-v1";
+
+pub(crate) const KDF_NAME: &str = "argon2id";
+pub(crate) const SALT_LEN: usize = 16;
+
+const VAULT_KEY_CONTEXT: &str = "encrypted-file-vault 2025-12 vault.db SQLCipher key";
+const INDEX_KEY_CONTEXT: &str = "encrypted-file-vault 2025-12 index.db SQLCipher key";
+
+/// Argon2id cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub m_cost_kib: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            m_cost_kib: ARGON2_M_COST_KIB,
+            t_cost: ARGON2_T_COST,
+            p_cost: ARGON2_P_COST,
+        }
+    }
+}
+
+/// Plaintext per-vault header: salt + KDF parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultHeader {
+    pub format: String,
+    pub kdf: String,
+    pub params: KdfParams,
+    /// Standard base64
+    pub salt: String,
+    pub created_at: String,
+}
+
+impl VaultHeader {
+    /// Fresh header with a random salt
+    pub fn new(params: KdfParams) -> Self {
+        let salt: [u8; SALT_LEN] = rand::random();
+        Self {
+            format: HEADER_FORMAT.into(),
+            kdf: KDF_NAME.into(),
+            params,
+            salt: STANDARD.encode(salt),
+            created_at: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        }
+    }
+
+    /// Read and validate the header in a vault directory
+    pub fn load<P: AsRef<Path>>(vault_dir: P) -> Result<Self> {
+        let path = vault_dir.as_ref().join(VAULT_HEADER_FILE_NAME);
+        let content = std::fs::read_to_string(&path)?;
+        let header: Self = serde_json::from_str(&content).map_err(|e| {
+            CoreError::Config(format!("invalid vault header {}: {e}", path.display()))
+        })?;
+
+        if header.format != HEADER_FORMAT || header.kdf != KDF_NAME {
+            return Err(CoreError::Config(format!(
+                "unsupported vault header: format {:?}, kdf {:?}",
+                header.format, header.kdf
+            )));
+        }
+        Ok(header)
+    }
+
+    /// Write the header atomically into a vault directory
+    pub fn save<P: AsRef<Path>>(&self, vault_dir: P) -> Result<()> {
+        let rendered = serde_json::to_string_pretty(self).map_err(std::io::Error::from)?;
+        write_atomic(vault_dir.as_ref().join(VAULT_HEADER_FILE_NAME), |file| {
+            file.write_all(rendered.as_bytes())
+        })?;
+        Ok(())
+    }
+
+    /// Turn one passphrase into the two independent database keys
+    pub fn derive_keys(&self, passphrase: &UserPassphrase) -> Result<(VaultKey32, IndexKey32)> {
+        let salt = STANDARD
+            .decode(&self.salt)
+            .map_err(|e| CoreError::Config(format!("invalid vault header salt: {e}")))?;
+
+        let master = argon2id(passphrase, &salt, self.params)?;
+
+        let vault_key = VaultKey32::new(blake3::derive_key(VAULT_KEY_CONTEXT, master.as_slice()));
+        let index_key = IndexKey32::new(blake3::derive_key(INDEX_KEY_CONTEXT, master.as_slice()));
+
+        Ok((vault_key, index_key))
+    }
+}
+
+/// Stretch `passphrase` into 32 bytes with Argon2id
+///
+/// Parameters above [`ARGON2_MAX_M_COST_KIB`] / [`ARGON2_MAX_T_COST`] are
+/// refused with `CoreError::PolicyViolation` before any work is done.
+pub(crate) fn argon2id(
+    passphrase: &UserPassphrase,
+    salt: &[u8],
+    params: KdfParams,
+) -> Result<Zeroizing<[u8; 32]>> {
+    if params.m_cost_kib > ARGON2_MAX_M_COST_KIB || params.t_cost > ARGON2_MAX_T_COST {
+        return Err(CoreError::PolicyViolation(format!(
+            "Argon2id parameters m = {} KiB, t = {} exceed the accepted maximum",
+            params.m_cost_kib, params.t_cost
+        )));
+    }
+    let params = Params::new(params.m_cost_kib, params.t_cost, params.p_cost, Some(32))
+        .map_err(|e| CoreError::Config(format!("invalid Argon2id parameters: {e}")))?;
+
+    let mut master = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(
+            passphrase.expose_secret().as_bytes(),
+            salt,
+            master.as_mut_slice(),
+        )
+        .map_err(|e| CoreError::Config(format!("Argon2id failed: {e}")))?;
+    Ok(master)
+}
@@ -0,0 +1,715 @@
+// src/vault.rs
+//! `Vault` — one handle that owns both databases
+//!
+//! Opened from explicit paths and keys, or from a [`Config`] value; no global
+//! state or env vars are consulted.
+//! The free functions in `db::*` remain available as building blocks, but
+//! callers should not have to juggle two connections themselves.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+
+use crate::aliases::{FileKey32, FilePassword, IndexKey32, UserPassphrase, VaultKey32};
+use crate::config::Config;
+use crate::consts::{
+    INDEX_DB_FILE_NAME, STORAGE_DIR_NAME, VAULT_DB_FILE_NAME, VAULT_HEADER_FILE_NAME,
+};
+use crate::db::index_db_conn::open_index_db_at;
+use crate::db::index_db_ops::{self, FileEntry, FileRef};
+use crate::db::keying::{self, RekeyRecovery};
+use crate::db::query::{FileQuery, FileRecordIter};
+use crate::db::tag_ops::{self, TagCount};
+use crate::db::vault_db_conn::open_vault_db_at;
+use crate::db::vault_db_ops::{self, RemovalReport, RotationRecovery, StoreOptions};
+use crate::enums::{OriginalsPolicy, RemoveMode};
+use crate::error::CoreError;
+use crate::export::{self, CsvExportOptions};
+use crate::file_id;
+use crate::import::{
+    self, DirectoryImportOptions, ImportReport, JsonImportOptions, PortableImport, PortableKey,
+};
+use crate::naming::{self, NamingPolicy, RelayoutReport};
+use crate::unlock::{KdfParams, VaultHeader};
+use crate::Result;
+
+pub struct Vault {
+    vault_conn: Connection,
+    index_conn: Connection,
+    // Kept so a failed rekey can be undone in place
+    vault_key: VaultKey32,
+    index_key: IndexKey32,
+    recovered_rotations: Vec<RotationRecovery>,
+    store: StoreOptions,
+    allow_insecure_export: bool,
+}
+
+impl Vault {
+    /// Open (or create) a vault from explicit database paths and raw 256-bit keys
+    ///
+    /// Interrupted rekeys and key rotations are finished or rolled back before
+    /// this returns; see [`Vault::recovered_rotations`].
+    pub fn open<V: AsRef<Path>, I: AsRef<Path>>(
+        vault_db: V,
+        vault_key: &VaultKey32,
+        index_db: I,
+        index_key: &IndexKey32,
+    ) -> Result<Self> {
+        let (vault_db, index_db) = (vault_db.as_ref(), index_db.as_ref());
+
+        let rekey_recovery = keying::recover_interrupted_rekey(
+            vault_db,
+            vault_key.expose_secret(),
+            index_db,
+            index_key.expose_secret(),
+        )?;
+
+        let mut vault_conn = open_vault_db_at(vault_db, vault_key)?;
+        let index_conn = open_index_db_at(index_db, index_key)?;
+
+        match rekey_recovery {
+            RekeyRecovery::Clean => {}
+            RekeyRecovery::Completed => vault_db_ops::record_audit(
+                &vault_conn,
+                "rekey-recovered",
+                Some("interrupted rekey completed"),
+            )?,
+            RekeyRecovery::RolledBack => vault_db_ops::record_audit(
+                &vault_conn,
+                "rekey-recovered",
+                Some("interrupted rekey rolled back"),
+            )?,
+        }
+
+        let recovered_rotations =
+            vault_db_ops::recover_interrupted_rotations(&mut vault_conn, &index_conn)?;
+
+        let upgraded_ids = file_id::upgrade_file_ids(&mut vault_conn, &index_conn)?;
+        if upgraded_ids > 0 {
+            vault_db_ops::record_audit(
+                &vault_conn,
+                "file-ids-upgraded",
+                Some(&format!("{upgraded_ids} file id(s) re-keyed")),
+            )?;
+        }
+
+        let store = StoreOptions {
+            naming: NamingPolicy::load(&vault_conn)?,
+            ..StoreOptions::default()
+        };
+
+        Ok(Self {
+            vault_conn,
+            index_conn,
+            vault_key: VaultKey32::new(*vault_key.expose_secret()),
+            index_key: IndexKey32::new(*index_key.expose_secret()),
+            recovered_rotations,
+            store,
+            allow_insecure_export: false,
+        })
+    }
+
+    /// Open the vault a [`Config`] describes
+    ///
+    /// The config is validated first ([`Config::validate_for_build`]), so
+    /// dev keys and test paths are refused unless built with `dev-config`.
+    /// `features.allow_insecure_export` enables [`Vault::export_json`].
+    pub fn from_config(config: &Config) -> Result<Self> {
+        config.validate_for_build()?;
+
+        let vault_key = VaultKey32::new(*keying::parse_key_hex(&config.keys.vault_key)?);
+        let index_key = IndexKey32::new(*keying::parse_key_hex(&config.keys.index_key)?);
+
+        let mut vault = Self::open(
+            &config.paths.vault_db,
+            &vault_key,
+            &config.paths.index_db,
+            &index_key,
+        )?;
+        vault.allow_insecure_export = config.features.allow_insecure_export;
+        Ok(vault)
+    }
+
+    /// Create a new passphrase-protected vault in `dir`
+    ///
+    /// Writes `vault-header.json` (salt + Argon2id parameters) and derives both
+    /// database keys from the one passphrase. Refuses to touch an existing vault.
+    pub fn create_with_passphrase<P: AsRef<Path>>(
+        dir: P,
+        passphrase: &UserPassphrase,
+        params: KdfParams,
+    ) -> Result<Self> {
+        let dir = dir.as_ref();
+        if dir.join(VAULT_HEADER_FILE_NAME).exists() || dir.join(VAULT_DB_FILE_NAME).exists() {
+            return Err(CoreError::Config(format!(
+                "a vault already exists in {}",
+                dir.display()
+            )));
+        }
+        std::fs::create_dir_all(dir)?;
+
+        let header = VaultHeader::new(params);
+        let (vault_key, index_key) = header.derive_keys(passphrase)?;
+        header.save(dir)?;
+
+        Self::open(
+            dir.join(VAULT_DB_FILE_NAME),
+            &vault_key,
+            dir.join(INDEX_DB_FILE_NAME),
+            &index_key,
+        )
+    }
+
+    /// Unlock an existing passphrase-protected vault in `dir`
+    pub fn unlock<P: AsRef<Path>>(dir: P, passphrase: &UserPassphrase) -> Result<Self> {
+        let dir = dir.as_ref();
+        let header = VaultHeader::load(dir)?;
+
+        let vault_db = dir.join(VAULT_DB_FILE_NAME);
+        let index_db = dir.join(INDEX_DB_FILE_NAME);
+        if !vault_db.exists() || !index_db.exists() {
+            return Err(CoreError::Config(format!(
+                "vault databases missing in {}",
+                dir.display()
+            )));
+        }
+
+        let (vault_key, index_key) = header.derive_keys(passphrase)?;
+        Self::open(vault_db, &vault_key, index_db, &index_key)
+    }
+
+    /// Unpack a portable archive into `dest_dir` and open it with raw keys
+    ///
+    /// Every checksum is verified and each `current_path` rewritten under
+    /// `dest_dir` before anything lands there — see [`import::portable`].
+    pub fn import_portable<A: AsRef<Path>, D: AsRef<Path>>(
+        archive: A,
+        dest_dir: D,
+        vault_key: &VaultKey32,
+        index_key: &IndexKey32,
+    ) -> Result<Self> {
+        let archive = archive.as_ref();
+        let unpacked = import::import_portable(
+            archive,
+            dest_dir.as_ref(),
+            PortableKey::Raw {
+                vault_key,
+                index_key,
+            },
+        )?;
+        let vault = Self::open(
+            unpacked.dest_dir.join(VAULT_DB_FILE_NAME),
+            vault_key,
+            unpacked.dest_dir.join(INDEX_DB_FILE_NAME),
+            index_key,
+        )?;
+        vault.record_portable_import(archive, &unpacked)?;
+        Ok(vault)
+    }
+
+    /// [`Vault::import_portable`] for a passphrase-protected vault
+    pub fn import_portable_with_passphrase<A: AsRef<Path>, D: AsRef<Path>>(
+        archive: A,
+        dest_dir: D,
+        passphrase: &UserPassphrase,
+    ) -> Result<Self> {
+        let archive = archive.as_ref();
+        let unpacked = import::import_portable(
+            archive,
+            dest_dir.as_ref(),
+            PortableKey::Passphrase(passphrase),
+        )?;
+        let vault = Self::unlock(&unpacked.dest_dir, passphrase)?;
+        vault.record_portable_import(archive, &unpacked)?;
+        Ok(vault)
+    }
+
+    fn record_portable_import(&self, archive: &Path, unpacked: &PortableImport) -> Result<()> {
+        vault_db_ops::record_audit(
+            &self.vault_conn,
+            "portable-import",
+            Some(&format!(
+                "{}: {} entries",
+                archive.display(),
+                unpacked.relocated.len()
+            )),
+        )?;
+        Ok(())
+    }
+
+    /// Rotations that were repaired while opening this vault
+    pub fn recovered_rotations(&self) -> &[RotationRecovery] {
+        &self.recovered_rotations
+    }
+
+    /// Re-key both databases under new raw keys
+    ///
+    /// Only the SQLCipher page encryption changes (`PRAGMA rekey`); per-file
+    /// keys and ciphertext are untouched. Crash-safe:
+    ///
+    /// 1. Copy both databases to `*.rekey-backup` (fsynced).
+    /// 2. `PRAGMA rekey` each live database.
+    /// 3. Verify fresh connections open both with the new keys.
+    /// 4. Discard the backups and record an `audit_log` entry.
+    ///
+    /// A failure in 2–3 re-keys back to the old keys. After a crash the next
+    /// [`Vault::open`] keeps whichever generation opens *both* databases.
+    pub fn rekey(&mut self, new_vault_key: &VaultKey32, new_index_key: &IndexKey32) -> Result<()> {
+        self.rekey_with(new_vault_key, new_index_key, || Ok(()))?;
+        vault_db_ops::record_audit(&self.vault_conn, "rekey", Some("raw keys replaced"))?;
+        Ok(())
+    }
+
+    /// Change the passphrase of a vault created with [`Vault::create_with_passphrase`]
+    ///
+    /// A fresh salt is drawn and both database keys are re-derived and applied
+    /// as in [`Vault::rekey`]. The new `vault-header.json` replaces the old one
+    /// only after both databases are verified under the new keys.
+    pub fn change_passphrase(
+        &mut self,
+        new_passphrase: &UserPassphrase,
+        params: KdfParams,
+    ) -> Result<()> {
+        let (vault_db, _) = self.db_paths()?;
+        let dir = vault_db.parent().unwrap_or(Path::new(".")).to_path_buf();
+        if !dir.join(VAULT_HEADER_FILE_NAME).exists() {
+            return Err(CoreError::Config(format!(
+                "no {VAULT_HEADER_FILE_NAME} in {} — not a passphrase vault",
+                dir.display()
+            )));
+        }
+
+        let header = VaultHeader::new(params);
+        let (vault_key, index_key) = header.derive_keys(new_passphrase)?;
+
+        self.rekey_with(&vault_key, &index_key, || header.save(&dir))?;
+        vault_db_ops::record_audit(&self.vault_conn, "passphrase-change", None)?;
+        Ok(())
+    }
+
+    /// Encrypt a file into the vault under a fresh key and index it
+    ///
+    /// Content the vault already holds is handled per the
+    /// [`StoreOptions::dedup`] policy. An existing file at `encrypted_path`
+    /// is never replaced; that fails with an `AlreadyExists` I/O error.
+    pub fn add_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        plaintext_path: P,
+        encrypted_path: Q,
+    ) -> Result<FileEntry> {
+        vault_db_ops::add_file_with_dedup(
+            plaintext_path.as_ref(),
+            encrypted_path.as_ref(),
+            &mut self.vault_conn,
+            &self.index_conn,
+            None,
+            None,
+            self.store.dedup,
+        )
+    }
+
+    /// Encrypt a file into [`Vault::storage_dir`] per [`Vault::store_options`]
+    pub fn store_file<P: AsRef<Path>>(&mut self, plaintext_path: P) -> Result<FileEntry> {
+        let storage_dir = self.storage_dir()?;
+        vault_db_ops::store_file(
+            plaintext_path,
+            storage_dir,
+            &mut self.vault_conn,
+            &self.index_conn,
+            &self.store,
+        )
+    }
+
+    /// Encrypt every file under `root` into [`Vault::storage_dir`]
+    ///
+    /// See [`import::directory`]; an interrupted import resumes when run again.
+    pub fn import_directory<P: AsRef<Path>>(
+        &mut self,
+        root: P,
+        options: &DirectoryImportOptions,
+    ) -> Result<ImportReport> {
+        let storage_dir = self.storage_dir()?;
+        import::import_directory(
+            &mut self.vault_conn,
+            &self.index_conn,
+            root.as_ref(),
+            &storage_dir,
+            &self.store,
+            options,
+        )
+    }
+
+    /// Upgrade and store an AES Crypt file, or a tree of them, trying each
+    /// of `passwords` — see [`import::legacy`]
+    pub fn import_legacy<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        passwords: &[FilePassword],
+        originals: OriginalsPolicy,
+    ) -> Result<ImportReport> {
+        let storage_dir = self.storage_dir()?;
+        import::import_legacy(
+            &mut self.vault_conn,
+            &self.index_conn,
+            path.as_ref(),
+            &storage_dir,
+            &self.store,
+            passwords,
+            originals,
+        )
+    }
+
+    /// Restore the entries of a JSON export — see [`import::json`]
+    pub fn import_from_json<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        options: &JsonImportOptions,
+    ) -> Result<ImportReport> {
+        import::import_from_json(&mut self.vault_conn, &self.index_conn, path, options)
+    }
+
+    /// Restore the entries of an encrypted backup — see [`import::backup`]
+    pub fn import_encrypted_backup<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        passphrase: &UserPassphrase,
+        options: &JsonImportOptions,
+    ) -> Result<ImportReport> {
+        import::import_encrypted_backup(
+            &mut self.vault_conn,
+            &self.index_conn,
+            path,
+            passphrase,
+            options,
+        )
+    }
+
+    /// Naming and id options used by [`Vault::store_file`]
+    pub fn store_options(&self) -> &StoreOptions {
+        &self.store
+    }
+
+    /// Set the options [`Vault::store_file`] uses
+    ///
+    /// The naming policy is saved in the vault; the other options last as
+    /// long as this handle.
+    pub fn set_store_options(&mut self, options: StoreOptions) -> Result<()> {
+        options.naming.save(&self.vault_conn)?;
+        self.store = options;
+        Ok(())
+    }
+
+    /// Naming scheme used by [`Vault::store_file`]
+    pub fn naming_policy(&self) -> &NamingPolicy {
+        &self.store.naming
+    }
+
+    /// Change and save the naming scheme for new files (existing ones:
+    /// [`Vault::relayout`])
+    pub fn set_naming_policy(&mut self, policy: NamingPolicy) -> Result<()> {
+        policy.save(&self.vault_conn)?;
+        self.store.naming = policy;
+        Ok(())
+    }
+
+    /// Rename every stored file to match `policy`, then adopt and save it
+    /// for new files
+    pub fn relayout(&mut self, policy: NamingPolicy) -> Result<RelayoutReport> {
+        let report = naming::relayout(&self.index_conn, &policy)?;
+        self.set_naming_policy(policy)?;
+        Ok(report)
+    }
+
+    /// Where [`Vault::store_file`] puts ciphertext: `files/` next to vault.db
+    pub fn storage_dir(&self) -> Result<PathBuf> {
+        let (vault_db, _) = self.db_paths()?;
+        Ok(vault_db
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(STORAGE_DIR_NAME))
+    }
+
+    /// Decrypt a stored file to `dest`; returns the plaintext size
+    ///
+    /// The output is BLAKE3-verified against `content_hash` before `dest`
+    /// appears — a mismatch fails with `CoreError::IntegrityMismatch`.
+    pub fn extract_file<P: AsRef<Path>>(&self, file_id: &str, dest: P) -> Result<u64> {
+        vault_db_ops::extract_file(&self.vault_conn, &self.index_conn, file_id, dest)
+    }
+
+    /// Decrypt a stored file into any writer; returns the plaintext size
+    ///
+    /// Verified like [`Vault::extract_file`], but the writer has already seen
+    /// the bytes when a mismatch is reported.
+    pub fn extract_to_writer<W: Write>(&self, file_id: &str, writer: W) -> Result<u64> {
+        vault_db_ops::extract_to_writer(&self.vault_conn, &self.index_conn, file_id, writer)
+    }
+
+    /// Extract the one file with this display name
+    ///
+    /// Several files can share a name; then this fails with
+    /// `CoreError::Ambiguous` listing their ids, and the caller picks one
+    /// for [`Vault::extract_file`]. An unknown name is
+    /// `CoreError::NameNotFound`.
+    pub fn extract_by_name<P: AsRef<Path>>(&self, display_name: &str, dest: P) -> Result<u64> {
+        let mut matches = self.find_by_name(display_name)?;
+        match matches.len() {
+            0 => Err(CoreError::NameNotFound(display_name.to_string())),
+            1 => self.extract_file(&matches.remove(0).file_id, dest),
+            _ => Err(CoreError::Ambiguous {
+                name: display_name.to_string(),
+                candidates: matches.into_iter().map(|e| e.file_id).collect(),
+            }),
+        }
+    }
+
+    /// Every file with exactly this display name
+    pub fn find_by_name(&self, display_name: &str) -> Result<Vec<FileEntry>> {
+        Ok(index_db_ops::find_file_entries_by_name(
+            &self.index_conn,
+            display_name,
+        )?)
+    }
+
+    /// Rotate a file's key (journaled, crash-safe); returns the new key
+    pub fn rotate(&mut self, file_id: &str, note: Option<&str>) -> Result<FileKey32> {
+        let entry = self.entry(file_id)?;
+        let password = self.current_password(file_id)?;
+        vault_db_ops::rotate_key_in_vault(
+            &entry.current_path,
+            &mut self.vault_conn,
+            &self.index_conn,
+            file_id,
+            &password,
+            note,
+        )
+    }
+
+    /// Soft-delete, hard-delete or crypto-shred a file — see [`RemoveMode`]
+    pub fn remove_file(&mut self, file_id: &str, mode: RemoveMode) -> Result<RemovalReport> {
+        vault_db_ops::remove_file(&self.vault_conn, &self.index_conn, file_id, mode)
+    }
+
+    /// Drop one source reference; the last one hard-deletes the file
+    pub fn remove_reference(
+        &mut self,
+        file_id: &str,
+        source_path: &str,
+    ) -> Result<Option<RemovalReport>> {
+        vault_db_ops::remove_reference(&self.vault_conn, &self.index_conn, file_id, source_path)
+    }
+
+    /// Sources a stored file was added from
+    pub fn references(&self, file_id: &str) -> Result<Vec<FileRef>> {
+        Ok(index_db_ops::list_file_refs(&self.index_conn, file_id)?)
+    }
+
+    /// Bring a soft-deleted file back from the trash
+    pub fn restore_file(&mut self, file_id: &str) -> Result<FileEntry> {
+        if index_db_ops::get_file_entry(&self.index_conn, file_id)?.is_some() {
+            return Err(CoreError::PolicyViolation(format!(
+                "{file_id} is already in the index"
+            )));
+        }
+        if !index_db_ops::restore_from_trash(&self.index_conn, file_id)? {
+            return Err(CoreError::NotFound(file_id.to_string()));
+        }
+        vault_db_ops::record_audit(&self.vault_conn, "restore", Some(file_id))?;
+        self.entry(file_id)
+    }
+
+    /// Soft-deleted files, most recently deleted first
+    pub fn trash(&self) -> Result<Vec<FileEntry>> {
+        Ok(index_db_ops::list_trash_entries(&self.index_conn)?)
+    }
+
+    /// Every indexed file, ordered by display name
+    pub fn list(&self) -> Result<Vec<FileEntry>> {
+        Ok(index_db_ops::list_file_entries(&self.index_conn)?)
+    }
+
+    /// Lazily run a typed query over the index
+    pub fn query(&self, query: &FileQuery) -> FileRecordIter<'_> {
+        query.run(&self.index_conn)
+    }
+
+    /// Tag a file; returns how many tags were new to it
+    pub fn add_tags(&mut self, file_id: &str, tags: &[&str]) -> Result<usize> {
+        tag_ops::add_tags(&self.index_conn, file_id, tags)
+    }
+
+    /// Untag a file; returns how many of the tags it carried
+    pub fn remove_tags(&mut self, file_id: &str, tags: &[&str]) -> Result<usize> {
+        tag_ops::remove_tags(&self.index_conn, file_id, tags)
+    }
+
+    /// Rename a tag on every file (merging into `to` if it already exists)
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> Result<()> {
+        tag_ops::rename_tag(&self.index_conn, from, to)
+    }
+
+    /// A file's tags, sorted by name
+    pub fn file_tags(&self, file_id: &str) -> Result<Vec<String>> {
+        Ok(tag_ops::file_tags(&self.index_conn, file_id)?)
+    }
+
+    /// Every tag with its live file count
+    pub fn tags(&self) -> Result<Vec<TagCount>> {
+        Ok(tag_ops::list_tags(&self.index_conn)?)
+    }
+
+    /// Metadata for one file
+    pub fn entry(&self, file_id: &str) -> Result<FileEntry> {
+        index_db_ops::get_file_entry(&self.index_conn, file_id)?
+            .ok_or_else(|| CoreError::NotFound(file_id.to_string()))
+    }
+
+    /// Export every entry and key to plaintext JSON; returns the file count
+    ///
+    /// Refused with `CoreError::PolicyViolation` unless plaintext exports
+    /// were opted into ([`Vault::set_allow_insecure_export`]).
+    pub fn export_json<P: AsRef<Path>>(&self, path: P) -> Result<usize> {
+        export::export_to_json(
+            &self.vault_conn,
+            &self.index_conn,
+            path,
+            self.allow_insecure_export,
+        )
+    }
+
+    /// Export live entries to CSV; returns the row count — see [`export::csv`]
+    ///
+    /// Metadata-only exports are always allowed; [`export::CsvKeys::Include`] is
+    /// refused like [`Vault::export_json`] unless plaintext exports were
+    /// opted into.
+    pub fn export_csv<P: AsRef<Path>>(&self, path: P, options: &CsvExportOptions) -> Result<usize> {
+        export::export_to_csv(
+            &self.vault_conn,
+            &self.index_conn,
+            path,
+            options,
+            self.allow_insecure_export,
+        )
+    }
+
+    /// Allow [`Vault::export_json`] and key-bearing CSV exports to write every
+    /// key in cleartext
+    pub fn set_allow_insecure_export(&mut self, allow: bool) {
+        self.allow_insecure_export = allow;
+    }
+
+    /// Export every entry and key to a backup encrypted under `passphrase`;
+    /// returns the file count — see [`export::backup`]
+    pub fn export_encrypted_backup<P: AsRef<Path>>(
+        &self,
+        path: P,
+        passphrase: &UserPassphrase,
+        params: KdfParams,
+    ) -> Result<usize> {
+        export::export_encrypted_backup(
+            &self.vault_conn,
+            &self.index_conn,
+            path,
+            passphrase,
+            params,
+        )
+    }
+
+    /// Write the databases and every ciphertext to one portable archive;
+    /// returns the entry count — see [`export::portable`]
+    pub fn export_portable<P: AsRef<Path>>(&self, path: P) -> Result<usize> {
+        export::export_portable(&self.vault_conn, &self.index_conn, path)
+    }
+
+    /// Direct access to the vault DB (keys, key_history)
+    pub fn vault_conn(&self) -> &Connection {
+        &self.vault_conn
+    }
+
+    /// Direct access to the index DB (files)
+    pub fn index_conn(&self) -> &Connection {
+        &self.index_conn
+    }
+
+    /// Backup → rekey → verify → `commit` → drop backups; undo on failure
+    fn rekey_with<F: FnOnce() -> Result<()>>(
+        &mut self,
+        new_vault_key: &VaultKey32,
+        new_index_key: &IndexKey32,
+        commit: F,
+    ) -> Result<()> {
+        let (vault_db, index_db) = self.db_paths()?;
+
+        keying::backup_for_rekey(&vault_db)?;
+        if let Err(e) = keying::backup_for_rekey(&index_db) {
+            let _ = keying::discard_rekey_backup(&vault_db);
+            return Err(e);
+        }
+
+        let applied = self
+            .apply_keys(
+                new_vault_key.expose_secret(),
+                new_index_key.expose_secret(),
+                &vault_db,
+                &index_db,
+            )
+            .and_then(|()| commit());
+
+        if let Err(e) = applied {
+            let (old_vault_key, old_index_key) = (
+                *self.vault_key.expose_secret(),
+                *self.index_key.expose_secret(),
+            );
+            // If this fails too, the backups stay and the next open sorts it out
+            if self
+                .apply_keys(&old_vault_key, &old_index_key, &vault_db, &index_db)
+                .is_ok()
+            {
+                let _ = keying::discard_rekey_backup(&vault_db);
+                let _ = keying::discard_rekey_backup(&index_db);
+            }
+            return Err(e);
+        }
+
+        self.vault_key = VaultKey32::new(*new_vault_key.expose_secret());
+        self.index_key = IndexKey32::new(*new_index_key.expose_secret());
+
+        keying::discard_rekey_backup(&vault_db)?;
+        keying::discard_rekey_backup(&index_db)?;
+        Ok(())
+    }
+
+    fn apply_keys(
+        &self,
+        vault_key: &[u8; 32],
+        index_key: &[u8; 32],
+        vault_db: &Path,
+        index_db: &Path,
+    ) -> Result<()> {
+        keying::rekey_raw(&self.vault_conn, vault_key)?;
+        keying::rekey_raw(&self.index_conn, index_key)?;
+
+        if !keying::opens_with(vault_db, vault_key) || !keying::opens_with(index_db, index_key) {
+            return Err(CoreError::Config(
+                "rekeyed databases did not open with the new keys".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn db_paths(&self) -> Result<(PathBuf, PathBuf)> {
+        match (self.vault_conn.path(), self.index_conn.path()) {
+            (Some(v), Some(i)) if !v.is_empty() && !i.is_empty() => {
+                Ok((PathBuf::from(v), PathBuf::from(i)))
+            }
+            _ => Err(CoreError::Config(
+                "this operation needs file-backed databases".into(),
+            )),
+        }
+    }
+
+    fn current_password(&self, file_id: &str) -> Result<FilePassword> {
+        vault_db_ops::load_current_password(&self.vault_conn, file_id)
+    }
+}
@@ -0,0 +1,86 @@
+// tests/atomic_write_tests.rs
+use encrypted_file_vault::aliases::FilePassword;
+use encrypted_file_vault::atomic_write::{
+    rename_durable, write_atomic, RenameError, ATOMIC_TEMP_PREFIX,
+};
+use encrypted_file_vault::file_ops::{decrypt_file, encrypt_file};
+use encrypted_file_vault::key_ops::generate_key;
+use encrypted_file_vault::SecureConversionsExt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use tempfile::tempdir;
+
+fn leftover_temp_files(dir: &Path) -> usize {
+    fs::read_dir(dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .starts_with(ATOMIC_TEMP_PREFIX)
+        })
+        .count()
+}
+
+#[test]
+fn test_write_atomic_replaces_destination() {
+    let dir = tempdir().unwrap();
+    let dest = dir.path().join("out.bin");
+    fs::write(&dest, b"old").unwrap();
+
+    write_atomic(&dest, |f| f.write_all(b"new contents")).unwrap();
+
+    assert_eq!(fs::read(&dest).unwrap(), b"new contents");
+    assert_eq!(leftover_temp_files(dir.path()), 0);
+}
+
+#[test]
+fn test_write_atomic_failure_leaves_destination_untouched() {
+    let dir = tempdir().unwrap();
+    let dest = dir.path().join("out.bin");
+    fs::write(&dest, b"original").unwrap();
+
+    let result: io::Result<()> = write_atomic(&dest, |f| {
+        f.write_all(b"partial garbage")?;
+        Err(io::Error::other("simulated crash mid-write"))
+    });
+
+    assert!(result.is_err());
+    assert_eq!(fs::read(&dest).unwrap(), b"original");
+    assert_eq!(leftover_temp_files(dir.path()), 0);
+}
+
+#[test]
+fn test_decrypt_with_wrong_password_creates_no_output() {
+    let dir = tempdir().unwrap();
+    let plain = dir.path().join("plain.txt");
+    let enc = dir.path().join("plain.txt.aes");
+    let dec = dir.path().join("plain.out");
+    fs::write(&plain, b"do not leak half of me").unwrap();
+
+    let right = FilePassword::new(generate_key().expose_secret().to_hex());
+    let wrong = FilePassword::new(generate_key().expose_secret().to_hex());
+
+    encrypt_file(&plain, &enc, &right).unwrap();
+    assert!(decrypt_file(&enc, &dec, &wrong).is_err());
+
+    assert!(!dec.exists());
+    assert_eq!(leftover_temp_files(dir.path()), 0);
+}
+
+#[test]
+fn test_rename_durable_reports_a_failed_rename() {
+    let dir = tempdir().unwrap();
+    let dest = dir.path().join("dest.bin");
+    fs::write(&dest, b"kept").unwrap();
+
+    let err = rename_durable(dir.path().join("missing.bin"), &dest).unwrap_err();
+    assert!(matches!(err, RenameError::Rename(_)), "got {err:?}");
+    assert_eq!(fs::read(&dest).unwrap(), b"kept");
+
+    let staged = dir.path().join("staged.bin");
+    fs::write(&staged, b"new").unwrap();
+    rename_durable(&staged, &dest).unwrap();
+    assert_eq!(fs::read(&dest).unwrap(), b"new");
+}
@@ -0,0 +1,153 @@
+// tests/backup_tests.rs
+//! Encrypted backups: export, restore, passphrase and format checks
+
+mod common;
+use common::open_test_vault;
+
+use encrypted_file_vault::aliases::UserPassphrase;
+use encrypted_file_vault::export::backup::BACKUP_FORMAT;
+use encrypted_file_vault::import::JsonImportOptions;
+use encrypted_file_vault::unlock::KdfParams;
+use encrypted_file_vault::{CoreError, Vault};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+// Cheap parameters — tests only
+const FAST: KdfParams = KdfParams {
+    m_cost_kib: 64,
+    t_cost: 1,
+    p_cost: 1,
+};
+
+fn passphrase(text: &str) -> UserPassphrase {
+    UserPassphrase::new(text.to_string())
+}
+
+/// Vault holding secret-report.txt, backed up to `backup.efv`
+fn backed_up_vault(dir: &Path) -> (Vault, PathBuf) {
+    let mut vault = open_test_vault(&dir.join("a"));
+    let plain = dir.join("secret-report.txt");
+    fs::write(&plain, "quarterly numbers").unwrap();
+    let entry = vault
+        .add_file(&plain, dir.join("secret-report.txt.aes"))
+        .unwrap();
+    vault.add_tags(&entry.file_id, &["finance"]).unwrap();
+
+    let backup = dir.join("backup.efv");
+    let count = vault
+        .export_encrypted_backup(&backup, &passphrase("correct horse"), FAST)
+        .unwrap();
+    assert_eq!(count, 1);
+    (vault, backup)
+}
+
+#[test]
+fn test_backup_round_trip() {
+    let dir = tempdir().unwrap();
+    let (_, backup) = backed_up_vault(dir.path());
+
+    let raw = fs::read(&backup).unwrap();
+    let header_end = raw.iter().position(|&b| b == b'\n').unwrap();
+    let header: Value = serde_json::from_slice(&raw[..header_end]).unwrap();
+    assert_eq!(header["format"], BACKUP_FORMAT);
+    assert_eq!(header["kdf"], "argon2id");
+    assert_eq!(&raw[header_end + 1..header_end + 4], b"AES");
+    // Neither metadata nor keys are readable
+    let text = String::from_utf8_lossy(&raw);
+    assert!(!text.contains("secret-report"));
+    assert!(!text.contains("password_base64url"));
+
+    let mut vault = open_test_vault(&dir.path().join("b"));
+    let report = vault
+        .import_encrypted_backup(
+            &backup,
+            &passphrase("correct horse"),
+            &JsonImportOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(report.added.len(), 1);
+
+    let file_id = &report.added[0].file_id;
+    let mut out = Vec::new();
+    vault.extract_to_writer(file_id, &mut out).unwrap();
+    assert_eq!(out, b"quarterly numbers");
+    assert_eq!(vault.file_tags(file_id).unwrap(), ["finance"]);
+
+    let event: String = vault
+        .vault_conn()
+        .query_row(
+            "SELECT event FROM audit_log ORDER BY id DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(event, "backup-restore");
+}
+
+#[test]
+fn test_backup_refuses_wrong_passphrase_and_other_files() {
+    let dir = tempdir().unwrap();
+    let (mut source, backup) = backed_up_vault(dir.path());
+    let mut vault = open_test_vault(&dir.path().join("b"));
+    let options = JsonImportOptions::default();
+
+    assert!(matches!(
+        vault.import_encrypted_backup(&backup, &passphrase("wrong horse"), &options),
+        Err(CoreError::WrongPassword)
+    ));
+
+    // A plaintext export is not a backup
+    let json = dir.path().join("export.json");
+    source.set_allow_insecure_export(true);
+    source.export_json(&json).unwrap();
+    assert!(matches!(
+        vault.import_encrypted_backup(&json, &passphrase("correct horse"), &options),
+        Err(CoreError::InvalidBackup(_))
+    ));
+    assert!(vault.list().unwrap().is_empty());
+}
+
+#[test]
+fn test_plaintext_export_needs_opt_in() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let out = dir.path().join("export.json");
+
+    assert!(matches!(
+        vault.export_json(&out),
+        Err(CoreError::PolicyViolation(_))
+    ));
+    assert!(!out.exists());
+
+    vault.set_allow_insecure_export(true);
+    assert_eq!(vault.export_json(&out).unwrap(), 0);
+}
+
+#[test]
+fn test_backup_with_oversized_kdf_params_is_refused() {
+    let dir = tempdir().unwrap();
+    let (_, backup) = backed_up_vault(dir.path());
+    let mut vault = open_test_vault(&dir.path().join("b"));
+
+    // Same backup, header rewritten to ask for 64 GiB of Argon2id memory
+    let bytes = fs::read(&backup).unwrap();
+    let split = bytes.iter().position(|&b| b == b'\n').unwrap();
+    let mut header: Value = serde_json::from_slice(&bytes[..split]).unwrap();
+    header["params"]["m_cost_kib"] = Value::from(64u32 * 1024 * 1024 - 1);
+    let crafted = dir.path().join("crafted.efv");
+    let mut rewritten = serde_json::to_vec(&header).unwrap();
+    rewritten.extend_from_slice(&bytes[split..]);
+    fs::write(&crafted, rewritten).unwrap();
+
+    assert!(matches!(
+        vault.import_encrypted_backup(
+            &crafted,
+            &passphrase("correct horse"),
+            &JsonImportOptions::default()
+        ),
+        Err(CoreError::PolicyViolation(_))
+    ));
+    assert!(vault.list().unwrap().is_empty());
+}
@@ -4,14 +4,39 @@
 
 #![allow(dead_code)]
 
-use encrypted_file_vault::aliases::{FileKey32, RandomFileKey32, SecureRandomExt};
+use encrypted_file_vault::aliases::{
+    FileKey32, IndexKey32, RandomFileKey32, SecureRandomExt, VaultKey32,
+};
 use encrypted_file_vault::vault_db_ops::store_key_blob;
-use encrypted_file_vault::{index_db_conn::open_index_db, vault_db_conn::open_vault_db};
+use encrypted_file_vault::Vault;
+use encrypted_file_vault::{index_db_conn::open_index_db_at, vault_db_conn::open_vault_db_at};
 use rusqlite::{params, Connection};
-use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+pub const TEST_VAULT_KEY: [u8; 32] = [0x5a; 32];
+pub const TEST_INDEX_KEY: [u8; 32] = [0xa5; 32];
+
+pub fn test_vault_key() -> VaultKey32 {
+    VaultKey32::new(TEST_VAULT_KEY)
+}
+
+pub fn test_index_key() -> IndexKey32 {
+    IndexKey32::new(TEST_INDEX_KEY)
+}
+
+/// Open (or create) a vault with both databases in `dir`, under the test keys
+pub fn open_test_vault(dir: &Path) -> Vault {
+    fs::create_dir_all(dir).expect("create vault dir");
+    Vault::open(
+        dir.join("vault.db"),
+        &test_vault_key(),
+        dir.join("index.db"),
+        &test_index_key(),
+    )
+    .expect("open test vault")
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DbMode {
     Fresh,
@@ -45,17 +70,8 @@ impl TestDbPair {
         let index_path = subdir_path.join(index_name);
         let vault_path = subdir_path.join(vault_name);
 
-        // All tests run sequentially — safe to touch env vars
-        unsafe {
-            env::set_var("EFV_TEST_MODE", "1");
-            env::set_var("EFV_VAULT_DB", &vault_path);
-            env::set_var("EFV_INDEX_DB", &index_path);
-            env::set_var("EFV_VAULT_KEY", "test-vault-secret-2025");
-            env::set_var("EFV_INDEX_KEY", "test-index-secret-2025");
-        }
-
-        let vault = open_vault_db().expect("open vault db");
-        let index = open_index_db().expect("open index db");
+        let vault = open_vault_db_at(&vault_path, &test_vault_key()).expect("open vault db");
+        let index = open_index_db_at(&index_path, &test_index_key()).expect("open index db");
 
         Self {
             vault,
@@ -70,6 +86,16 @@ impl TestDbPair {
         &self.base_path
     }
 
+    #[inline]
+    pub fn vault_db_path(&self) -> PathBuf {
+        self.base_path.join("vault.db")
+    }
+
+    #[inline]
+    pub fn index_db_path(&self) -> PathBuf {
+        self.base_path.join("index.db")
+    }
+
     #[inline]
     pub fn mode(&self) -> DbMode {
         self.mode
@@ -0,0 +1,201 @@
+// tests/config_tests.rs
+//! Layered config: defaults < file < EFV_* env < programmatic, with provenance
+
+use encrypted_file_vault::config::{ConfigField, ConfigLoader, ConfigSource};
+use encrypted_file_vault::vault_db_conn::configured_vault_db;
+use serial_test::serial;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+const ALL_VARS: [&str; 10] = [
+    "EFV_CONFIG",
+    "EFV_PROFILE",
+    "EFV_VAULT_DB",
+    "EFV_INDEX_DB",
+    "EFV_VAULT_KEY",
+    "EFV_INDEX_KEY",
+    "EFV_USE_DEV_KEYS",
+    "EFV_SKIP_KDF_SLOWDOWN",
+    "EFV_ALLOW_INSECURE_EXPORT",
+    "EFV_TEST_MODE",
+];
+
+/// Sets env vars for one test and clears every EFV_* var afterwards
+struct EnvGuard;
+
+impl EnvGuard {
+    fn set(vars: &[(&str, &str)]) -> Self {
+        for name in ALL_VARS {
+            std::env::remove_var(name);
+        }
+        for (name, value) in vars {
+            std::env::set_var(name, value);
+        }
+        EnvGuard
+    }
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        for name in ALL_VARS {
+            std::env::remove_var(name);
+        }
+    }
+}
+
+fn write_config(dir: &std::path::Path, body: &str) -> PathBuf {
+    let path = dir.join("efv.toml");
+    fs::write(&path, body).unwrap();
+    path
+}
+
+#[test]
+#[serial]
+fn test_each_layer_overrides_the_one_below() {
+    let dir = tempdir().unwrap();
+    let file = write_config(
+        dir.path(),
+        r#"
+        [paths]
+        vault_db = "/from/file/vault.db"
+        index_db = "/from/file/index.db"
+
+        [features]
+        skip_kdf_slowdown = false
+        "#,
+    );
+    let _env = EnvGuard::set(&[
+        ("EFV_VAULT_DB", "/from/env/vault.db"),
+        ("EFV_INDEX_DB", "/from/env/index.db"),
+    ]);
+
+    let loaded = ConfigLoader::new()
+        .file(&file)
+        .set(ConfigField::VaultDb, "/from/code/vault.db")
+        .load()
+        .unwrap();
+
+    assert_eq!(loaded.config.paths.vault_db, "/from/code/vault.db");
+    assert_eq!(
+        *loaded.source(ConfigField::VaultDb),
+        ConfigSource::Programmatic
+    );
+
+    assert_eq!(loaded.config.paths.index_db, "/from/env/index.db");
+    assert_eq!(
+        *loaded.source(ConfigField::IndexDb),
+        ConfigSource::Env("EFV_INDEX_DB")
+    );
+
+    assert!(!loaded.config.features.skip_kdf_slowdown);
+    assert_eq!(
+        *loaded.source(ConfigField::SkipKdfSlowdown),
+        ConfigSource::File(file.clone())
+    );
+
+    assert_eq!(
+        *loaded.source(ConfigField::AllowInsecureExport),
+        ConfigSource::Default
+    );
+}
+
+#[test]
+#[serial]
+fn test_efv_config_selects_the_file() {
+    let dir = tempdir().unwrap();
+    let file = write_config(dir.path(), "[paths]\nvault_db = \"/picked/by/env.db\"\n");
+    let _env = EnvGuard::set(&[("EFV_CONFIG", file.to_str().unwrap())]);
+
+    let loaded = ConfigLoader::new().load().unwrap();
+    assert_eq!(loaded.config.paths.vault_db, "/picked/by/env.db");
+
+    // A named but missing file is an error, not a silent fallback
+    std::env::set_var("EFV_CONFIG", dir.path().join("missing.toml"));
+    assert!(ConfigLoader::new().load().is_err());
+}
+
+#[test]
+#[serial]
+fn test_boolean_env_values_and_test_mode_alias() {
+    let _env = EnvGuard::set(&[("EFV_TEST_MODE", "1"), ("EFV_ALLOW_INSECURE_EXPORT", "off")]);
+
+    let loaded = ConfigLoader::new().load().unwrap();
+    assert!(!loaded.config.features.use_dev_keys);
+    assert_eq!(
+        *loaded.source(ConfigField::UseDevKeys),
+        ConfigSource::Env("EFV_TEST_MODE")
+    );
+    assert!(!loaded.config.features.allow_insecure_export);
+
+    std::env::set_var("EFV_SKIP_KDF_SLOWDOWN", "maybe");
+    assert!(ConfigLoader::new().load().is_err());
+}
+
+#[test]
+#[serial]
+fn test_default_keys_refused_once_dev_keys_are_off() {
+    let _env = EnvGuard::set(&[("EFV_USE_DEV_KEYS", "false")]);
+
+    let loaded = ConfigLoader::new().load().unwrap();
+    assert!(loaded.key_hex(ConfigField::VaultKey).is_err());
+
+    let key = "ab".repeat(32);
+    let loaded = ConfigLoader::new()
+        .set(ConfigField::VaultKey, key.clone())
+        .load()
+        .unwrap();
+    assert_eq!(loaded.key_hex(ConfigField::VaultKey).unwrap(), key);
+}
+
+#[test]
+#[serial]
+fn test_report_lists_sources_and_hides_keys() {
+    let key = "cd".repeat(32);
+    let _env = EnvGuard::set(&[("EFV_INDEX_KEY", key.as_str())]);
+
+    let report = ConfigLoader::new()
+        .set(ConfigField::VaultDb, "/srv/vault.db")
+        .load()
+        .unwrap()
+        .report();
+
+    assert!(report.contains("paths.vault_db = /srv/vault.db  (set in code)"));
+    assert!(report.contains("keys.index_key = <redacted>  (env EFV_INDEX_KEY)"));
+    assert!(!report.contains(&key));
+}
+
+#[test]
+#[serial]
+fn test_without_env_ignores_variables() {
+    let _env = EnvGuard::set(&[("EFV_VAULT_DB", "/ignored.db")]);
+
+    let loaded = ConfigLoader::new().without_env().load().unwrap();
+    assert_ne!(loaded.config.paths.vault_db, "/ignored.db");
+    assert_eq!(*loaded.source(ConfigField::VaultDb), ConfigSource::Default);
+}
+
+#[test]
+#[serial]
+#[cfg(not(feature = "dev-config"))]
+fn test_configured_open_validates_the_config() {
+    let dir = tempdir().unwrap();
+
+    // Real keys, but the built-in tests/… paths
+    let (vault_key, index_key) = ("ab".repeat(32), "cd".repeat(32));
+    let _env = EnvGuard::set(&[
+        ("EFV_VAULT_KEY", vault_key.as_str()),
+        ("EFV_INDEX_KEY", index_key.as_str()),
+    ]);
+    let err = configured_vault_db().unwrap_err().to_string();
+    assert!(err.contains("test data directory"));
+
+    let vault_db = dir.path().join("vault.db");
+    std::env::set_var("EFV_VAULT_DB", &vault_db);
+    std::env::set_var("EFV_INDEX_DB", dir.path().join("index.db"));
+    let (path, _) = configured_vault_db().unwrap();
+    assert_eq!(path, vault_db.to_string_lossy());
+
+    std::env::set_var("EFV_USE_DEV_KEYS", "1");
+    assert!(configured_vault_db().is_err());
+}
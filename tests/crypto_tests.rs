@@ -81,5 +81,5 @@ fn test_decrypt_fails_with_wrong_password() {
         &FilePassword::new(key2.expose_secret().to_hex()),
     );
     assert!(wrong.is_err());
-    assert!(matches!(wrong, Err(CoreError::Crypto(_))));
+    assert!(matches!(wrong, Err(CoreError::WrongPassword)));
 }
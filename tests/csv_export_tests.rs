@@ -0,0 +1,240 @@
+// tests/csv_export_tests.rs
+//! CSV export: columns, RFC 4180 quoting, key modes and their opt-in
+
+mod common;
+use common::open_test_vault;
+
+use encrypted_file_vault::enums::KeyEncoding;
+use encrypted_file_vault::export::{export_to_csv, CsvColumn, CsvExportOptions, CsvKeys};
+use encrypted_file_vault::key_ops::password_representations;
+use encrypted_file_vault::vault_db_ops::load_current_key;
+use encrypted_file_vault::{CoreError, Vault};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn add(vault: &mut Vault, dir: &Path, name: &str, contents: &str) -> String {
+    let plain = dir.join("plain.txt");
+    fs::write(&plain, contents).unwrap();
+    let id = vault
+        .add_file(&plain, dir.join(format!("{}.aes", contents)))
+        .unwrap()
+        .file_id;
+    vault
+        .index_conn()
+        .execute(
+            "UPDATE files SET display_name = ?1 WHERE file_id = ?2",
+            [name, &id],
+        )
+        .unwrap();
+    id
+}
+
+#[test]
+fn test_metadata_csv_quotes_awkward_names() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let awkward = add(&mut vault, dir.path(), "Q3, \"final\"\nreport.pdf", "one");
+    add(&mut vault, dir.path(), "plain.txt", "two");
+    vault.add_tags(&awkward, &["audit", "finance"]).unwrap();
+
+    let out = dir.path().join("files.csv");
+    let options = CsvExportOptions {
+        columns: vec![CsvColumn::DisplayName, CsvColumn::FileId, CsvColumn::Tags],
+        keys: CsvKeys::Omit,
+        ..CsvExportOptions::default()
+    };
+    assert_eq!(vault.export_csv(&out, &options).unwrap(), 2);
+
+    let text = fs::read_to_string(&out).unwrap();
+    assert_eq!(
+        text,
+        format!(
+            "display_name,file_id,tags\r\n\
+             \"Q3, \"\"final\"\"\nreport.pdf\",{awkward},\"audit,finance\"\r\n\
+             plain.txt,{},\r\n",
+            vault.find_by_name("plain.txt").unwrap()[0].file_id
+        )
+    );
+}
+
+#[test]
+fn test_default_columns_carry_no_keys() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let id = add(&mut vault, dir.path(), "a.txt", "alpha");
+    let repr = password_representations(&load_current_key(vault.vault_conn(), &id).unwrap());
+
+    let out = dir.path().join("files.csv");
+    vault
+        .export_csv(&out, &CsvExportOptions::default())
+        .unwrap();
+    let text = fs::read_to_string(&out).unwrap();
+    assert!(text.starts_with(
+        "file_id,display_name,current_path,plaintext_size,created_at,rotated_at,\
+         content_hash,filename_style,tags,note\r\n"
+    ));
+    for key in [&repr.hex, &repr.base64, &repr.base64url_no_pad] {
+        assert!(!text.contains(key.as_str()));
+    }
+
+    assert!(matches!(
+        vault.export_csv(
+            &out,
+            &CsvExportOptions {
+                columns: Vec::new(),
+                keys: CsvKeys::Omit,
+                ..CsvExportOptions::default()
+            }
+        ),
+        Err(CoreError::Config(_))
+    ));
+    assert!(matches!(
+        "size".parse::<CsvColumn>(),
+        Err(CoreError::Config(_))
+    ));
+    assert_eq!(
+        "plaintext_size".parse::<CsvColumn>().unwrap(),
+        CsvColumn::PlaintextSize
+    );
+}
+
+#[test]
+fn test_key_columns_need_opt_in_and_follow_encoding() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let id = add(&mut vault, dir.path(), "a.txt", "alpha");
+    let repr = password_representations(&load_current_key(vault.vault_conn(), &id).unwrap());
+    let out = dir.path().join("keys.csv");
+
+    let with_keys = |encoding| CsvExportOptions {
+        columns: vec![CsvColumn::FileId],
+        keys: CsvKeys::Include(encoding),
+        ..CsvExportOptions::default()
+    };
+    assert!(matches!(
+        vault.export_csv(&out, &with_keys(KeyEncoding::Hex)),
+        Err(CoreError::PolicyViolation(_))
+    ));
+    assert!(!out.exists());
+
+    vault.set_allow_insecure_export(true);
+    for (encoding, header, key) in [
+        (KeyEncoding::Hex, "key_hex", &repr.hex),
+        (KeyEncoding::Base64, "key_base64", &repr.base64),
+        (
+            KeyEncoding::Base64Url,
+            "key_base64url",
+            &repr.base64url_no_pad,
+        ),
+    ] {
+        vault.export_csv(&out, &with_keys(encoding)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&out).unwrap(),
+            format!("file_id,{header}\r\n{id},{key}\r\n")
+        );
+    }
+}
+
+#[test]
+fn test_free_function_gates_keys_too() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    add(&mut vault, dir.path(), "a.txt", "alpha");
+    let out = dir.path().join("keys.csv");
+
+    let options = CsvExportOptions {
+        columns: vec![CsvColumn::FileId],
+        keys: CsvKeys::Include(KeyEncoding::Hex),
+        ..CsvExportOptions::default()
+    };
+    assert!(matches!(
+        export_to_csv(
+            vault.vault_conn(),
+            vault.index_conn(),
+            &out,
+            &options,
+            false
+        ),
+        Err(CoreError::PolicyViolation(_))
+    ));
+    assert!(!out.exists());
+
+    export_to_csv(vault.vault_conn(), vault.index_conn(), &out, &options, true).unwrap();
+    assert!(fs::read_to_string(&out)
+        .unwrap()
+        .starts_with("file_id,key_hex\r\n"));
+}
+
+#[test]
+fn test_formula_like_cells_are_neutralised() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let id = add(&mut vault, dir.path(), "=HYPERLINK(\"x\")", "alpha");
+    vault.add_tags(&id, &["@team"]).unwrap();
+    vault
+        .index_conn()
+        .execute("UPDATE files SET note = '+1 555' WHERE file_id = ?1", [&id])
+        .unwrap();
+
+    let out = dir.path().join("files.csv");
+    let options = CsvExportOptions {
+        columns: vec![CsvColumn::DisplayName, CsvColumn::Tags, CsvColumn::Note],
+        keys: CsvKeys::Omit,
+        ..CsvExportOptions::default()
+    };
+    vault.export_csv(&out, &options).unwrap();
+    assert_eq!(
+        fs::read_to_string(&out).unwrap(),
+        "display_name,tags,note\r\n\"'=HYPERLINK(\"\"x\"\")\",'@team,'+1 555\r\n"
+    );
+}
+
+#[test]
+fn test_tab_and_carriage_return_prefixes_are_neutralised() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let tab = add(&mut vault, dir.path(), "tab.txt", "alpha");
+    let cr = add(&mut vault, dir.path(), "cr.txt", "bravo");
+    for (id, note) in [(&tab, "\t=1+1"), (&cr, "\r=1+1")] {
+        vault
+            .index_conn()
+            .execute(
+                "UPDATE files SET note = ?1 WHERE file_id = ?2",
+                [note, id.as_str()],
+            )
+            .unwrap();
+    }
+
+    let out = dir.path().join("files.csv");
+    let options = CsvExportOptions {
+        columns: vec![CsvColumn::DisplayName, CsvColumn::Note],
+        keys: CsvKeys::Omit,
+        ..CsvExportOptions::default()
+    };
+    vault.export_csv(&out, &options).unwrap();
+    assert_eq!(
+        fs::read_to_string(&out).unwrap(),
+        "display_name,note\r\ncr.txt,\"'\r=1+1\"\r\ntab.txt,'\t=1+1\r\n"
+    );
+}
+
+#[test]
+fn test_neutralisation_can_be_turned_off() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let id = add(&mut vault, dir.path(), "=SUM(A1)", "alpha");
+    vault.add_tags(&id, &["@team"]).unwrap();
+
+    let out = dir.path().join("files.csv");
+    let options = CsvExportOptions {
+        columns: vec![CsvColumn::DisplayName, CsvColumn::Tags],
+        keys: CsvKeys::Omit,
+        neutralise_formulas: false,
+    };
+    vault.export_csv(&out, &options).unwrap();
+    assert_eq!(
+        fs::read_to_string(&out).unwrap(),
+        "display_name,tags\r\n=SUM(A1),@team\r\n"
+    );
+}
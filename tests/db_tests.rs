@@ -18,9 +18,7 @@ fn full_lifecycle_export_contains_correct_password() {
 
     let (file_id, key) = db.insert_test_file("Secret Document.pdf", 123_456);
 
-    drop(db); // Close connections
-
-    export_to_json(export_path.to_str().unwrap()).expect("export failed");
+    export_to_json(&db.vault, &db.index, &export_path, true).expect("export failed");
 
     let json_str = fs::read_to_string(&export_path).unwrap();
     let json: Value = serde_json::from_str(&json_str).unwrap();
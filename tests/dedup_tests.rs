@@ -0,0 +1,196 @@
+// tests/dedup_tests.rs
+//! Adding content the vault already holds: reuse, copy or reject
+
+mod common;
+use common::open_test_vault;
+
+use encrypted_file_vault::db::vault_db_ops::StoreOptions;
+use encrypted_file_vault::enums::{DedupPolicy, RemoveMode};
+use encrypted_file_vault::{CoreError, FileEntry, Vault};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+fn vault_in(dir: &Path, dedup: DedupPolicy) -> Vault {
+    let mut vault = open_test_vault(dir);
+    vault
+        .set_store_options(StoreOptions {
+            dedup,
+            ..StoreOptions::default()
+        })
+        .unwrap();
+    vault
+}
+
+fn write(dir: &Path, rel: &str, contents: &[u8]) -> PathBuf {
+    let path = dir.join(rel);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn add(vault: &mut Vault, plain: &Path, encrypted: &Path) -> FileEntry {
+    vault.add_file(plain, encrypted).unwrap()
+}
+
+#[test]
+fn test_reuse_keeps_one_ciphertext_and_records_references() {
+    let dir = tempdir().unwrap();
+    let mut vault = vault_in(dir.path(), DedupPolicy::ReuseExisting);
+    let a = write(dir.path(), "x/report.pdf", b"same bytes");
+    let b = write(dir.path(), "y/copy of report.pdf", b"same bytes");
+
+    let first = add(&mut vault, &a, &dir.path().join("first.aes"));
+    let first_ciphertext = fs::read(&first.current_path).unwrap();
+    let second = add(&mut vault, &b, &dir.path().join("second.aes"));
+
+    assert_eq!(second.file_id, first.file_id);
+    assert_eq!(second.current_path, first.current_path);
+    assert!(!dir.path().join("second.aes").exists());
+    // The first ciphertext was not replaced or orphaned
+    assert_eq!(fs::read(&first.current_path).unwrap(), first_ciphertext);
+    assert_eq!(vault.list().unwrap().len(), 1);
+
+    let refs = vault.references(&first.file_id).unwrap();
+    assert_eq!(refs.len(), 2);
+    assert_eq!(refs[1].display_name, "copy of report.pdf");
+
+    // Re-adding the same source does not count twice
+    add(&mut vault, &a, &dir.path().join("again.aes"));
+    assert_eq!(vault.references(&first.file_id).unwrap().len(), 2);
+
+    let out = dir.path().join("out.pdf");
+    vault.extract_file(&first.file_id, &out).unwrap();
+    assert_eq!(fs::read(out).unwrap(), b"same bytes");
+}
+
+#[test]
+fn test_ciphertext_is_deleted_with_the_last_reference() {
+    let dir = tempdir().unwrap();
+    let mut vault = vault_in(dir.path(), DedupPolicy::ReuseExisting);
+    let a = write(dir.path(), "x/a.txt", b"shared");
+    let b = write(dir.path(), "y/b.txt", b"shared");
+    let entry = add(&mut vault, &a, &dir.path().join("shared.aes"));
+    add(&mut vault, &b, &dir.path().join("unused.aes"));
+
+    assert!(matches!(
+        vault.remove_file(&entry.file_id, RemoveMode::HardDelete),
+        Err(CoreError::PolicyViolation(_))
+    ));
+
+    let refs = vault.references(&entry.file_id).unwrap();
+    let removed = vault
+        .remove_reference(&entry.file_id, &refs[0].source_path)
+        .unwrap();
+    assert!(removed.is_none());
+    assert!(entry.current_path.exists());
+
+    let removed = vault
+        .remove_reference(&entry.file_id, &refs[1].source_path)
+        .unwrap()
+        .expect("last reference deletes the file");
+    assert_eq!(removed.ciphertext_removed, Some(entry.current_path.clone()));
+    assert!(!entry.current_path.exists());
+    assert!(vault.list().unwrap().is_empty());
+}
+
+#[test]
+fn test_store_copy_gets_its_own_id_and_key() {
+    let dir = tempdir().unwrap();
+    let mut vault = vault_in(dir.path(), DedupPolicy::StoreCopy);
+    let a = write(dir.path(), "x/a.txt", b"twice");
+    let b = write(dir.path(), "y/a.txt", b"twice");
+
+    let first = add(&mut vault, &a, &dir.path().join("1.aes"));
+    let second = add(&mut vault, &b, &dir.path().join("2.aes"));
+    // Even from the same source
+    let third = add(&mut vault, &a, &dir.path().join("3.aes"));
+
+    assert_ne!(first.file_id, second.file_id);
+    assert_ne!(second.file_id, third.file_id);
+    assert_eq!(first.content_hash, second.content_hash);
+    assert_ne!(first.known_password_hex, second.known_password_hex);
+    assert_eq!(vault.list().unwrap().len(), 3);
+
+    vault
+        .remove_file(&first.file_id, RemoveMode::HardDelete)
+        .unwrap();
+    let out = dir.path().join("out.txt");
+    vault.extract_file(&second.file_id, &out).unwrap();
+    assert_eq!(fs::read(out).unwrap(), b"twice");
+}
+
+#[test]
+fn test_reject_fails_with_typed_error_and_leaves_nothing() {
+    let dir = tempdir().unwrap();
+    let mut vault = vault_in(dir.path(), DedupPolicy::Reject);
+    let a = write(dir.path(), "x/a.txt", b"once only");
+    let b = write(dir.path(), "y/b.txt", b"once only");
+    let first = add(&mut vault, &a, &dir.path().join("a.aes"));
+
+    match vault.add_file(&b, dir.path().join("b.aes")) {
+        Err(CoreError::DuplicateContent {
+            display_name,
+            existing_id,
+        }) => {
+            assert_eq!(display_name, "b.txt");
+            assert_eq!(existing_id, first.file_id);
+        }
+        other => panic!("expected DuplicateContent, got {other:?}"),
+    }
+    assert!(!dir.path().join("b.aes").exists());
+    let staged = fs::read_dir(dir.path())
+        .unwrap()
+        .filter(|e| {
+            e.as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with(".staging")
+        })
+        .count();
+    assert_eq!(staged, 0);
+}
+
+#[test]
+fn test_reuse_restores_trashed_content() {
+    let dir = tempdir().unwrap();
+    let mut vault = vault_in(dir.path(), DedupPolicy::ReuseExisting);
+    let a = write(dir.path(), "x/a.txt", b"came back");
+    let entry = add(&mut vault, &a, &dir.path().join("a.aes"));
+    vault
+        .remove_file(&entry.file_id, RemoveMode::SoftDelete)
+        .unwrap();
+
+    let again = add(&mut vault, &a, &dir.path().join("b.aes"));
+    assert_eq!(again.file_id, entry.file_id);
+    assert!(vault.trash().unwrap().is_empty());
+}
+
+#[test]
+fn test_existing_ciphertext_path_is_never_replaced() {
+    let dir = tempdir().unwrap();
+    let mut vault = vault_in(dir.path(), DedupPolicy::ReuseExisting);
+    let a = write(dir.path(), "x/a.txt", b"first");
+    let b = write(dir.path(), "y/b.txt", b"second");
+    let target = dir.path().join("taken.aes");
+    let entry = add(&mut vault, &a, &target);
+    let ciphertext = fs::read(&target).unwrap();
+
+    match vault.add_file(&b, &target) {
+        Err(CoreError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::AlreadyExists),
+        other => panic!("expected AlreadyExists, got {other:?}"),
+    }
+    assert_eq!(fs::read(&target).unwrap(), ciphertext);
+    assert_eq!(vault.list().unwrap().len(), 1);
+    // The refused file's key was taken back out
+    let keys: i64 = vault
+        .vault_conn()
+        .query_row("SELECT COUNT(*) FROM key_history", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(keys, 1);
+
+    let out = dir.path().join("out.txt");
+    vault.extract_file(&entry.file_id, &out).unwrap();
+    assert_eq!(fs::read(out).unwrap(), b"first");
+}
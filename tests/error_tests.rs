@@ -0,0 +1,126 @@
+// tests/error_tests.rs
+//! Typed `CoreError` variants instead of panics / opaque errors
+
+mod common;
+use common::open_test_vault;
+
+use encrypted_file_vault::aliases::{CypherText, FilePassword, PlainText};
+use encrypted_file_vault::crypto::{decrypt_to_vec, encrypt_to_vec};
+use encrypted_file_vault::key_ops::generate_key;
+use encrypted_file_vault::{CoreError, SecureConversionsExt};
+use serde_json::Value;
+use std::fs;
+use tempfile::tempdir;
+
+/// Last (largest) v0 test vector, encrypted under "Hello"
+fn v0_vector() -> CypherText {
+    let vectors: Value =
+        serde_json::from_str(&fs::read_to_string("tests/data_input/test_vectors_v0.json").unwrap())
+            .unwrap();
+    let last = vectors.as_array().unwrap().last().unwrap();
+    CypherText::new(hex::decode(last["ciphertext"].as_str().unwrap()).unwrap())
+}
+
+#[test]
+fn test_truncated_ciphertext_is_corrupt_not_wrong_password() {
+    let password = FilePassword::new(generate_key().expose_secret().to_hex());
+    // Big enough that plaintext is flowing before the input runs out
+    let ciphertext = encrypt_to_vec(&PlainText::new(vec![0x5a; 1 << 20]), &password).unwrap();
+
+    let mut bytes = ciphertext.expose_secret().clone();
+    bytes.truncate(bytes.len() - 40);
+
+    let result = decrypt_to_vec(&CypherText::new(bytes), &password);
+    assert!(matches!(result, Err(CoreError::CorruptCiphertext(_))));
+}
+
+#[test]
+fn test_damaged_header_is_corrupt_not_wrong_password() {
+    let password = FilePassword::new(generate_key().expose_secret().to_hex());
+    let ciphertext = encrypt_to_vec(&PlainText::new(b"short".to_vec()), &password).unwrap();
+
+    // Cut off inside the header, before any password check could run
+    let mut bytes = ciphertext.expose_secret().clone();
+    bytes.truncate(12);
+
+    let result = decrypt_to_vec(&CypherText::new(bytes), &password);
+    assert!(matches!(result, Err(CoreError::CorruptCiphertext(_))));
+}
+
+#[test]
+fn test_v0_wrong_password_is_wrong_password() {
+    let ciphertext = v0_vector();
+    assert!(decrypt_to_vec(&ciphertext, &FilePassword::new("Hello".to_string())).is_ok());
+
+    // v0 has no session HMAC: the password is only checked after the payload
+    assert!(matches!(
+        decrypt_to_vec(&ciphertext, &FilePassword::new("Goodbye".to_string())),
+        Err(CoreError::WrongPassword)
+    ));
+}
+
+#[test]
+fn test_wrong_password_is_not_corrupt() {
+    let password = FilePassword::new(generate_key().expose_secret().to_hex());
+    let ciphertext = encrypt_to_vec(&PlainText::new(vec![0x5a; 1 << 20]), &password).unwrap();
+
+    let other = FilePassword::new(generate_key().expose_secret().to_hex());
+    assert!(matches!(
+        decrypt_to_vec(&ciphertext, &other),
+        Err(CoreError::WrongPassword)
+    ));
+}
+
+#[test]
+fn test_non_aescrypt_input_is_corrupt() {
+    let password = FilePassword::new("00".repeat(32));
+    let result = decrypt_to_vec(&CypherText::new(b"plain old text".to_vec()), &password);
+    assert!(matches!(result, Err(CoreError::CorruptCiphertext(_))));
+}
+
+#[test]
+fn test_unknown_file_id_is_not_found() {
+    let dir = tempdir().unwrap();
+    let vault = open_test_vault(dir.path());
+
+    match vault.extract_file("no-such-id", dir.path().join("out")) {
+        Err(CoreError::NotFound(id)) => assert_eq!(id, "no-such-id"),
+        other => panic!("expected NotFound, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_indexed_file_without_key_is_missing_key() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+
+    let plain = dir.path().join("orphan.txt");
+    fs::write(&plain, b"key goes missing").unwrap();
+    let entry = vault
+        .add_file(&plain, dir.path().join("orphan.txt.aes"))
+        .unwrap();
+
+    vault
+        .vault_conn()
+        .execute_batch("DELETE FROM keys; DELETE FROM key_history;")
+        .unwrap();
+
+    assert!(matches!(
+        vault.extract_file(&entry.file_id, dir.path().join("out")),
+        Err(CoreError::MissingKey(_))
+    ));
+    assert!(matches!(
+        vault.rotate(&entry.file_id, None),
+        Err(CoreError::MissingKey(_))
+    ));
+}
+
+#[test]
+fn test_plaintext_path_without_file_name_is_invalid_path() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+
+    let result = vault.add_file(dir.path().join(".."), dir.path().join("x.aes"));
+    assert!(matches!(result, Err(CoreError::InvalidPath(_))));
+    assert!(vault.list().unwrap().is_empty());
+}
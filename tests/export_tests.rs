@@ -7,6 +7,7 @@ use common::{DbMode, TestDbPair};
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::Engine;
 use encrypted_file_vault::export::json::export_to_json;
+use encrypted_file_vault::CoreError;
 use serde_json::Value;
 use serial_test::serial; // ← This is the only thing you need
 use std::fs;
@@ -22,9 +23,7 @@ fn export_contains_correct_password_and_metadata() {
     let export_dir = tempdir().unwrap();
     let export_path = export_dir.path().join("vault-export.json");
 
-    drop(db);
-
-    export_to_json(export_path.to_str().unwrap()).expect("export failed");
+    export_to_json(&db.vault, &db.index, &export_path, true).expect("export failed");
 
     let json_str = fs::read_to_string(&export_path).unwrap();
     let json: Value = serde_json::from_str(&json_str).unwrap();
@@ -56,9 +55,7 @@ fn export_multiple_files_all_correct() {
     let export_dir = tempdir().unwrap();
     let export_path = export_dir.path().join("full-vault.json");
 
-    drop(db);
-
-    export_to_json(export_path.to_str().unwrap()).expect("export failed");
+    export_to_json(&db.vault, &db.index, &export_path, true).expect("export failed");
 
     let json_str = fs::read_to_string(&export_path).unwrap();
     let json: Value = serde_json::from_str(&json_str).unwrap();
@@ -110,3 +107,19 @@ fn export_multiple_files_all_correct() {
         vec!["Love Letter.txt", "Photo.jpg", "Taxes 2024.pdf"]
     );
 }
+
+#[test]
+#[serial]
+fn export_refused_without_opt_in() {
+    let mut db = TestDbPair::new(DbMode::Fresh);
+    db.insert_test_file("Keep Out.txt", 10);
+
+    let export_dir = tempdir().unwrap();
+    let export_path = export_dir.path().join("refused.json");
+
+    assert!(matches!(
+        export_to_json(&db.vault, &db.index, &export_path, false),
+        Err(CoreError::PolicyViolation(_))
+    ));
+    assert!(!export_path.exists());
+}
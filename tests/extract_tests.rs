@@ -0,0 +1,117 @@
+// tests/extract_tests.rs
+//! Getting files back out: by id, into a writer, by display name
+
+mod common;
+use common::open_test_vault;
+
+use encrypted_file_vault::{CoreError, Vault};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+/// Add `contents` as `<sub>/<name>`; returns the file id
+fn add(vault: &mut Vault, dir: &Path, sub: &str, name: &str, contents: &[u8]) -> String {
+    let src = dir.join(sub);
+    fs::create_dir_all(&src).unwrap();
+    let plain = src.join(name);
+    fs::write(&plain, contents).unwrap();
+    vault
+        .add_file(&plain, src.join(format!("{name}.aes")))
+        .unwrap()
+        .file_id
+}
+
+#[test]
+fn test_extract_to_writer_streams_verified_plaintext() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let id = add(
+        &mut vault,
+        dir.path(),
+        "a",
+        "memo.txt",
+        b"straight into memory",
+    );
+
+    let mut out = Vec::new();
+    let size = vault.extract_to_writer(&id, &mut out).unwrap();
+    assert_eq!(size, 20);
+    assert_eq!(out, b"straight into memory");
+}
+
+#[test]
+fn test_hash_mismatch_fails_and_leaves_no_output() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let id = add(
+        &mut vault,
+        dir.path(),
+        "a",
+        "memo.txt",
+        b"verified on the way out",
+    );
+    let content_hash = vault.entry(&id).unwrap().content_hash;
+
+    vault
+        .index_conn()
+        .execute(
+            "UPDATE files SET content_hash = ?1 WHERE file_id = ?2",
+            ["00".repeat(32), id.clone()],
+        )
+        .unwrap();
+
+    let dest = dir.path().join("memo.out");
+    match vault.extract_file(&id, &dest) {
+        Err(CoreError::IntegrityMismatch {
+            file_id,
+            expected,
+            actual,
+        }) => {
+            assert_eq!(file_id, id);
+            assert_eq!(expected, "00".repeat(32));
+            assert_eq!(actual, content_hash);
+        }
+        other => panic!("expected IntegrityMismatch, got {:?}", other),
+    }
+    assert!(!dest.exists());
+}
+
+#[test]
+fn test_extract_by_unique_name() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    add(&mut vault, dir.path(), "a", "only.txt", b"one of a kind");
+
+    let dest = dir.path().join("only.out");
+    vault.extract_by_name("only.txt", &dest).unwrap();
+    assert_eq!(fs::read(&dest).unwrap(), b"one of a kind");
+
+    assert!(matches!(
+        vault.extract_by_name("missing.txt", dir.path().join("x")),
+        Err(CoreError::NameNotFound(name)) if name == "missing.txt"
+    ));
+}
+
+#[test]
+fn test_duplicate_names_need_disambiguation() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let first = add(&mut vault, dir.path(), "2024", "report.pdf", b"last year");
+    let second = add(&mut vault, dir.path(), "2025", "report.pdf", b"this year");
+
+    let dest = dir.path().join("report.out");
+    match vault.extract_by_name("report.pdf", &dest) {
+        Err(CoreError::Ambiguous { name, candidates }) => {
+            assert_eq!(name, "report.pdf");
+            let mut expected = vec![first.clone(), second.clone()];
+            expected.sort();
+            assert_eq!(candidates, expected);
+        }
+        other => panic!("expected Ambiguous, got {:?}", other),
+    }
+    assert!(!dest.exists());
+
+    // Picking one by id resolves it
+    vault.extract_file(&second, &dest).unwrap();
+    assert_eq!(fs::read(&dest).unwrap(), b"this year");
+}
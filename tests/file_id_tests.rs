@@ -0,0 +1,121 @@
+// tests/file_id_tests.rs
+//! Keyed file ids: no plain BLAKE3 in the index, path salting, legacy upgrade
+
+mod common;
+use common::open_test_vault;
+
+use encrypted_file_vault::db::vault_db_ops::StoreOptions;
+use encrypted_file_vault::enums::DedupPolicy;
+use encrypted_file_vault::file_id::{FileIdHasher, FILE_ID_SCHEME};
+use encrypted_file_vault::{FileEntry, Vault};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn store(vault: &mut Vault, path: &Path, contents: &[u8]) -> FileEntry {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+    vault.store_file(path).unwrap()
+}
+
+#[test]
+fn test_ids_are_keyed_per_vault() {
+    let contents = b"a document everyone has";
+    let plain_hash = blake3::hash(contents).to_hex().to_string();
+
+    let (one, two) = (tempdir().unwrap(), tempdir().unwrap());
+    let mut first = open_test_vault(one.path());
+    let mut second = open_test_vault(two.path());
+    let a = store(&mut first, &one.path().join("doc.txt"), contents);
+    let b = store(&mut second, &two.path().join("doc.txt"), contents);
+
+    // Nothing in the index confirms the known plaintext hash
+    assert_ne!(a.file_id, plain_hash);
+    assert_ne!(a.content_hash, plain_hash);
+    assert_ne!(a.file_id, a.content_hash);
+    // Different vaults, different secrets
+    assert_ne!(a.file_id, b.file_id);
+
+    let out = one.path().join("doc.out");
+    first.extract_file(&a.file_id, &out).unwrap();
+    assert_eq!(fs::read(out).unwrap(), contents);
+}
+
+#[test]
+fn test_path_salt_separates_identical_files() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    vault
+        .set_store_options(StoreOptions {
+            salt_with_path: true,
+            dedup: DedupPolicy::StoreCopy,
+            ..StoreOptions::default()
+        })
+        .unwrap();
+
+    let a = store(&mut vault, &dir.path().join("x/same.txt"), b"same bytes");
+    let b = store(&mut vault, &dir.path().join("y/same.txt"), b"same bytes");
+
+    assert_ne!(a.file_id, b.file_id);
+    assert_eq!(a.content_hash, b.content_hash);
+    assert!(vault.entry(&a.file_id).unwrap().salted_with_path);
+    assert_eq!(vault.list().unwrap().len(), 2);
+}
+
+#[test]
+fn test_legacy_ids_are_rekeyed_in_both_databases() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let entry = store(
+        &mut vault,
+        &dir.path().join("old.txt"),
+        b"from before keyed ids",
+    );
+    vault.add_tags(&entry.file_id, &["legacy"]).unwrap();
+    let plain_hash = blake3::hash(b"from before keyed ids").to_hex().to_string();
+
+    // Rewind to the plain-BLAKE3 scheme
+    for (conn, sql) in [
+        (
+            vault.vault_conn(),
+            "UPDATE keys SET file_id = ?1 WHERE file_id = ?2",
+        ),
+        (
+            vault.vault_conn(),
+            "UPDATE key_history SET file_id = ?1 WHERE file_id = ?2",
+        ),
+        (
+            vault.index_conn(),
+            "UPDATE files SET file_id = ?1, content_hash = ?1 WHERE file_id = ?2",
+        ),
+        (
+            vault.index_conn(),
+            "UPDATE file_tags SET file_id = ?1 WHERE file_id = ?2",
+        ),
+    ] {
+        conn.execute(sql, [&plain_hash, &entry.file_id]).unwrap();
+    }
+    vault
+        .vault_conn()
+        .execute("DELETE FROM vault_meta WHERE name = ?1", [FILE_ID_SCHEME])
+        .unwrap();
+    assert!(!FileIdHasher::load(vault.vault_conn()).unwrap().is_keyed());
+    drop(vault);
+
+    let vault = open_test_vault(dir.path());
+    assert!(FileIdHasher::load(vault.vault_conn()).unwrap().is_keyed());
+    let upgraded = vault.entry(&entry.file_id).unwrap();
+    assert_eq!(upgraded.content_hash, entry.content_hash);
+    assert_eq!(vault.file_tags(&entry.file_id).unwrap(), ["legacy"]);
+    assert!(vault.entry(&plain_hash).is_err());
+
+    let out = dir.path().join("old.out");
+    vault.extract_file(&entry.file_id, &out).unwrap();
+    assert_eq!(fs::read(out).unwrap(), b"from before keyed ids");
+
+    let remap: i64 = vault
+        .vault_conn()
+        .query_row("SELECT COUNT(*) FROM file_id_remap", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(remap, 0);
+}
@@ -1,9 +1,10 @@
 // tests/core/file.rs
 use encrypted_file_vault::aliases::FilePassword;
-use encrypted_file_vault::file_ops::{decrypt_file, encrypt_file};
+use encrypted_file_vault::file_ops::{decrypt_file, decrypt_stream, encrypt_file, encrypt_stream};
 use encrypted_file_vault::key_ops::generate_key;
 use encrypted_file_vault::SecureConversionsExt;
 use std::fs;
+use std::io::Cursor;
 use tempfile::tempdir; // This is the missing line!
 
 #[test]
@@ -27,3 +28,42 @@ fn test_encrypt_file_and_decrypt_file_roundtrip() {
     assert_eq!(size1, size2);
     assert_eq!(fs::read(&dec).unwrap(), fs::read(&plain).unwrap());
 }
+
+#[test]
+fn test_streaming_roundtrip_larger_than_buffer() {
+    let dir = tempdir().unwrap();
+    let plain = dir.path().join("big.bin");
+    let enc = dir.path().join("big.bin.aes");
+    let dec = dir.path().join("big.out");
+
+    // Deliberately not a multiple of STREAM_BUFFER_SIZE
+    let data: Vec<u8> = (0..(3 * 1024 * 1024 + 17))
+        .map(|i| (i % 251) as u8)
+        .collect();
+    fs::write(&plain, &data).unwrap();
+
+    let password = FilePassword::new(generate_key().expose_secret().to_hex());
+
+    let size1 = encrypt_file(&plain, &enc, &password).unwrap();
+    let size2 = decrypt_file(&enc, &dec, &password).unwrap();
+
+    assert_eq!(size1, data.len() as u64);
+    assert_eq!(size2, data.len() as u64);
+    assert_eq!(fs::read(&dec).unwrap(), data);
+}
+
+#[test]
+fn test_encrypt_stream_and_decrypt_stream_in_memory() {
+    let password = FilePassword::new(generate_key().expose_secret().to_hex());
+    let data = b"streamed through cursors".to_vec();
+
+    let mut ciphertext = Vec::new();
+    let written = encrypt_stream(Cursor::new(&data), &mut ciphertext, &password).unwrap();
+    assert_eq!(written, data.len() as u64);
+    assert!(ciphertext.starts_with(b"AES\x03"));
+
+    let mut plaintext = Vec::new();
+    let read = decrypt_stream(Cursor::new(&ciphertext), &mut plaintext, &password).unwrap();
+    assert_eq!(read, data.len() as u64);
+    assert_eq!(plaintext, data);
+}
@@ -0,0 +1,270 @@
+// tests/import_tests.rs
+//! import_directory: globs, .gitignore, duplicates, resuming
+
+mod common;
+use common::open_test_vault;
+
+use encrypted_file_vault::import::{DirectoryImportOptions, SkipReason};
+use encrypted_file_vault::CoreError;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tempfile::tempdir;
+
+fn write(root: &Path, rel: &str, contents: &str) {
+    let path = root.join(rel);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// docs/a.pdf, docs/2024/b.pdf, notes.txt, build/out.bin, docs/copy.pdf (= a.pdf)
+fn sample_tree(root: &Path) {
+    write(root, "docs/a.pdf", "pdf a");
+    write(root, "docs/2024/b.pdf", "pdf b");
+    write(root, "notes.txt", "notes");
+    write(root, "build/out.bin", "binary");
+    write(root, "docs/copy.pdf", "pdf a");
+}
+
+fn sorted(mut paths: Vec<String>) -> Vec<String> {
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_import_keeps_relative_paths_and_skips_duplicates() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("tree");
+    sample_tree(&root);
+    let mut vault = open_test_vault(dir.path());
+
+    let report = vault
+        .import_directory(&root, &DirectoryImportOptions::default())
+        .unwrap();
+
+    let added: Vec<String> = report
+        .added
+        .iter()
+        .map(|f| f.relative_path.clone())
+        .collect();
+    assert_eq!(
+        sorted(added),
+        [
+            "build/out.bin",
+            "docs/2024/b.pdf",
+            "docs/a.pdf",
+            "notes.txt"
+        ]
+    );
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].relative_path, "docs/copy.pdf");
+    assert!(matches!(
+        report.skipped[0].reason,
+        SkipReason::Duplicate { .. }
+    ));
+    assert!(report.is_complete());
+
+    let b = report
+        .added
+        .iter()
+        .find(|f| f.relative_path == "docs/2024/b.pdf")
+        .unwrap();
+    let refs = vault.references(&b.file_id).unwrap();
+    assert_eq!(refs[0].relative_path.as_deref(), Some("docs/2024/b.pdf"));
+
+    // The skipped copy is still recorded against the content it duplicates
+    let a = report
+        .added
+        .iter()
+        .find(|f| f.relative_path == "docs/a.pdf")
+        .unwrap();
+    let a_paths: Vec<String> = vault
+        .references(&a.file_id)
+        .unwrap()
+        .into_iter()
+        .filter_map(|r| r.relative_path)
+        .collect();
+    assert_eq!(sorted(a_paths), ["docs/a.pdf", "docs/copy.pdf"]);
+
+    let out = dir.path().join("b.out");
+    vault.extract_file(&b.file_id, &out).unwrap();
+    assert_eq!(fs::read_to_string(out).unwrap(), "pdf b");
+
+    // Everything is already there the second time
+    let again = vault
+        .import_directory(&root, &DirectoryImportOptions::default())
+        .unwrap();
+    assert!(again.added.is_empty());
+    assert_eq!(again.skipped.len(), 5);
+}
+
+#[test]
+fn test_include_exclude_globs() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("tree");
+    sample_tree(&root);
+    let mut vault = open_test_vault(dir.path());
+
+    let options = DirectoryImportOptions {
+        include: vec!["*.pdf".into(), "*.bin".into()],
+        exclude: vec!["build".into(), "**/2024/**".into()],
+        ..Default::default()
+    };
+    let report = vault.import_directory(&root, &options).unwrap();
+
+    let added: Vec<&str> = report
+        .added
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .collect();
+    assert_eq!(added, ["docs/a.pdf"]);
+    assert_eq!(report.skipped.len(), 1);
+
+    assert!(matches!(
+        vault.import_directory(
+            &root,
+            &DirectoryImportOptions {
+                include: vec!["[".into()],
+                ..Default::default()
+            }
+        ),
+        Err(CoreError::Config(_))
+    ));
+}
+
+#[test]
+fn test_gitignore_and_hidden_files() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("tree");
+    sample_tree(&root);
+    write(&root, ".gitignore", "build/\n*.txt\n");
+    write(&root, ".git/config", "[core]");
+    write(&root, ".env", "SECRET=1");
+    let mut vault = open_test_vault(dir.path());
+
+    let options = DirectoryImportOptions {
+        respect_gitignore: true,
+        skip_hidden: true,
+        ..Default::default()
+    };
+    let report = vault.import_directory(&root, &options).unwrap();
+
+    let added: Vec<String> = report
+        .added
+        .iter()
+        .map(|f| f.relative_path.clone())
+        .collect();
+    assert_eq!(sorted(added), ["docs/2024/b.pdf", "docs/a.pdf"]);
+
+    // Without filters the dot-files come in too
+    let report = vault
+        .import_directory(&root, &DirectoryImportOptions::default())
+        .unwrap();
+    let added: Vec<String> = report
+        .added
+        .iter()
+        .map(|f| f.relative_path.clone())
+        .collect();
+    assert_eq!(
+        sorted(added),
+        [
+            ".env",
+            ".git/config",
+            ".gitignore",
+            "build/out.bin",
+            "notes.txt"
+        ]
+    );
+}
+
+#[test]
+fn test_vault_inside_the_tree_is_not_imported() {
+    let dir = tempdir().unwrap();
+    write(dir.path(), "photos/cat.jpg", "meow");
+    let mut vault = open_test_vault(dir.path());
+    vault.store_file(dir.path().join("photos/cat.jpg")).unwrap();
+
+    let report = vault
+        .import_directory(dir.path(), &DirectoryImportOptions::default())
+        .unwrap();
+    assert!(report.added.is_empty());
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].relative_path, "photos/cat.jpg");
+}
+
+#[test]
+fn test_cancelled_import_resumes_from_the_journal() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("tree");
+    sample_tree(&root);
+    let mut vault = open_test_vault(dir.path());
+
+    let cancel = Arc::new(AtomicBool::new(true));
+    let options = DirectoryImportOptions {
+        cancel: Some(cancel.clone()),
+        ..Default::default()
+    };
+    assert!(matches!(
+        vault.import_directory(&root, &options),
+        Err(CoreError::Cancelled)
+    ));
+    assert!(vault.list().unwrap().is_empty());
+
+    // What a run killed after docs/a.pdf and notes.txt leaves behind
+    let a = vault.store_file(root.join("docs/a.pdf")).unwrap();
+    let notes = vault.store_file(root.join("notes.txt")).unwrap();
+    let root_key = root.canonicalize().unwrap();
+    for (rel, id) in [("docs/a.pdf", &a.file_id), ("notes.txt", &notes.file_id)] {
+        let meta = fs::metadata(root.join(rel)).unwrap();
+        let modified = meta
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as i64;
+        vault
+            .index_conn()
+            .execute(
+                "INSERT INTO import_journal \
+                 (root, relative_path, size, modified_ns, outcome, file_id) \
+                 VALUES (?1, ?2, ?3, ?4, 'added', ?5)",
+                rusqlite::params![
+                    root_key.to_str().unwrap(),
+                    rel,
+                    meta.len() as i64,
+                    modified,
+                    id
+                ],
+            )
+            .unwrap();
+    }
+    // notes.txt changed after it was imported
+    write(&root, "notes.txt", "notes, edited");
+
+    cancel.store(false, std::sync::atomic::Ordering::Relaxed);
+    let report = vault.import_directory(&root, &options).unwrap();
+
+    let added: Vec<String> = report
+        .added
+        .iter()
+        .map(|f| f.relative_path.clone())
+        .collect();
+    assert_eq!(
+        sorted(added),
+        ["build/out.bin", "docs/2024/b.pdf", "notes.txt"]
+    );
+    let resumed: Vec<&str> = report
+        .skipped
+        .iter()
+        .filter(|s| matches!(s.reason, SkipReason::AlreadyImported { .. }))
+        .map(|s| s.relative_path.as_str())
+        .collect();
+    assert_eq!(resumed, ["docs/a.pdf"]);
+
+    let left: i64 = vault
+        .index_conn()
+        .query_row("SELECT COUNT(*) FROM import_journal", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(left, 0);
+}
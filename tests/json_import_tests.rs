@@ -0,0 +1,295 @@
+// tests/json_import_tests.rs
+//! import_from_json: restoring an export, conflicts, validation
+
+mod common;
+use common::open_test_vault;
+
+use encrypted_file_vault::enums::ConflictPolicy;
+use encrypted_file_vault::import::{JsonImportOptions, SkipReason};
+use encrypted_file_vault::{CoreError, Vault};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+/// Vault with a.txt (tagged, noted) and b.txt, exported to `export.json`
+fn exported_vault(dir: &Path) -> (Vault, PathBuf) {
+    let mut vault = open_test_vault(&dir.join("a"));
+    for (name, contents) in [("a.txt", "alpha"), ("b.txt", "bravo")] {
+        let plain = dir.join(name);
+        fs::write(&plain, contents).unwrap();
+        vault
+            .add_file(&plain, dir.join(format!("{name}.aes")))
+            .unwrap();
+    }
+    let a = vault.find_by_name("a.txt").unwrap().remove(0);
+    vault.add_tags(&a.file_id, &["work", "2024"]).unwrap();
+    vault
+        .index_conn()
+        .execute(
+            "UPDATE files SET note = 'keep' WHERE file_id = ?1",
+            [&a.file_id],
+        )
+        .unwrap();
+
+    let export = dir.join("export.json");
+    vault.set_allow_insecure_export(true);
+    vault.export_json(&export).unwrap();
+    (vault, export)
+}
+
+fn extract(vault: &Vault, file_id: &str) -> String {
+    let mut out = Vec::new();
+    vault.extract_to_writer(file_id, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_restore_into_a_new_vault_rekeys_ids() {
+    let dir = tempdir().unwrap();
+    let (source, export) = exported_vault(dir.path());
+    let mut vault = open_test_vault(&dir.path().join("b"));
+
+    let report = vault
+        .import_from_json(&export, &JsonImportOptions::default())
+        .unwrap();
+    assert_eq!(report.added.len(), 2);
+    assert!(report.is_complete());
+
+    let restored = vault.find_by_name("a.txt").unwrap().remove(0);
+    let original = source.find_by_name("a.txt").unwrap().remove(0);
+    // Ids are keyed with this vault's secret, not the exporting vault's
+    assert_ne!(restored.file_id, original.file_id);
+    // The report maps each exported id to the id it was restored under
+    let added = report
+        .added
+        .iter()
+        .find(|f| f.file_id == restored.file_id)
+        .unwrap();
+    assert_eq!(added.source_id.as_deref(), Some(original.file_id.as_str()));
+    assert!(added.relative_path.is_empty());
+    assert_eq!(extract(&vault, &restored.file_id), "alpha");
+    assert_eq!(
+        vault.file_tags(&restored.file_id).unwrap(),
+        ["2024", "work"]
+    );
+
+    let (note, key_note): (String, String) = (
+        vault
+            .index_conn()
+            .query_row(
+                "SELECT note FROM files WHERE file_id = ?1",
+                [&restored.file_id],
+                |row| row.get(0),
+            )
+            .unwrap(),
+        vault
+            .vault_conn()
+            .query_row(
+                "SELECT note FROM key_history WHERE file_id = ?1",
+                [&restored.file_id],
+                |row| row.get(0),
+            )
+            .unwrap(),
+    );
+    assert_eq!(note, "keep");
+    assert_eq!(key_note, "restored");
+}
+
+#[test]
+fn test_conflict_policies() {
+    let dir = tempdir().unwrap();
+    let (mut vault, export) = exported_vault(dir.path());
+    let ids: Vec<String> = vault
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|e| e.file_id)
+        .collect();
+
+    let skip = vault
+        .import_from_json(&export, &JsonImportOptions::default())
+        .unwrap();
+    assert!(skip.added.is_empty());
+    assert!(skip.skipped.iter().all(
+        |s| matches!(&s.reason, SkipReason::Conflict { existing_id } if ids.contains(existing_id))
+    ));
+    assert!(skip
+        .skipped
+        .iter()
+        .all(|s| ids.contains(s.source_id.as_ref().unwrap())));
+
+    let overwrite = vault
+        .import_from_json(
+            &export,
+            &JsonImportOptions {
+                conflict: ConflictPolicy::Overwrite,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(overwrite.added.len(), 2);
+    assert_eq!(vault.list().unwrap().len(), 2);
+    let versions: i64 = vault
+        .vault_conn()
+        .query_row(
+            "SELECT COUNT(*) FROM key_history WHERE file_id = ?1",
+            [&ids[0]],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(versions, 2);
+
+    let both = vault
+        .import_from_json(
+            &export,
+            &JsonImportOptions {
+                conflict: ConflictPolicy::KeepBoth,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(both.added.len(), 2);
+    let entries = vault.list().unwrap();
+    assert_eq!(entries.len(), 4);
+    for added in &both.added {
+        assert!(!ids.contains(&added.file_id));
+        let copy = vault.entry(&added.file_id).unwrap();
+        let original = vault.entry(added.source_id.as_ref().unwrap()).unwrap();
+        assert_ne!(copy.current_path, original.current_path);
+        assert_eq!(
+            extract(&vault, &added.file_id),
+            extract(&vault, &original.file_id)
+        );
+    }
+}
+
+#[test]
+fn test_invalid_documents_are_refused() {
+    let dir = tempdir().unwrap();
+    let (_, export) = exported_vault(dir.path());
+    let mut vault = open_test_vault(&dir.path().join("b"));
+    let doc: Value = serde_json::from_str(&fs::read_to_string(&export).unwrap()).unwrap();
+
+    let write = |doc: &Value| {
+        let path = dir.path().join("edited.json");
+        fs::write(&path, doc.to_string()).unwrap();
+        path
+    };
+
+    let mut wrong_format = doc.clone();
+    wrong_format["export_format"] = "encrypted-file-vault-v9".into();
+    let mut truncated = doc.clone();
+    truncated["files"].as_array_mut().unwrap().pop();
+
+    for bad in [wrong_format, truncated, Value::String("nope".into())] {
+        assert!(matches!(
+            vault.import_from_json(write(&bad), &JsonImportOptions::default()),
+            Err(CoreError::InvalidBackup(_))
+        ));
+    }
+    assert!(vault.list().unwrap().is_empty());
+
+    // Bad entries fail on their own
+    let mut entries = doc.clone();
+    let (a, b) = (
+        entries["files"][0]["password_base64url"].clone(),
+        entries["files"][1]["password_base64url"].clone(),
+    );
+    entries["files"][0]["password_base64url"] = b;
+    entries["files"][1]["password_base64url"] = "c2hvcnQ".into();
+    let report = vault
+        .import_from_json(write(&entries), &JsonImportOptions::default())
+        .unwrap();
+    assert!(report.added.is_empty());
+    assert!(matches!(report.failed[0].error, CoreError::WrongPassword));
+    assert!(matches!(
+        report.failed[1].error,
+        CoreError::InvalidBackup(_)
+    ));
+
+    // From another vault's ids, verification cannot be turned off
+    entries["files"][1]["password_base64url"] = a;
+    let report = vault
+        .import_from_json(
+            write(&entries),
+            &JsonImportOptions {
+                verify: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert!(report.added.is_empty());
+    assert!(matches!(report.failed[0].error, CoreError::WrongPassword));
+}
+
+#[test]
+fn test_unverified_restore_into_a_new_vault_can_be_extracted() {
+    let dir = tempdir().unwrap();
+    let (_, export) = exported_vault(dir.path());
+    let mut vault = open_test_vault(&dir.path().join("b"));
+
+    let report = vault
+        .import_from_json(
+            &export,
+            &JsonImportOptions {
+                verify: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(report.added.len(), 2);
+    for added in &report.added {
+        assert!(!extract(&vault, &added.file_id).is_empty());
+    }
+}
+
+#[test]
+fn test_overwrite_keeps_references_and_replaces_ciphertext() {
+    let dir = tempdir().unwrap();
+    let (mut vault, export) = exported_vault(dir.path());
+    let a = vault.find_by_name("a.txt").unwrap().remove(0);
+    let b = vault.find_by_name("b.txt").unwrap().remove(0);
+
+    // The export points a.txt at a copy elsewhere
+    let moved = dir.path().join("moved.aes");
+    fs::copy(&a.current_path, &moved).unwrap();
+    let mut doc: Value = serde_json::from_str(&fs::read_to_string(&export).unwrap()).unwrap();
+    for file in doc["files"].as_array_mut().unwrap() {
+        if file["file_id"] == a.file_id.as_str() {
+            file["current_path"] = moved.to_str().unwrap().into();
+        }
+    }
+    fs::write(&export, doc.to_string()).unwrap();
+
+    // A key rotated since the export must not be replaced by the stale one
+    vault.rotate(&b.file_id, None).unwrap();
+
+    let report = vault
+        .import_from_json(
+            &export,
+            &JsonImportOptions {
+                conflict: ConflictPolicy::Overwrite,
+                verify: false,
+            },
+        )
+        .unwrap();
+    assert_eq!(report.added.len(), 1);
+    assert!(matches!(report.failed[0].error, CoreError::WrongPassword));
+    assert_eq!(extract(&vault, &b.file_id), "bravo");
+
+    let restored = vault.entry(&a.file_id).unwrap();
+    assert_eq!(restored.current_path, moved);
+    assert!(!a.current_path.exists());
+    assert_eq!(extract(&vault, &a.file_id), "alpha");
+    assert_eq!(vault.file_tags(&a.file_id).unwrap(), ["2024", "work"]);
+    let refs: i64 = vault
+        .index_conn()
+        .query_row(
+            "SELECT COUNT(*) FROM file_refs WHERE file_id = ?1 AND source_path != ''",
+            [&a.file_id],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(refs, 1);
+}
@@ -0,0 +1,68 @@
+// tests/keying_tests.rs
+//! Raw-key SQLCipher unlock + migration from passphrase-keyed databases
+
+use encrypted_file_vault::aliases::{IndexKey32, VaultKey32};
+use encrypted_file_vault::db::keying::{migrate_passphrase_db, parse_key_hex};
+use encrypted_file_vault::{index_db_conn::open_index_db_at, vault_db_conn::open_vault_db_at};
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+/// Create a DB exactly the way pre-raw-key builds did
+fn create_passphrase_db(path: &std::path::Path, passphrase: &str) {
+    let conn = Connection::open(path).unwrap();
+    conn.execute_batch(&format!(
+        "PRAGMA key = '{}';
+         PRAGMA cipher_page_size = 4096;
+         PRAGMA kdf_iter = 256000;
+         PRAGMA cipher_hmac_algorithm = HMAC_SHA512;
+         PRAGMA cipher_kdf_algorithm = PBKDF2_HMAC_SHA512;
+         PRAGMA cipher_plaintext_header_size = 0;
+         CREATE TABLE legacy_marker (v TEXT);
+         INSERT INTO legacy_marker VALUES ('still here');",
+        passphrase.replace('\'', "''")
+    ))
+    .unwrap();
+}
+
+#[test]
+fn test_raw_key_reopen_and_wrong_key_rejected() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("vault.db");
+
+    drop(open_vault_db_at(&path, &VaultKey32::new([7; 32])).unwrap());
+    assert!(open_vault_db_at(&path, &VaultKey32::new([7; 32])).is_ok());
+    assert!(open_vault_db_at(&path, &VaultKey32::new([8; 32])).is_err());
+}
+
+#[test]
+fn test_migrate_passphrase_db_with_quote_in_passphrase() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("index.db");
+    let passphrase = "it's a 'quoted' passphrase";
+    create_passphrase_db(&path, passphrase);
+
+    migrate_passphrase_db(&path, passphrase, &[0x42; 32]).unwrap();
+
+    let conn = open_index_db_at(&path, &IndexKey32::new([0x42; 32])).unwrap();
+    let marker: String = conn
+        .query_row("SELECT v FROM legacy_marker", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(marker, "still here");
+}
+
+#[test]
+fn test_migrate_with_wrong_passphrase_fails_and_keeps_db() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("vault.db");
+    create_passphrase_db(&path, "correct horse");
+
+    assert!(migrate_passphrase_db(&path, "battery staple", &[1; 32]).is_err());
+    assert!(migrate_passphrase_db(&path, "correct horse", &[1; 32]).is_ok());
+}
+
+#[test]
+fn test_parse_key_hex_requires_64_hex_chars() {
+    assert!(parse_key_hex(&"ab".repeat(32)).is_ok());
+    assert!(parse_key_hex("not-hex").is_err());
+    assert!(parse_key_hex(&"ab".repeat(31)).is_err());
+}
@@ -0,0 +1,240 @@
+// tests/legacy_import_tests.rs
+//! import_legacy: AES Crypt files upgraded, keyed and indexed in one go
+
+mod common;
+use common::open_test_vault;
+
+use encrypted_file_vault::aliases::FilePassword;
+use encrypted_file_vault::enums::OriginalsPolicy;
+use encrypted_file_vault::file_ops::encrypt_file;
+use encrypted_file_vault::import::SkipReason;
+use encrypted_file_vault::CoreError;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+/// Encrypt `contents` under a user password, as AES Crypt itself would have
+fn aes_file(root: &Path, rel: &str, contents: &str, password: &str) {
+    let path = root.join(rel);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let plain = root.join(".plain");
+    fs::write(&plain, contents).unwrap();
+    encrypt_file(&plain, &path, &FilePassword::new(password.to_string())).unwrap();
+    fs::remove_file(plain).unwrap();
+}
+
+fn passwords(candidates: &[&str]) -> Vec<FilePassword> {
+    candidates
+        .iter()
+        .map(|p| FilePassword::new(p.to_string()))
+        .collect()
+}
+
+/// old/a.txt.aes (pw "alpha"), old/sub/b.pdf.aes (pw "beta"),
+/// old/locked.aes (pw unknown), old/readme.txt (not AES Crypt)
+fn legacy_tree(root: &Path) {
+    aes_file(root, "old/a.txt.aes", "alpha contents", "alpha");
+    aes_file(root, "old/sub/b.pdf.aes", "beta contents", "beta");
+    aes_file(root, "old/locked.aes", "locked", "forgotten");
+    fs::write(root.join("old/readme.txt"), "plain").unwrap();
+}
+
+#[test]
+fn test_tree_is_upgraded_keyed_and_indexed() {
+    let dir = tempdir().unwrap();
+    legacy_tree(dir.path());
+    let mut vault = open_test_vault(dir.path());
+
+    let report = vault
+        .import_legacy(
+            dir.path().join("old"),
+            &passwords(&["nope", "alpha", "beta"]),
+            OriginalsPolicy::Keep,
+        )
+        .unwrap();
+
+    let added: Vec<&str> = report
+        .added
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .collect();
+    assert_eq!(added, ["a.txt.aes", "sub/b.pdf.aes"]);
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].relative_path, "readme.txt");
+    assert_eq!(report.skipped[0].reason, SkipReason::NotAescrypt);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].relative_path, "locked.aes");
+    assert!(matches!(report.failed[0].error, CoreError::WrongPassword));
+    assert!(report.removed_sources.is_empty());
+
+    for (imported, expected) in report.added.iter().zip(["alpha contents", "beta contents"]) {
+        let entry = vault.entry(&imported.file_id).unwrap();
+        assert!(!entry.display_name.ends_with(".aes"));
+        assert_eq!(entry.plaintext_size, expected.len() as u64);
+
+        let note: String = vault
+            .vault_conn()
+            .query_row(
+                "SELECT note FROM key_history WHERE file_id = ?1",
+                [&imported.file_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(note, "legacy-upgrade");
+
+        // Extraction checks the content hash computed during the upgrade
+        let mut out = Vec::new();
+        vault
+            .extract_to_writer(&imported.file_id, &mut out)
+            .unwrap();
+        assert_eq!(out, expected.as_bytes());
+    }
+    assert_eq!(vault.find_by_name("b.pdf").unwrap().len(), 1);
+    assert!(dir.path().join("old/a.txt.aes").exists());
+}
+
+#[test]
+fn test_remove_policy_deletes_only_verified_originals() {
+    let dir = tempdir().unwrap();
+    legacy_tree(dir.path());
+    aes_file(dir.path(), "old/sub/again.aes", "alpha contents", "beta");
+    let mut vault = open_test_vault(dir.path());
+
+    let report = vault
+        .import_legacy(
+            dir.path().join("old"),
+            &passwords(&["alpha", "beta"]),
+            OriginalsPolicy::Remove,
+        )
+        .unwrap();
+
+    assert_eq!(report.added.len(), 2);
+    assert!(matches!(
+        report
+            .skipped
+            .iter()
+            .find(|s| s.relative_path == "sub/again.aes")
+            .unwrap()
+            .reason,
+        SkipReason::Duplicate { .. }
+    ));
+    let mut removed = report.removed_sources.clone();
+    removed.sort();
+    assert_eq!(removed, ["a.txt.aes", "sub/again.aes", "sub/b.pdf.aes"]);
+
+    assert!(!dir.path().join("old/a.txt.aes").exists());
+    assert!(!dir.path().join("old/sub/again.aes").exists());
+    // Not imported, so kept
+    assert!(dir.path().join("old/locked.aes").exists());
+    assert!(dir.path().join("old/readme.txt").exists());
+}
+
+#[test]
+fn test_single_file() {
+    let dir = tempdir().unwrap();
+    aes_file(dir.path(), "report.aes", "quarterly", "pw");
+    let mut vault = open_test_vault(dir.path());
+
+    let report = vault
+        .import_legacy(
+            dir.path().join("report.aes"),
+            &passwords(&["pw"]),
+            OriginalsPolicy::Keep,
+        )
+        .unwrap();
+    assert_eq!(report.added.len(), 1);
+    assert_eq!(report.added[0].relative_path, "report.aes");
+    assert_eq!(
+        vault.entry(&report.added[0].file_id).unwrap().display_name,
+        "report"
+    );
+}
+
+#[derive(serde::Deserialize)]
+struct TestVector {
+    plaintext: String,
+    ciphertext: String,
+}
+
+/// Real v0–v2 files (needs the real AES Crypt implementation)
+#[test]
+fn test_legacy_vectors_ingest() {
+    let dir = tempdir().unwrap();
+    let old = dir.path().join("old");
+    fs::create_dir_all(&old).unwrap();
+
+    let mut expected = Vec::new();
+    for version in ["v0", "v1", "v2"] {
+        let json =
+            fs::read_to_string(format!("tests/data_input/test_vectors_{version}.json")).unwrap();
+        let vectors: Vec<TestVector> = serde_json::from_str(&json).unwrap();
+        // Skip the empty plaintext, which every version shares
+        for (i, v) in vectors
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| !v.plaintext.is_empty())
+            .take(3)
+        {
+            let name = format!("{version}-{i}.aes");
+            fs::write(old.join(&name), hex::decode(&v.ciphertext).unwrap()).unwrap();
+            expected.push((name, v.plaintext.clone()));
+        }
+    }
+
+    let mut vault = open_test_vault(dir.path());
+    let report = vault
+        .import_legacy(&old, &passwords(&["Hello"]), OriginalsPolicy::Keep)
+        .unwrap();
+    assert!(report.failed.is_empty(), "{:?}", report.failed);
+
+    for (name, plaintext) in expected {
+        let file_id = match report.added.iter().find(|f| f.relative_path == name) {
+            Some(f) => f.file_id.clone(),
+            None => continue, // same plaintext as an earlier vector
+        };
+        let mut out = Vec::new();
+        vault.extract_to_writer(&file_id, &mut out).unwrap();
+        assert_eq!(out, plaintext.as_bytes());
+    }
+}
+
+#[test]
+fn test_correct_password_second_in_list() {
+    let dir = tempdir().unwrap();
+    let old = dir.path().join("old");
+    fs::create_dir_all(&old).unwrap();
+
+    let mut expected = Vec::new();
+    for version in ["v0", "v1"] {
+        let json =
+            fs::read_to_string(format!("tests/data_input/test_vectors_{version}.json")).unwrap();
+        let vectors: Vec<TestVector> = serde_json::from_str(&json).unwrap();
+        let v = vectors.last().unwrap();
+        let name = format!("{version}.aes");
+        fs::write(old.join(&name), hex::decode(&v.ciphertext).unwrap()).unwrap();
+        expected.push((name, v.plaintext.clone()));
+    }
+
+    let mut vault = open_test_vault(dir.path());
+    let report = vault
+        .import_legacy(
+            &old,
+            &passwords(&["Goodbye", "Hello"]),
+            OriginalsPolicy::Keep,
+        )
+        .unwrap();
+    assert!(report.failed.is_empty(), "{:?}", report.failed);
+
+    for (name, plaintext) in expected {
+        let imported = report
+            .added
+            .iter()
+            .find(|f| f.relative_path == name)
+            .unwrap();
+        let mut out = Vec::new();
+        vault
+            .extract_to_writer(&imported.file_id, &mut out)
+            .unwrap();
+        assert_eq!(out, plaintext.as_bytes());
+    }
+}
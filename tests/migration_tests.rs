@@ -0,0 +1,124 @@
+// tests/migration_tests.rs
+//! Versioned schema migrations (`PRAGMA user_version`)
+
+mod common;
+
+use common::{test_index_key, test_vault_key};
+use encrypted_file_vault::db::migrations::{
+    self, schema_version, INDEX_MIGRATIONS, VAULT_MIGRATIONS,
+};
+use encrypted_file_vault::index_db_conn::open_index_db_at;
+use encrypted_file_vault::vault_db_conn::open_vault_db_at;
+use encrypted_file_vault::CoreError;
+use tempfile::tempdir;
+
+#[test]
+fn test_fresh_databases_are_at_latest_version() {
+    let dir = tempdir().unwrap();
+    let vault = open_vault_db_at(dir.path().join("vault.db"), &test_vault_key()).unwrap();
+    let index = open_index_db_at(dir.path().join("index.db"), &test_index_key()).unwrap();
+
+    assert_eq!(
+        schema_version(&vault).unwrap(),
+        VAULT_MIGRATIONS.latest_version()
+    );
+    assert_eq!(
+        schema_version(&index).unwrap(),
+        INDEX_MIGRATIONS.latest_version()
+    );
+}
+
+#[test]
+fn test_unversioned_index_is_adopted_and_upgraded() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("index.db");
+
+    // Roll an index back to what a pre-versioning build left behind
+    {
+        let conn = open_index_db_at(&path, &test_index_key()).unwrap();
+        conn.execute_batch(
+            "ALTER TABLE files DROP COLUMN known_password_hex;
+             PRAGMA user_version = 0;",
+        )
+        .unwrap();
+    }
+
+    let conn = open_index_db_at(&path, &test_index_key()).unwrap();
+    assert_eq!(
+        schema_version(&conn).unwrap(),
+        INDEX_MIGRATIONS.latest_version()
+    );
+    conn.execute("UPDATE files SET known_password_hex = 'ab' WHERE 0", [])
+        .unwrap();
+}
+
+#[test]
+fn test_runtime_added_column_does_not_break_migration() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("index.db");
+
+    // decrypt_batch used to ALTER in known_password_hex on unversioned DBs
+    {
+        let conn = open_index_db_at(&path, &test_index_key()).unwrap();
+        conn.execute_batch("PRAGMA user_version = 1;").unwrap();
+    }
+
+    let conn = open_index_db_at(&path, &test_index_key()).unwrap();
+    assert_eq!(
+        schema_version(&conn).unwrap(),
+        INDEX_MIGRATIONS.latest_version()
+    );
+}
+
+#[test]
+fn test_newer_schema_is_refused() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("vault.db");
+    {
+        let conn = open_vault_db_at(&path, &test_vault_key()).unwrap();
+        conn.execute_batch("PRAGMA user_version = 99;").unwrap();
+    }
+
+    match open_vault_db_at(&path, &test_vault_key()) {
+        Err(CoreError::SchemaTooNew {
+            database,
+            found,
+            supported,
+        }) => {
+            assert_eq!(database, "vault.db");
+            assert_eq!(found, 99);
+            assert_eq!(supported, VAULT_MIGRATIONS.latest_version());
+        }
+        other => panic!("expected SchemaTooNew, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_dry_run_reports_without_applying() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("vault.db");
+
+    let missing =
+        migrations::dry_run(&path, test_vault_key().expose_secret(), &VAULT_MIGRATIONS).unwrap();
+    assert_eq!(missing.current_version, 0);
+    assert_eq!(missing.pending.len(), VAULT_MIGRATIONS.migrations.len());
+    assert!(!path.exists());
+
+    {
+        let conn = open_vault_db_at(&path, &test_vault_key()).unwrap();
+        conn.execute_batch("PRAGMA user_version = 1;").unwrap();
+    }
+
+    let plan =
+        migrations::dry_run(&path, test_vault_key().expose_secret(), &VAULT_MIGRATIONS).unwrap();
+    assert_eq!(plan.current_version, 1);
+    assert_eq!(
+        plan.pending.iter().map(|m| m.version).collect::<Vec<_>>(),
+        (2..=VAULT_MIGRATIONS.latest_version()).collect::<Vec<_>>()
+    );
+
+    // Still at 1 afterwards
+    let plan =
+        migrations::dry_run(&path, test_vault_key().expose_secret(), &VAULT_MIGRATIONS).unwrap();
+    assert_eq!(plan.current_version, 1);
+}
@@ -0,0 +1,261 @@
+// tests/naming_tests.rs
+//! Policy-generated ciphertext names, short-id collisions, relayout
+
+mod common;
+use common::open_test_vault;
+
+use encrypted_file_vault::enums::FilenameStyle;
+use encrypted_file_vault::naming::{allocate_name, NamingPolicy};
+use encrypted_file_vault::{CoreError, FileEntry, Vault};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn store(vault: &mut Vault, dir: &Path, name: &str, contents: &[u8]) -> FileEntry {
+    let plain = dir.join("plain").join(name);
+    fs::create_dir_all(plain.parent().unwrap()).unwrap();
+    fs::write(&plain, contents).unwrap();
+    vault.store_file(&plain).unwrap()
+}
+
+fn file_name(entry: &FileEntry) -> String {
+    entry
+        .current_path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn test_human_name_is_stem_and_short_id() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let entry = store(&mut vault, dir.path(), "Tax Return.pdf", b"2024");
+
+    assert_eq!(
+        file_name(&entry),
+        format!("Tax Return.{}.aes", &entry.file_id[..20])
+    );
+    assert_eq!(
+        entry.current_path.parent().unwrap(),
+        vault.storage_dir().unwrap()
+    );
+    assert_eq!(entry.filename_style, "human");
+    assert_eq!(entry.id_length_hex, 20);
+
+    let stored = vault.entry(&entry.file_id).unwrap();
+    assert_eq!(stored.current_path, entry.current_path);
+
+    let out = dir.path().join("out.pdf");
+    vault.extract_file(&entry.file_id, &out).unwrap();
+    assert_eq!(fs::read(out).unwrap(), b"2024");
+}
+
+#[test]
+fn test_hash_and_opaque_names() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+
+    vault
+        .set_naming_policy(NamingPolicy::new(FilenameStyle::Hash, 64).unwrap())
+        .unwrap();
+    let hashed = store(&mut vault, dir.path(), "secret-plans.txt", b"hash me");
+    assert_eq!(file_name(&hashed), format!("{}.aes", hashed.file_id));
+
+    vault
+        .set_naming_policy(NamingPolicy::new(FilenameStyle::Opaque, 20).unwrap())
+        .unwrap();
+    let opaque = store(&mut vault, dir.path(), "secret-plans.txt", b"hide me");
+    let name = file_name(&opaque);
+    assert!(!name.contains("secret"));
+    assert!(!name.contains(&opaque.file_id[..8]));
+    assert_eq!(opaque.filename_style, "opaque");
+}
+
+#[test]
+fn test_short_id_collision_extends_the_id() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    vault
+        .set_naming_policy(NamingPolicy::new(FilenameStyle::Human, 8).unwrap())
+        .unwrap();
+    let entry = store(&mut vault, dir.path(), "notes.txt", b"first");
+
+    // Another file that happens to share the 8-hex prefix
+    let storage = vault.storage_dir().unwrap();
+    let imposter_id = format!("{}{}", &entry.file_id[..8], "0".repeat(56));
+    let name = allocate_name(
+        vault.index_conn(),
+        &storage,
+        "notes.txt",
+        &imposter_id,
+        vault.naming_policy(),
+    )
+    .unwrap();
+    assert_eq!(name.id_length_hex, 12);
+    assert_eq!(
+        name.path.file_name().unwrap().to_string_lossy(),
+        format!("notes.{}.aes", &imposter_id[..12])
+    );
+
+    // An unindexed file in the way counts too
+    fs::write(&name.path, b"stray").unwrap();
+    let next = allocate_name(
+        vault.index_conn(),
+        &storage,
+        "notes.txt",
+        &imposter_id,
+        vault.naming_policy(),
+    )
+    .unwrap();
+    assert_eq!(next.id_length_hex, 16);
+
+    assert!(matches!(
+        NamingPolicy::new(FilenameStyle::Human, 4),
+        Err(CoreError::Config(_))
+    ));
+}
+
+#[test]
+fn test_relayout_renames_and_updates_index() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let a = store(&mut vault, dir.path(), "a.txt", b"alpha");
+    let b = store(&mut vault, dir.path(), "b.txt", b"bravo");
+
+    let report = vault
+        .relayout(NamingPolicy::new(FilenameStyle::Hash, 64).unwrap())
+        .unwrap();
+    assert_eq!(report.renamed, 2);
+    assert!(report.missing.is_empty());
+
+    for old in [&a, &b] {
+        let moved = vault.entry(&old.file_id).unwrap();
+        assert!(!old.current_path.exists());
+        assert!(moved.current_path.exists());
+        assert_eq!(file_name(&moved), format!("{}.aes", old.file_id));
+        assert_eq!(moved.filename_style, "hash");
+    }
+
+    // Idempotent
+    let again = vault
+        .relayout(NamingPolicy::new(FilenameStyle::Hash, 64).unwrap())
+        .unwrap();
+    assert_eq!((again.renamed, again.unchanged), (0, 2));
+
+    let out = dir.path().join("a.out");
+    vault.extract_file(&a.file_id, &out).unwrap();
+    assert_eq!(fs::read(out).unwrap(), b"alpha");
+}
+
+#[test]
+fn test_relayout_policy_survives_reopen() {
+    let dir = tempdir().unwrap();
+    let policy = NamingPolicy::new(FilenameStyle::Hash, 64).unwrap();
+    {
+        let mut vault = open_test_vault(dir.path());
+        store(&mut vault, dir.path(), "a.txt", b"alpha");
+        vault.relayout(policy).unwrap();
+    }
+
+    let mut vault = open_test_vault(dir.path());
+    assert_eq!(*vault.naming_policy(), policy);
+    let b = store(&mut vault, dir.path(), "b.txt", b"bravo");
+    assert_eq!(file_name(&b), format!("{}.aes", b.file_id));
+}
+
+/// Journal a move the way `relayout` does just before renaming
+fn journal_move(vault: &Vault, entry: &FileEntry, target: &Path, style: &str, id_length: i64) {
+    vault
+        .index_conn()
+        .execute(
+            "INSERT INTO relayout_journal \
+             (file_id, source_table, from_path, target_path, filename_style, id_length) \
+             VALUES (?1, 'files', ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                entry.file_id,
+                entry.current_path.to_str().unwrap(),
+                target.to_str().unwrap(),
+                style,
+                id_length
+            ],
+        )
+        .unwrap();
+}
+
+fn journal_len(vault: &Vault) -> i64 {
+    vault
+        .index_conn()
+        .query_row("SELECT COUNT(*) FROM relayout_journal", [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_relayout_adopts_a_rename_interrupted_before_commit() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let a = store(&mut vault, dir.path(), "a.txt", b"alpha");
+
+    // Simulate a crash after the rename but before the row was updated
+    let target = a.current_path.with_file_name(format!("{}.aes", a.file_id));
+    journal_move(&vault, &a, &target, "hash", 64);
+    fs::rename(&a.current_path, &target).unwrap();
+
+    let report = vault
+        .relayout(NamingPolicy::new(FilenameStyle::Hash, 64).unwrap())
+        .unwrap();
+    assert_eq!((report.renamed, report.unchanged), (1, 0));
+    assert_eq!(vault.entry(&a.file_id).unwrap().current_path, target);
+    assert_eq!(journal_len(&vault), 0);
+}
+
+#[test]
+fn test_relayout_finishes_an_interrupted_opaque_rename() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let a = store(&mut vault, dir.path(), "a.txt", b"alpha");
+
+    // A random name can't be guessed back; only the journal knows it
+    let target = a
+        .current_path
+        .with_file_name("0123456789abcdef0123456789abcdef.aes");
+    journal_move(&vault, &a, &target, "opaque", 0);
+    fs::rename(&a.current_path, &target).unwrap();
+
+    let report = vault
+        .relayout(NamingPolicy::new(FilenameStyle::Opaque, 32).unwrap())
+        .unwrap();
+    assert_eq!((report.renamed, report.unchanged), (1, 0));
+    assert!(report.missing.is_empty());
+
+    let moved = vault.entry(&a.file_id).unwrap();
+    assert_eq!(moved.current_path, target);
+    assert_eq!(moved.filename_style, "opaque");
+    assert_eq!(journal_len(&vault), 0);
+
+    let out = dir.path().join("a.out");
+    vault.extract_file(&a.file_id, &out).unwrap();
+    assert_eq!(fs::read(out).unwrap(), b"alpha");
+}
+
+#[test]
+fn test_relayout_performs_a_journaled_rename_not_yet_done() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let a = store(&mut vault, dir.path(), "a.txt", b"alpha");
+
+    // Crash after the journal write, before the rename
+    let target = a.current_path.with_file_name(format!("{}.aes", a.file_id));
+    journal_move(&vault, &a, &target, "hash", 64);
+
+    let report = vault
+        .relayout(NamingPolicy::new(FilenameStyle::Hash, 64).unwrap())
+        .unwrap();
+    assert_eq!(report.renamed, 1);
+    assert!(!a.current_path.exists());
+    assert_eq!(vault.entry(&a.file_id).unwrap().current_path, target);
+    assert_eq!(journal_len(&vault), 0);
+}
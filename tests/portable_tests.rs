@@ -0,0 +1,159 @@
+// tests/portable_tests.rs
+//! Portable vault archives: export, unpack elsewhere, checksums, passphrase vaults
+
+mod common;
+use common::{open_test_vault, test_index_key, test_vault_key};
+
+use encrypted_file_vault::aliases::{IndexKey32, UserPassphrase, VaultKey32};
+use encrypted_file_vault::consts::VAULT_DB_FILE_NAME;
+use encrypted_file_vault::enums::RemoveMode;
+use encrypted_file_vault::unlock::KdfParams;
+use encrypted_file_vault::{CoreError, Vault};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+/// Cheap parameters — the defaults are deliberately slow
+const FAST: KdfParams = KdfParams {
+    m_cost_kib: 1024,
+    t_cost: 1,
+    p_cost: 1,
+};
+
+fn keys() -> (VaultKey32, IndexKey32) {
+    (test_vault_key(), test_index_key())
+}
+
+fn contents(vault: &Vault, file_id: &str) -> String {
+    let mut out = Vec::new();
+    vault.extract_to_writer(file_id, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Archive of a vault with a stored file, one outside the vault dir and a
+/// trashed one; the source vault is deleted afterwards
+fn archived_vault(dir: &Path) -> (Vec<(String, String)>, std::path::PathBuf) {
+    let source = dir.join("source");
+    let mut vault = open_test_vault(&source.join("vault"));
+    let mut ids = Vec::new();
+    for (name, text) in [("a.txt", "alpha"), ("b.txt", "bravo"), ("c.txt", "charlie")] {
+        let plain = dir.join(name);
+        fs::write(&plain, text).unwrap();
+        let entry = match name {
+            "b.txt" => vault
+                .add_file(&plain, source.join("elsewhere").join("b.aes"))
+                .unwrap(),
+            _ => vault.store_file(&plain).unwrap(),
+        };
+        ids.push((entry.file_id, text.to_string()));
+    }
+    vault.add_tags(&ids[0].0, &["moved"]).unwrap();
+    vault
+        .remove_file(&ids[2].0, RemoveMode::SoftDelete)
+        .unwrap();
+
+    let archive = dir.join("vault.tar");
+    assert_eq!(vault.export_portable(&archive).unwrap(), 3);
+    drop(vault);
+    fs::remove_dir_all(&source).unwrap();
+    (ids, archive)
+}
+
+#[test]
+fn test_portable_round_trip_rewrites_paths() {
+    let dir = tempdir().unwrap();
+    let (ids, archive) = archived_vault(dir.path());
+    let (vault_key, index_key) = keys();
+
+    let dest = dir.path().join("restored");
+    let mut vault = Vault::import_portable(&archive, &dest, &vault_key, &index_key).unwrap();
+    let storage = dest.canonicalize().unwrap().join("files");
+
+    let listed = vault.list().unwrap();
+    assert_eq!(listed.len(), 2);
+    for entry in &listed {
+        assert_eq!(entry.current_path.parent(), Some(storage.as_path()));
+    }
+    for (file_id, text) in &ids[..2] {
+        assert_eq!(&contents(&vault, file_id), text);
+    }
+    assert_eq!(vault.file_tags(&ids[0].0).unwrap(), ["moved"]);
+    // The original human names are kept
+    assert!(storage.join("b.aes").is_file());
+
+    vault.restore_file(&ids[2].0).unwrap();
+    assert_eq!(contents(&vault, &ids[2].0), "charlie");
+    assert!(fs::read_dir(&dest).unwrap().all(|e| !e
+        .unwrap()
+        .file_name()
+        .to_string_lossy()
+        .starts_with('.')));
+}
+
+#[test]
+fn test_portable_rejects_tampering_and_existing_vaults() {
+    let dir = tempdir().unwrap();
+    let (_, archive) = archived_vault(dir.path());
+    let (vault_key, index_key) = keys();
+
+    // Flip one byte of the first archived ciphertext
+    let mut raw = fs::read(&archive).unwrap();
+    let at = raw.windows(5).position(|w| w == b"AES\x03\x00").unwrap() + 4;
+    raw[at] ^= 0x01;
+    let tampered = dir.path().join("tampered.tar");
+    fs::write(&tampered, &raw).unwrap();
+
+    let dest = dir.path().join("restored");
+    assert!(matches!(
+        Vault::import_portable(&tampered, &dest, &vault_key, &index_key),
+        Err(CoreError::InvalidBackup(_))
+    ));
+    assert_eq!(fs::read_dir(&dest).unwrap().count(), 0);
+
+    // A wrong key for either database leaves the destination untouched as well
+    let wrong = IndexKey32::new([0x99; 32]);
+    assert!(Vault::import_portable(&archive, &dest, &vault_key, &wrong).is_err());
+    assert_eq!(fs::read_dir(&dest).unwrap().count(), 0);
+    let wrong = VaultKey32::new([0x99; 32]);
+    assert!(matches!(
+        Vault::import_portable(&archive, &dest, &wrong, &index_key),
+        Err(CoreError::WrongPassword)
+    ));
+    assert_eq!(fs::read_dir(&dest).unwrap().count(), 0);
+
+    Vault::import_portable(&archive, &dest, &vault_key, &index_key).unwrap();
+    assert!(matches!(
+        Vault::import_portable(&archive, &dest, &vault_key, &index_key),
+        Err(CoreError::Config(_))
+    ));
+}
+
+#[test]
+fn test_portable_passphrase_vault() {
+    let dir = tempdir().unwrap();
+    let pass = UserPassphrase::new("moving day".to_string());
+
+    let source = dir.path().join("source");
+    let plain = dir.path().join("notes.txt");
+    fs::write(&plain, "packed up").unwrap();
+    let file_id = {
+        let mut vault = Vault::create_with_passphrase(&source, &pass, FAST).unwrap();
+        vault.store_file(&plain).unwrap().file_id
+    };
+    let archive = dir.path().join("vault.tar");
+    Vault::unlock(&source, &pass)
+        .unwrap()
+        .export_portable(&archive)
+        .unwrap();
+    fs::remove_dir_all(&source).unwrap();
+
+    let dest = dir.path().join("restored");
+    let wrong = UserPassphrase::new("moving night".to_string());
+    assert!(Vault::import_portable_with_passphrase(&archive, &dest, &wrong).is_err());
+    assert!(!dest.join(VAULT_DB_FILE_NAME).exists());
+
+    let vault = Vault::import_portable_with_passphrase(&archive, &dest, &pass).unwrap();
+    assert_eq!(contents(&vault, &file_id), "packed up");
+    drop(vault);
+    assert!(Vault::unlock(&dest, &pass).is_ok());
+}
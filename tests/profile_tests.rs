@@ -0,0 +1,134 @@
+// tests/profile_tests.rs
+//! Named profiles, validation and `Vault::from_config`
+
+use encrypted_file_vault::config::{Config, ConfigField, ConfigLoader, ConfigSource};
+use encrypted_file_vault::{CoreError, Vault};
+use serial_test::serial;
+use std::fs;
+use tempfile::tempdir;
+
+const CONFIG: &str = r#"
+[paths]
+vault_db = "/srv/personal/vault.db"
+index_db = "/srv/personal/index.db"
+
+[profiles.team.paths]
+vault_db = "/srv/team/vault.db"
+index_db = "/srv/team/index.db"
+
+[profiles.archive.paths]
+vault_db = "/mnt/archive/vault.db"
+
+[profiles.archive.features]
+allow_insecure_export = false
+"#;
+
+fn loader(dir: &std::path::Path) -> ConfigLoader {
+    let path = dir.join("efv.toml");
+    fs::write(&path, CONFIG).unwrap();
+    ConfigLoader::new().without_env().file(path)
+}
+
+fn production_config() -> Config {
+    let mut config = Config::default();
+    config.features.use_dev_keys = false;
+    config.keys.vault_key = "7a".repeat(32);
+    config.keys.index_key = "7b".repeat(32);
+    config.paths.vault_db = "/srv/vault/vault.db".into();
+    config.paths.index_db = "/srv/vault/index.db".into();
+    config
+}
+
+#[test]
+fn test_profile_overlays_top_level_values() {
+    let dir = tempdir().unwrap();
+
+    let base = loader(dir.path()).load().unwrap();
+    assert_eq!(base.profile, None);
+    assert_eq!(base.config.paths.vault_db, "/srv/personal/vault.db");
+    assert_eq!(base.available_profiles, vec!["archive", "team"]);
+
+    let archive = loader(dir.path()).profile("archive").load().unwrap();
+    assert_eq!(archive.config.paths.vault_db, "/mnt/archive/vault.db");
+    assert_eq!(
+        *archive.source(ConfigField::VaultDb),
+        ConfigSource::Profile("archive".into())
+    );
+    // Not in the profile → inherited from the top level
+    assert_eq!(archive.config.paths.index_db, "/srv/personal/index.db");
+    assert!(!archive.config.features.allow_insecure_export);
+}
+
+#[test]
+fn test_unknown_profile_is_an_error() {
+    let dir = tempdir().unwrap();
+    let err = loader(dir.path())
+        .profile("nope")
+        .load()
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("nope"));
+    assert!(err.contains("archive, team"));
+}
+
+#[test]
+#[serial]
+fn test_efv_profile_selects_the_profile() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("efv.toml");
+    fs::write(&path, CONFIG).unwrap();
+
+    std::env::set_var("EFV_PROFILE", "team");
+    let loaded = ConfigLoader::new().file(&path).load();
+    std::env::remove_var("EFV_PROFILE");
+
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.profile.as_deref(), Some("team"));
+    assert_eq!(loaded.config.paths.vault_db, "/srv/team/vault.db");
+}
+
+#[test]
+fn test_validate_rejects_dev_settings() {
+    // Dev keys are opt-in, and opting in still fails validation
+    assert!(!Config::default().features.use_dev_keys);
+    let mut config = Config::default();
+    config.features.use_dev_keys = true;
+    let err = config.validate().unwrap_err().to_string();
+    assert!(err.contains("use_dev_keys"));
+    assert!(err.contains("keys.vault_key is the built-in development key"));
+    assert!(err.contains("test data directory"));
+
+    let mut config = production_config();
+    config.keys.index_key = "short".into();
+    config.paths.index_db = config.paths.vault_db.clone();
+    let err = config.validate().unwrap_err().to_string();
+    assert!(err.contains("keys.index_key is not 64 hex characters"));
+    assert!(err.contains("same file"));
+}
+
+#[test]
+fn test_validate_accepts_production_config() {
+    production_config().validate().unwrap();
+}
+
+#[test]
+#[cfg(not(feature = "dev-config"))]
+fn test_vault_from_config_refuses_dev_settings() {
+    assert!(matches!(
+        Vault::from_config(&Config::default()),
+        Err(CoreError::Config(_))
+    ));
+}
+
+#[test]
+fn test_vault_from_config_opens_configured_paths() {
+    let dir = tempdir().unwrap();
+    let mut config = production_config();
+    config.paths.vault_db = dir.path().join("v.db").to_string_lossy().into();
+    config.paths.index_db = dir.path().join("i.db").to_string_lossy().into();
+
+    let vault = Vault::from_config(&config).unwrap();
+    assert!(vault.list().unwrap().is_empty());
+    assert!(dir.path().join("v.db").exists());
+    assert!(dir.path().join("i.db").exists());
+}
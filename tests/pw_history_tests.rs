@@ -13,7 +13,7 @@ use tempfile::tempdir;
 
 // Shared test helper — fresh DBs every test
 mod common;
-use common::{DbMode, TestDbPair};
+use common::{test_vault_key, DbMode, TestDbPair};
 
 // Use the REAL std::result::Result (two generics) for test functions
 type TestResult<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
@@ -153,8 +153,9 @@ fn test_history_backfill_on_existing_keys() -> TestResult {
     }
 
     // Re-open → backfill runs
+    let vault_path = db.vault_db_path();
     drop(db.vault);
-    let vault_conn = vault_db_conn::open_vault_db()?;
+    let vault_conn = vault_db_conn::open_vault_db_at(&vault_path, &test_vault_key())?;
     let file_id = "backfill_test";
 
     let (version, superseded_at): (i64, Option<String>) = vault_conn.query_row(
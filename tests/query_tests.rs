@@ -0,0 +1,157 @@
+// tests/query_tests.rs
+//! Typed index queries: filters, ordering, paging
+
+mod common;
+use common::open_test_vault;
+
+use chrono::{TimeZone, Utc};
+use encrypted_file_vault::db::query::{FileQuery, SortKey};
+use encrypted_file_vault::enums::EncryptionAlgorithm;
+use encrypted_file_vault::Vault;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn add(vault: &mut Vault, dir: &Path, name: &str, contents: &[u8]) -> String {
+    let plain = dir.join(name);
+    fs::write(&plain, contents).unwrap();
+    vault
+        .add_file(&plain, dir.join(format!("{name}.aes")))
+        .unwrap()
+        .file_id
+}
+
+fn set(vault: &Vault, id: &str, column: &str, value: &str) {
+    vault
+        .index_conn()
+        .execute(
+            &format!("UPDATE files SET {column} = ?1 WHERE file_id = ?2"),
+            [value, id],
+        )
+        .unwrap();
+}
+
+fn names(vault: &Vault, query: &FileQuery) -> Vec<String> {
+    vault
+        .query(query)
+        .map(|r| r.unwrap().display_name)
+        .collect()
+}
+
+/// a.pdf (10 B), b.txt (20 B), c.pdf (30 B)
+fn sample(dir: &Path) -> (Vault, [String; 3]) {
+    let mut vault = open_test_vault(dir);
+    let a = add(&mut vault, dir, "a.pdf", &[1; 10]);
+    let b = add(&mut vault, dir, "b.txt", &[2; 20]);
+    let c = add(&mut vault, dir, "c.pdf", &[3; 30]);
+    (vault, [a, b, c])
+}
+
+#[test]
+fn test_records_decode_every_column() {
+    let dir = tempdir().unwrap();
+    let (mut vault, [a, ..]) = sample(dir.path());
+    vault.add_tags(&a, &["work", "tax"]).unwrap();
+    set(&vault, &a, "note", "2024 return");
+    set(&vault, &a, "rotated_at", "2024-03-01 12:00:00");
+
+    let record = vault
+        .query(&FileQuery::new().name_glob("a.pdf"))
+        .next()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(record.file_id, a);
+    assert_eq!(record.plaintext_size, 10);
+    assert_eq!(record.encryption_algo, EncryptionAlgorithm::AESCryptV3);
+    assert_eq!(record.tags, vec!["tax", "work"]);
+    assert_eq!(record.note.as_deref(), Some("2024 return"));
+    assert_eq!(
+        record.rotated_at,
+        Some(Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap())
+    );
+    assert!(record.created_at <= Utc::now());
+}
+
+#[test]
+fn test_filters_combine() {
+    let dir = tempdir().unwrap();
+    let (mut vault, [a, b, c]) = sample(dir.path());
+    vault.add_tags(&a, &["work", "tax"]).unwrap();
+    vault.add_tags(&b, &["work"]).unwrap();
+    vault.add_tags(&c, &["taxonomy"]).unwrap();
+
+    assert_eq!(
+        names(&vault, &FileQuery::new().name_glob("*.pdf")),
+        ["a.pdf", "c.pdf"]
+    );
+    assert_eq!(
+        names(&vault, &FileQuery::new().min_size(15).max_size(30)),
+        ["b.txt", "c.pdf"]
+    );
+    // Whole tags only: "tax" does not match "taxonomy"
+    assert_eq!(names(&vault, &FileQuery::new().tag("tax")), ["a.pdf"]);
+    assert_eq!(
+        names(&vault, &FileQuery::new().tag("work").tag("tax")),
+        ["a.pdf"]
+    );
+    assert_eq!(
+        names(&vault, &FileQuery::new().tag("work").name_glob("*.txt")),
+        ["b.txt"]
+    );
+    assert_eq!(
+        FileQuery::new()
+            .encryption_algo(EncryptionAlgorithm::AESCryptV3)
+            .count(vault.index_conn())
+            .unwrap(),
+        3
+    );
+}
+
+#[test]
+fn test_date_ranges() {
+    let dir = tempdir().unwrap();
+    let (vault, [a, b, c]) = sample(dir.path());
+    set(&vault, &a, "created_at", "2023-01-15 08:00:00");
+    set(&vault, &b, "created_at", "2024-06-01 08:00:00");
+    set(&vault, &c, "created_at", "2025-01-01 00:00:00");
+    set(&vault, &b, "rotated_at", "2025-02-01 00:00:00");
+
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+
+    assert_eq!(
+        names(
+            &vault,
+            &FileQuery::new().created_after(start).created_before(end)
+        ),
+        ["b.txt"]
+    );
+    assert_eq!(
+        names(&vault, &FileQuery::new().rotated_after(end)),
+        ["b.txt"]
+    );
+}
+
+#[test]
+fn test_sorting_and_pagination() {
+    let dir = tempdir().unwrap();
+    let (vault, _) = sample(dir.path());
+
+    let by_size = FileQuery::new()
+        .order_by(SortKey::PlaintextSize)
+        .descending();
+    assert_eq!(names(&vault, &by_size), ["c.pdf", "b.txt", "a.pdf"]);
+
+    assert_eq!(names(&vault, &FileQuery::new().page(1, 2)), ["c.pdf"]);
+    assert_eq!(
+        names(&vault, &FileQuery::new().offset(1).limit(1)),
+        ["b.txt"]
+    );
+
+    // Tiny fetch pages still yield everything, in order
+    assert_eq!(
+        names(&vault, &FileQuery::new().page_size(1)),
+        ["a.pdf", "b.txt", "c.pdf"]
+    );
+}
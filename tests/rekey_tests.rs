@@ -0,0 +1,205 @@
+// tests/rekey_tests.rs
+//! Rekeying vault.db / index.db: raw keys, passphrase change, crash recovery
+
+use encrypted_file_vault::aliases::{IndexKey32, UserPassphrase, VaultKey32};
+use encrypted_file_vault::db::keying::rekey_backup_path;
+use encrypted_file_vault::unlock::{KdfParams, VaultHeader};
+use encrypted_file_vault::Vault;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+const OLD_VAULT_KEY: [u8; 32] = [0x31; 32];
+const OLD_INDEX_KEY: [u8; 32] = [0x32; 32];
+const NEW_VAULT_KEY: [u8; 32] = [0x41; 32];
+const NEW_INDEX_KEY: [u8; 32] = [0x42; 32];
+
+const FAST: KdfParams = KdfParams {
+    m_cost_kib: 1024,
+    t_cost: 1,
+    p_cost: 1,
+};
+
+fn paths(dir: &Path) -> (PathBuf, PathBuf) {
+    (dir.join("vault.db"), dir.join("index.db"))
+}
+
+fn open(
+    dir: &Path,
+    vault_key: [u8; 32],
+    index_key: [u8; 32],
+) -> encrypted_file_vault::Result<Vault> {
+    let (vault_db, index_db) = paths(dir);
+    Vault::open(
+        vault_db,
+        &VaultKey32::new(vault_key),
+        index_db,
+        &IndexKey32::new(index_key),
+    )
+}
+
+/// Vault under the old keys holding one file; returns its id
+fn populated_vault(dir: &Path) -> String {
+    let mut vault = open(dir, OLD_VAULT_KEY, OLD_INDEX_KEY).unwrap();
+    let plain = dir.join("ledger.txt");
+    fs::write(&plain, b"balances survive a rekey").unwrap();
+    vault
+        .add_file(&plain, dir.join("ledger.txt.aes"))
+        .unwrap()
+        .file_id
+}
+
+fn audit_events(vault: &Vault) -> Vec<(String, Option<String>)> {
+    let mut stmt = vault
+        .vault_conn()
+        .prepare("SELECT event, detail FROM audit_log ORDER BY id")
+        .unwrap();
+    stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap()
+}
+
+#[test]
+fn test_rekey_switches_both_databases_and_keeps_files_readable() {
+    let dir = tempdir().unwrap();
+    let file_id = populated_vault(dir.path());
+    let ciphertext_before = fs::read(dir.path().join("ledger.txt.aes")).unwrap();
+
+    {
+        let mut vault = open(dir.path(), OLD_VAULT_KEY, OLD_INDEX_KEY).unwrap();
+        vault
+            .rekey(
+                &VaultKey32::new(NEW_VAULT_KEY),
+                &IndexKey32::new(NEW_INDEX_KEY),
+            )
+            .unwrap();
+
+        // The live handle keeps working after the rekey
+        assert_eq!(vault.list().unwrap().len(), 1);
+    }
+
+    let (vault_db, index_db) = paths(dir.path());
+    assert!(!rekey_backup_path(&vault_db).exists());
+    assert!(!rekey_backup_path(&index_db).exists());
+    assert!(open(dir.path(), OLD_VAULT_KEY, OLD_INDEX_KEY).is_err());
+
+    let vault = open(dir.path(), NEW_VAULT_KEY, NEW_INDEX_KEY).unwrap();
+    let out = dir.path().join("ledger.out");
+    vault.extract_file(&file_id, &out).unwrap();
+    assert_eq!(fs::read(&out).unwrap(), b"balances survive a rekey");
+
+    // Per-file keys are untouched, so the ciphertext is byte-identical
+    assert_eq!(
+        fs::read(dir.path().join("ledger.txt.aes")).unwrap(),
+        ciphertext_before
+    );
+    assert_eq!(
+        audit_events(&vault),
+        vec![("rekey".to_string(), Some("raw keys replaced".to_string()))]
+    );
+}
+
+#[test]
+fn test_change_passphrase_replaces_header_and_keys() {
+    let dir = tempdir().unwrap();
+    let old = UserPassphrase::new("old passphrase".to_string());
+    let new = UserPassphrase::new("new passphrase".to_string());
+
+    drop(Vault::create_with_passphrase(dir.path(), &old, FAST).unwrap());
+    let old_salt = VaultHeader::load(dir.path()).unwrap().salt;
+
+    let mut vault = Vault::unlock(dir.path(), &old).unwrap();
+    vault.change_passphrase(&new, FAST).unwrap();
+    drop(vault);
+
+    assert_ne!(VaultHeader::load(dir.path()).unwrap().salt, old_salt);
+    assert!(Vault::unlock(dir.path(), &old).is_err());
+
+    let vault = Vault::unlock(dir.path(), &new).unwrap();
+    assert_eq!(audit_events(&vault)[0].0, "passphrase-change");
+}
+
+#[test]
+fn test_change_passphrase_requires_a_header() {
+    let dir = tempdir().unwrap();
+    populated_vault(dir.path());
+
+    let mut vault = open(dir.path(), OLD_VAULT_KEY, OLD_INDEX_KEY).unwrap();
+    let new = UserPassphrase::new("anything".to_string());
+    assert!(vault.change_passphrase(&new, FAST).is_err());
+    drop(vault);
+
+    // Nothing changed on disk
+    assert!(open(dir.path(), OLD_VAULT_KEY, OLD_INDEX_KEY).is_ok());
+}
+
+/// Snapshot both databases as the rekey would before touching them
+fn snapshot(dir: &Path) -> (Vec<u8>, Vec<u8>) {
+    let (vault_db, index_db) = paths(dir);
+    (fs::read(vault_db).unwrap(), fs::read(index_db).unwrap())
+}
+
+fn plant_backups(dir: &Path, (vault_bytes, index_bytes): &(Vec<u8>, Vec<u8>)) {
+    let (vault_db, index_db) = paths(dir);
+    fs::write(rekey_backup_path(&vault_db), vault_bytes).unwrap();
+    fs::write(rekey_backup_path(&index_db), index_bytes).unwrap();
+}
+
+fn rekey_to_new(dir: &Path) {
+    let mut vault = open(dir, OLD_VAULT_KEY, OLD_INDEX_KEY).unwrap();
+    vault
+        .rekey(
+            &VaultKey32::new(NEW_VAULT_KEY),
+            &IndexKey32::new(NEW_INDEX_KEY),
+        )
+        .unwrap();
+}
+
+#[test]
+fn test_crash_between_the_two_rekeys_rolls_back() {
+    let dir = tempdir().unwrap();
+    let file_id = populated_vault(dir.path());
+    let before = snapshot(dir.path());
+
+    // vault.db under the new key, index.db still under the old one
+    rekey_to_new(dir.path());
+    let (_, index_db) = paths(dir.path());
+    fs::write(&index_db, &before.1).unwrap();
+    plant_backups(dir.path(), &before);
+
+    // The new keys cannot open both databases, so nothing is touched
+    assert!(open(dir.path(), NEW_VAULT_KEY, NEW_INDEX_KEY).is_err());
+
+    let vault = open(dir.path(), OLD_VAULT_KEY, OLD_INDEX_KEY).unwrap();
+    assert_eq!(vault.entry(&file_id).unwrap().file_id, file_id);
+    assert_eq!(
+        audit_events(&vault),
+        vec![(
+            "rekey-recovered".to_string(),
+            Some("interrupted rekey rolled back".to_string())
+        )]
+    );
+    assert!(!rekey_backup_path(&index_db).exists());
+}
+
+#[test]
+fn test_crash_after_both_rekeys_completes_with_new_keys() {
+    let dir = tempdir().unwrap();
+    let file_id = populated_vault(dir.path());
+    let before = snapshot(dir.path());
+
+    rekey_to_new(dir.path());
+    plant_backups(dir.path(), &before);
+
+    let vault = open(dir.path(), NEW_VAULT_KEY, NEW_INDEX_KEY).unwrap();
+    assert_eq!(vault.entry(&file_id).unwrap().file_id, file_id);
+
+    let (vault_db, index_db) = paths(dir.path());
+    assert!(!rekey_backup_path(&vault_db).exists());
+    assert!(!rekey_backup_path(&index_db).exists());
+    assert_eq!(
+        audit_events(&vault).last().unwrap().1.as_deref(),
+        Some("interrupted rekey completed")
+    );
+}
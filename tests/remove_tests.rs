@@ -0,0 +1,157 @@
+// tests/remove_tests.rs
+//! remove_file: soft delete (trash), hard delete, crypto-shred
+
+mod common;
+use common::open_test_vault;
+
+use encrypted_file_vault::enums::RemoveMode;
+use encrypted_file_vault::{export_to_json, CoreError, Vault};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+/// Add one file; returns (file_id, ciphertext path)
+fn add(vault: &mut Vault, dir: &Path, name: &str) -> (String, PathBuf) {
+    let plain = dir.join(name);
+    fs::write(&plain, format!("contents of {name}")).unwrap();
+    let encrypted = dir.join(format!("{name}.aes"));
+    let id = vault.add_file(&plain, &encrypted).unwrap().file_id;
+    (id, encrypted)
+}
+
+fn exported_ids(vault: &Vault, dir: &Path) -> Vec<String> {
+    let path = dir.join("export.json");
+    export_to_json(vault.vault_conn(), vault.index_conn(), &path, true).unwrap();
+    let json: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    json["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["file_id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn key_rows(vault: &Vault, id: &str) -> (i64, i64) {
+    let count = |table: &str| -> i64 {
+        vault
+            .vault_conn()
+            .query_row(
+                &format!("SELECT COUNT(*) FROM {table} WHERE file_id = ?1"),
+                [id],
+                |r| r.get(0),
+            )
+            .unwrap()
+    };
+    (count("keys"), count("key_history"))
+}
+
+#[test]
+fn test_soft_delete_moves_to_trash_and_restores() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let (id, encrypted) = add(&mut vault, dir.path(), "draft.txt");
+    let (keep, _) = add(&mut vault, dir.path(), "keep.txt");
+
+    let report = vault.remove_file(&id, RemoveMode::SoftDelete).unwrap();
+    assert!(report.moved_to_trash);
+    assert!(report.ciphertext_removed.is_none());
+    assert_eq!(report.display_name, "draft.txt");
+
+    assert!(encrypted.exists());
+    assert_eq!(vault.list().unwrap().len(), 1);
+    assert_eq!(vault.trash().unwrap()[0].file_id, id);
+    assert_eq!(exported_ids(&vault, dir.path()), vec![keep]);
+
+    vault.restore_file(&id).unwrap();
+    assert!(vault.trash().unwrap().is_empty());
+    let out = dir.path().join("draft.out");
+    vault.extract_file(&id, &out).unwrap();
+    assert_eq!(fs::read(out).unwrap(), b"contents of draft.txt");
+}
+
+#[test]
+fn test_hard_delete_removes_ciphertext_and_index_but_keeps_keys() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let (id, encrypted) = add(&mut vault, dir.path(), "old.txt");
+
+    let report = vault.remove_file(&id, RemoveMode::HardDelete).unwrap();
+    assert_eq!(
+        report.ciphertext_removed.as_deref(),
+        Some(encrypted.as_path())
+    );
+    assert_eq!(report.index_rows_removed, 1);
+    assert_eq!(report.key_versions_purged, 0);
+
+    assert!(!encrypted.exists());
+    assert!(vault.list().unwrap().is_empty());
+    assert!(exported_ids(&vault, dir.path()).is_empty());
+    assert_eq!(key_rows(&vault, &id), (1, 1));
+    assert!(matches!(
+        vault.restore_file(&id),
+        Err(CoreError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_crypto_shred_purges_every_key_version() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let (id, encrypted) = add(&mut vault, dir.path(), "secret.txt");
+    vault.rotate(&id, None).unwrap();
+    assert_eq!(key_rows(&vault, &id), (1, 2));
+    let secure_delete = |vault: &Vault| -> i64 {
+        vault
+            .vault_conn()
+            .pragma_query_value(None, "secure_delete", |r| r.get(0))
+            .unwrap()
+    };
+    let before = secure_delete(&vault);
+
+    let report = vault.remove_file(&id, RemoveMode::CryptoShred).unwrap();
+    assert_eq!(report.key_versions_purged, 2);
+    assert!(report.vacuumed);
+    assert!(report.ciphertext_removed.is_some());
+    // Only the shred itself paid for secure_delete
+    assert_eq!(secure_delete(&vault), before);
+
+    assert!(!encrypted.exists());
+    assert_eq!(key_rows(&vault, &id), (0, 0));
+    assert!(exported_ids(&vault, dir.path()).is_empty());
+
+    let events: Vec<String> = vault
+        .vault_conn()
+        .prepare("SELECT event FROM audit_log ORDER BY id")
+        .unwrap()
+        .query_map([], |r| r.get(0))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    assert_eq!(events, vec!["crypto-shred"]);
+}
+
+#[test]
+fn test_trashed_file_can_still_be_shredded() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let (id, encrypted) = add(&mut vault, dir.path(), "binned.txt");
+
+    vault.remove_file(&id, RemoveMode::SoftDelete).unwrap();
+    let report = vault.remove_file(&id, RemoveMode::CryptoShred).unwrap();
+
+    assert_eq!(report.index_rows_removed, 1);
+    assert!(!encrypted.exists());
+    assert!(vault.trash().unwrap().is_empty());
+    assert_eq!(key_rows(&vault, &id), (0, 0));
+}
+
+#[test]
+fn test_removing_unknown_file_is_not_found() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    assert!(matches!(
+        vault.remove_file("nope", RemoveMode::HardDelete),
+        Err(CoreError::NotFound(_))
+    ));
+}
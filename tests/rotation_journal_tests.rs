@@ -0,0 +1,233 @@
+// tests/rotation_journal_tests.rs
+//! Crash-recovery tests for the two-phase, journaled key rotation
+
+mod common;
+use common::{open_test_vault, DbMode, TestDbPair};
+
+use encrypted_file_vault::add_file;
+use encrypted_file_vault::aliases::FilePassword;
+use encrypted_file_vault::atomic_write::fault;
+use encrypted_file_vault::file_ops::{decrypt_file, encrypt_file};
+use encrypted_file_vault::key_ops::generate_key;
+use encrypted_file_vault::vault_db_ops::{
+    load_current_key, recover_interrupted_rotations, rotate_key_in_vault, staged_rotation_path,
+    RecoveryOutcome,
+};
+use encrypted_file_vault::{CoreError, SecureConversionsExt};
+use rusqlite::params;
+use serial_test::serial;
+use std::fs;
+use tempfile::tempdir;
+
+fn current_blob(db: &TestDbPair, file_id: &str) -> Vec<u8> {
+    db.vault
+        .query_row(
+            "SELECT password_blob FROM keys WHERE file_id = ?1",
+            [file_id],
+            |r| r.get(0),
+        )
+        .unwrap()
+}
+
+fn journal_len(db: &TestDbPair) -> i64 {
+    db.vault
+        .query_row("SELECT COUNT(*) FROM rotation_journal", [], |r| r.get(0))
+        .unwrap()
+}
+
+#[test]
+#[serial]
+fn test_successful_rotation_leaves_empty_journal() {
+    let mut db = TestDbPair::new(DbMode::Fresh);
+    let dir = tempdir().unwrap();
+    let plain = dir.path().join("a.txt");
+    let enc = dir.path().join("a.txt.aes");
+    fs::write(&plain, b"rotate me").unwrap();
+
+    let entry = add_file(&plain, &enc, &mut db.vault, &db.index, None, None).unwrap();
+    let old = FilePassword::new(entry.known_password_hex.clone().unwrap());
+
+    let new_key =
+        rotate_key_in_vault(&enc, &mut db.vault, &db.index, &entry.file_id, &old, None).unwrap();
+
+    assert_eq!(journal_len(&db), 0);
+    assert!(!staged_rotation_path(&enc).exists());
+    assert_eq!(current_blob(&db, &entry.file_id), new_key.expose_secret());
+
+    let out = dir.path().join("a.out");
+    let new_password = FilePassword::new(new_key.expose_secret().to_hex());
+    decrypt_file(&enc, &out, &new_password).unwrap();
+    assert_eq!(fs::read(&out).unwrap(), b"rotate me");
+}
+
+#[test]
+#[serial]
+fn test_recovery_rolls_back_when_swap_never_happened() {
+    let mut db = TestDbPair::new(DbMode::Fresh);
+    let dir = tempdir().unwrap();
+    let plain = dir.path().join("b.txt");
+    let enc = dir.path().join("b.txt.aes");
+    fs::write(&plain, b"crash before rename").unwrap();
+
+    let entry = add_file(&plain, &enc, &mut db.vault, &db.index, None, None).unwrap();
+    let old_blob = current_blob(&db, &entry.file_id);
+
+    // Simulate: staged file written + journal committed, then the process died
+    let pending_key = generate_key();
+    let staged = staged_rotation_path(&enc);
+    encrypt_file(
+        &plain,
+        &staged,
+        &FilePassword::new(pending_key.expose_secret().to_hex()),
+    )
+    .unwrap();
+    db.vault
+        .execute(
+            "INSERT INTO rotation_journal
+                (file_id, encrypted_path, staged_path, from_version, new_password_blob, note)
+             VALUES (?1, ?2, ?3, 1, ?4, 'rotation')",
+            params![
+                &entry.file_id,
+                enc.to_str().unwrap(),
+                staged.to_str().unwrap(),
+                pending_key.expose_secret() as &[u8]
+            ],
+        )
+        .unwrap();
+
+    let recovered = recover_interrupted_rotations(&mut db.vault, &db.index).unwrap();
+
+    assert_eq!(recovered.len(), 1);
+    assert_eq!(recovered[0].outcome, RecoveryOutcome::RolledBack);
+    assert!(!staged.exists());
+    assert_eq!(journal_len(&db), 0);
+    assert_eq!(current_blob(&db, &entry.file_id), old_blob);
+
+    let out = dir.path().join("b.out");
+    let old_password = FilePassword::new(entry.known_password_hex.unwrap());
+    decrypt_file(&enc, &out, &old_password).unwrap();
+}
+
+#[test]
+#[serial]
+fn test_recovery_rolls_forward_when_swap_happened() {
+    let mut db = TestDbPair::new(DbMode::Fresh);
+    let dir = tempdir().unwrap();
+    let plain = dir.path().join("c.txt");
+    let enc = dir.path().join("c.txt.aes");
+    fs::write(&plain, b"crash after rename").unwrap();
+
+    let entry = add_file(&plain, &enc, &mut db.vault, &db.index, None, None).unwrap();
+
+    // Simulate: journal committed, file renamed, key_history commit never happened
+    let pending_key = generate_key();
+    let pending_password = FilePassword::new(pending_key.expose_secret().to_hex());
+    encrypt_file(&plain, &enc, &pending_password).unwrap();
+    db.vault
+        .execute(
+            "INSERT INTO rotation_journal
+                (file_id, encrypted_path, staged_path, from_version, new_password_blob, note)
+             VALUES (?1, ?2, ?3, 1, ?4, 'rotation')",
+            params![
+                &entry.file_id,
+                enc.to_str().unwrap(),
+                staged_rotation_path(&enc).to_str().unwrap(),
+                pending_key.expose_secret() as &[u8]
+            ],
+        )
+        .unwrap();
+
+    let recovered = recover_interrupted_rotations(&mut db.vault, &db.index).unwrap();
+
+    assert_eq!(recovered.len(), 1);
+    assert_eq!(recovered[0].outcome, RecoveryOutcome::RolledForward);
+    assert_eq!(journal_len(&db), 0);
+    assert_eq!(
+        current_blob(&db, &entry.file_id),
+        pending_key.expose_secret()
+    );
+
+    let versions: i64 = db
+        .vault
+        .query_row(
+            "SELECT MAX(version) FROM key_history WHERE file_id = ?1",
+            [&entry.file_id],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(versions, 2);
+
+    let out = dir.path().join("c.out");
+    decrypt_file(&enc, &out, &pending_password).unwrap();
+    assert_eq!(fs::read(&out).unwrap(), b"crash after rename");
+}
+
+#[test]
+fn test_failed_dir_sync_after_rename_keeps_new_key() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let plain = dir.path().join("d.txt");
+    fs::write(&plain, b"renamed but not synced").unwrap();
+    let entry = vault
+        .add_file(&plain, dir.path().join("d.txt.aes"))
+        .unwrap();
+    let old_blob = load_current_key(vault.vault_conn(), &entry.file_id)
+        .unwrap()
+        .expose_secret()
+        .to_vec();
+
+    fault::fail_next_rename_sync();
+    let err = vault.rotate(&entry.file_id, None).unwrap_err();
+    assert!(matches!(err, CoreError::Io(_)), "got {err:?}");
+
+    // The file is under the new key, so the new key must be current
+    assert!(!staged_rotation_path(&entry.current_path).exists());
+    let journaled: i64 = vault
+        .vault_conn()
+        .query_row("SELECT COUNT(*) FROM rotation_journal", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(journaled, 0);
+    let current = load_current_key(vault.vault_conn(), &entry.file_id).unwrap();
+    assert_ne!(current.expose_secret().to_vec(), old_blob);
+    let rotated_at: Option<String> = vault
+        .index_conn()
+        .query_row(
+            "SELECT rotated_at FROM files WHERE file_id = ?1",
+            [&entry.file_id],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert!(rotated_at.is_some());
+
+    let mut out = Vec::new();
+    vault.extract_to_writer(&entry.file_id, &mut out).unwrap();
+    assert_eq!(out, b"renamed but not synced");
+}
+
+#[test]
+fn test_recovery_removes_staged_file_without_journal_row() {
+    let dir = tempdir().unwrap();
+    let plain = dir.path().join("e.txt");
+    fs::write(&plain, b"crash before the journal commit").unwrap();
+    let (file_id, staged) = {
+        let mut vault = open_test_vault(dir.path());
+        let entry = vault
+            .add_file(&plain, dir.path().join("e.txt.aes"))
+            .unwrap();
+        // Simulate: phase 1 wrote the staged file, then the process died
+        let staged = staged_rotation_path(&entry.current_path);
+        fs::write(&staged, b"half-rotated").unwrap();
+        (entry.file_id, staged)
+    };
+
+    let vault = open_test_vault(dir.path());
+    let recovered = vault.recovered_rotations();
+    assert_eq!(recovered.len(), 1);
+    assert_eq!(recovered[0].file_id, file_id);
+    assert_eq!(recovered[0].outcome, RecoveryOutcome::StagedFileRemoved);
+    assert!(!staged.exists());
+
+    let mut out = Vec::new();
+    vault.extract_to_writer(&file_id, &mut out).unwrap();
+    assert_eq!(out, b"crash before the journal commit");
+}
@@ -0,0 +1,177 @@
+// tests/tag_tests.rs
+//! Tag management, boolean tag queries, migration from `files.tags`, export
+
+mod common;
+use common::{open_test_vault, test_index_key};
+
+use encrypted_file_vault::db::index_db_conn::open_index_db_at;
+use encrypted_file_vault::db::query::FileQuery;
+use encrypted_file_vault::db::tag_ops::{self, TagCount, TagExpr};
+use encrypted_file_vault::enums::RemoveMode;
+use encrypted_file_vault::{CoreError, Vault};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn add(vault: &mut Vault, dir: &Path, name: &str) -> String {
+    let plain = dir.join(name);
+    fs::write(&plain, name.as_bytes()).unwrap();
+    vault
+        .add_file(&plain, dir.join(format!("{name}.aes")))
+        .unwrap()
+        .file_id
+}
+
+fn names(vault: &Vault, expr: TagExpr) -> Vec<String> {
+    vault
+        .query(&FileQuery::new().tags(expr))
+        .map(|r| r.unwrap().display_name)
+        .collect()
+}
+
+fn count(name: &str, files: u64) -> TagCount {
+    TagCount {
+        name: name.into(),
+        files,
+    }
+}
+
+#[test]
+fn test_add_remove_and_list_with_counts() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let a = add(&mut vault, dir.path(), "a.txt");
+    let b = add(&mut vault, dir.path(), "b.txt");
+
+    assert_eq!(vault.add_tags(&a, &["work", " tax "]).unwrap(), 2);
+    // Case-insensitive: "Work" is the same tag
+    assert_eq!(vault.add_tags(&a, &["Work"]).unwrap(), 0);
+    vault.add_tags(&b, &["work"]).unwrap();
+
+    assert_eq!(vault.file_tags(&a).unwrap(), ["tax", "work"]);
+    assert_eq!(vault.tags().unwrap(), [count("tax", 1), count("work", 2)]);
+
+    assert_eq!(vault.remove_tags(&a, &["tax", "missing"]).unwrap(), 1);
+    // Unused tags disappear
+    assert_eq!(vault.tags().unwrap(), [count("work", 2)]);
+
+    assert!(matches!(
+        vault.add_tags("no-such-file", &["x"]),
+        Err(CoreError::NotFound(_))
+    ));
+    assert!(matches!(
+        vault.add_tags(&a, &["  "]),
+        Err(CoreError::PolicyViolation(_))
+    ));
+}
+
+#[test]
+fn test_rename_and_merge() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let a = add(&mut vault, dir.path(), "a.txt");
+    let b = add(&mut vault, dir.path(), "b.txt");
+    vault.add_tags(&a, &["invoice", "bills"]).unwrap();
+    vault.add_tags(&b, &["invoices"]).unwrap();
+
+    vault.rename_tag("invoices", "invoice").unwrap();
+    assert_eq!(
+        vault.tags().unwrap(),
+        [count("bills", 1), count("invoice", 2)]
+    );
+
+    vault.rename_tag("bills", "Bills").unwrap();
+    assert_eq!(vault.file_tags(&a).unwrap(), ["Bills", "invoice"]);
+
+    assert!(matches!(
+        vault.rename_tag("nope", "x"),
+        Err(CoreError::TagNotFound(tag)) if tag == "nope"
+    ));
+}
+
+#[test]
+fn test_and_or_not_queries() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let a = add(&mut vault, dir.path(), "a.txt");
+    let b = add(&mut vault, dir.path(), "b.txt");
+    let c = add(&mut vault, dir.path(), "c.txt");
+    vault.add_tags(&a, &["work", "tax"]).unwrap();
+    vault.add_tags(&b, &["work", "draft"]).unwrap();
+    vault.add_tags(&c, &["home"]).unwrap();
+
+    let work = || TagExpr::tag("work");
+    assert_eq!(names(&vault, work()), ["a.txt", "b.txt"]);
+    assert_eq!(
+        names(&vault, work().and(TagExpr::tag("draft").not())),
+        ["a.txt"]
+    );
+    assert_eq!(
+        names(&vault, TagExpr::tag("tax").or(TagExpr::tag("home"))),
+        ["a.txt", "c.txt"]
+    );
+    assert_eq!(names(&vault, work().not()), ["c.txt"]);
+}
+
+#[test]
+fn test_trash_keeps_tags_and_hard_delete_drops_them() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let a = add(&mut vault, dir.path(), "a.txt");
+    vault.add_tags(&a, &["keep"]).unwrap();
+
+    vault.remove_file(&a, RemoveMode::SoftDelete).unwrap();
+    assert_eq!(vault.tags().unwrap(), [count("keep", 0)]);
+    vault.restore_file(&a).unwrap();
+    assert_eq!(vault.file_tags(&a).unwrap(), ["keep"]);
+
+    vault.remove_file(&a, RemoveMode::HardDelete).unwrap();
+    assert!(vault.tags().unwrap().is_empty());
+}
+
+#[test]
+fn test_legacy_tags_column_is_migrated() {
+    let dir = tempdir().unwrap();
+    let index_db = dir.path().join("index.db");
+    let key = test_index_key();
+
+    let conn = open_index_db_at(&index_db, &key).unwrap();
+    conn.execute_batch(
+        r#"
+        DROP TABLE file_tags;
+        DROP TABLE tags;
+        INSERT INTO files (file_id, content_hash, display_name, current_path,
+                           plaintext_size, created_at, tags)
+        VALUES ('f1', 'h1', 'old.txt', '/x/old.aes', 1, datetime('now'), 'work, tax,,work');
+        PRAGMA user_version = 3;
+        "#,
+    )
+    .unwrap();
+    drop(conn);
+
+    let conn = open_index_db_at(&index_db, &key).unwrap();
+    assert_eq!(tag_ops::file_tags(&conn, "f1").unwrap(), ["tax", "work"]);
+    let legacy: Option<String> = conn
+        .query_row("SELECT tags FROM files WHERE file_id = 'f1'", [], |r| {
+            r.get(0)
+        })
+        .unwrap();
+    assert_eq!(legacy, None);
+}
+
+#[test]
+fn test_export_lists_tags_as_array() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+    let a = add(&mut vault, dir.path(), "a.txt");
+    add(&mut vault, dir.path(), "b.txt");
+    vault.add_tags(&a, &["work", "tax"]).unwrap();
+
+    let out = dir.path().join("export.json");
+    vault.set_allow_insecure_export(true);
+    vault.export_json(&out).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(out).unwrap()).unwrap();
+
+    assert_eq!(json["files"][0]["tags"], serde_json::json!(["tax", "work"]));
+    assert_eq!(json["files"][1]["tags"], serde_json::json!([]));
+}
@@ -0,0 +1,84 @@
+// tests/unlock_tests.rs
+//! Passphrase unlock: Argon2id header + derived VaultKey32 / IndexKey32
+
+use encrypted_file_vault::aliases::UserPassphrase;
+use encrypted_file_vault::consts::VAULT_HEADER_FILE_NAME;
+use encrypted_file_vault::unlock::{KdfParams, VaultHeader};
+use encrypted_file_vault::Vault;
+use std::fs;
+use tempfile::tempdir;
+
+/// Cheap parameters — the defaults are deliberately slow
+const FAST: KdfParams = KdfParams {
+    m_cost_kib: 1024,
+    t_cost: 1,
+    p_cost: 1,
+};
+
+fn passphrase(s: &str) -> UserPassphrase {
+    UserPassphrase::new(s.to_string())
+}
+
+#[test]
+fn test_create_then_unlock_with_same_passphrase() {
+    let dir = tempdir().unwrap();
+    let vault_dir = dir.path().join("team-vault");
+
+    let plain = dir.path().join("notes.txt");
+    fs::write(&plain, b"shared secret notes").unwrap();
+
+    let file_id = {
+        let mut vault =
+            Vault::create_with_passphrase(&vault_dir, &passphrase("one passphrase"), FAST).unwrap();
+        vault
+            .add_file(&plain, vault_dir.join("notes.txt.aes"))
+            .unwrap()
+            .file_id
+    };
+
+    let vault = Vault::unlock(&vault_dir, &passphrase("one passphrase")).unwrap();
+    let listed = vault.list().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].file_id, file_id);
+}
+
+#[test]
+fn test_unlock_with_wrong_passphrase_fails() {
+    let dir = tempdir().unwrap();
+    drop(Vault::create_with_passphrase(dir.path(), &passphrase("right"), FAST).unwrap());
+
+    assert!(Vault::unlock(dir.path(), &passphrase("wrong")).is_err());
+}
+
+#[test]
+fn test_create_refuses_existing_vault() {
+    let dir = tempdir().unwrap();
+    drop(Vault::create_with_passphrase(dir.path(), &passphrase("first"), FAST).unwrap());
+
+    assert!(Vault::create_with_passphrase(dir.path(), &passphrase("second"), FAST).is_err());
+}
+
+#[test]
+fn test_header_holds_no_secrets_and_subkeys_differ() {
+    let dir = tempdir().unwrap();
+    drop(Vault::create_with_passphrase(dir.path(), &passphrase("hunter2"), FAST).unwrap());
+
+    let raw = fs::read_to_string(dir.path().join(VAULT_HEADER_FILE_NAME)).unwrap();
+    assert!(!raw.contains("hunter2"));
+    assert!(raw.contains("argon2id"));
+
+    let header = VaultHeader::load(dir.path()).unwrap();
+    assert_eq!(header.params, FAST);
+
+    let (vault_key, index_key) = header.derive_keys(&passphrase("hunter2")).unwrap();
+    assert_ne!(vault_key.expose_secret(), index_key.expose_secret());
+
+    // Deterministic for the same salt + passphrase
+    let (again, _) = header.derive_keys(&passphrase("hunter2")).unwrap();
+    assert_eq!(vault_key.expose_secret(), again.expose_secret());
+}
+
+#[test]
+fn test_fresh_headers_use_fresh_salts() {
+    assert_ne!(VaultHeader::new(FAST).salt, VaultHeader::new(FAST).salt);
+}
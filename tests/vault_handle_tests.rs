@@ -0,0 +1,85 @@
+// tests/vault_handle_tests.rs
+//! `Vault` handle: explicit paths + keys, no env vars
+
+mod common;
+use common::open_test_vault;
+
+use serde_json::Value;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_vault_add_list_extract_roundtrip() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+
+    let plain = dir.path().join("report.txt");
+    fs::write(&plain, b"quarterly numbers").unwrap();
+
+    let entry = vault
+        .add_file(&plain, dir.path().join("report.txt.aes"))
+        .unwrap();
+
+    let listed = vault.list().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].file_id, entry.file_id);
+    assert_eq!(listed[0].display_name, "report.txt");
+    assert!(listed[0].known_password_hex.is_none());
+
+    let out = dir.path().join("report.out");
+    let size = vault.extract_file(&entry.file_id, &out).unwrap();
+    assert_eq!(size, 17);
+    assert_eq!(fs::read(&out).unwrap(), b"quarterly numbers");
+}
+
+#[test]
+fn test_vault_rotate_then_extract_still_works() {
+    let dir = tempdir().unwrap();
+    let mut vault = open_test_vault(dir.path());
+
+    let plain = dir.path().join("keys.txt");
+    fs::write(&plain, b"rotate and read back").unwrap();
+    let entry = vault
+        .add_file(&plain, dir.path().join("keys.txt.aes"))
+        .unwrap();
+
+    vault.rotate(&entry.file_id, Some("scheduled")).unwrap();
+
+    let out = dir.path().join("keys.out");
+    vault.extract_file(&entry.file_id, &out).unwrap();
+    assert_eq!(fs::read(&out).unwrap(), b"rotate and read back");
+}
+
+#[test]
+fn test_vault_reopen_and_export_json() {
+    let dir = tempdir().unwrap();
+    let plain = dir.path().join("a.txt");
+    fs::write(&plain, b"persisted").unwrap();
+
+    let file_id = {
+        let mut vault = open_test_vault(dir.path());
+        vault
+            .add_file(&plain, dir.path().join("a.txt.aes"))
+            .unwrap()
+            .file_id
+    };
+
+    let mut vault = open_test_vault(dir.path());
+    assert!(vault.recovered_rotations().is_empty());
+
+    let export_path = dir.path().join("export.json");
+    vault.set_allow_insecure_export(true);
+    assert_eq!(vault.export_json(&export_path).unwrap(), 1);
+
+    let json: Value = serde_json::from_str(&fs::read_to_string(&export_path).unwrap()).unwrap();
+    assert_eq!(json["files"][0]["file_id"], file_id);
+}
+
+#[test]
+fn test_vault_extract_unknown_id_fails() {
+    let dir = tempdir().unwrap();
+    let vault = open_test_vault(dir.path());
+    assert!(vault
+        .extract_file("does-not-exist", dir.path().join("x"))
+        .is_err());
+}
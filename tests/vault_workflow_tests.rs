@@ -6,7 +6,9 @@ use common::TestDbPair;
 use encrypted_file_vault::add_file;
 use encrypted_file_vault::aliases::FilePassword;
 use encrypted_file_vault::consts::{DEFAULT_FILENAME_STYLE, DEFAULT_ID_LENGTH_HEX};
+use encrypted_file_vault::file_id::FileIdHasher;
 use encrypted_file_vault::key_ops::generate_key;
+use encrypted_file_vault::util::blake3_hex;
 use encrypted_file_vault::vault_db_ops::store_key_blob;
 use serial_test::serial;
 use std::fs;
@@ -94,3 +96,33 @@ fn test_add_file_uses_defaults_when_options_none() {
     assert_eq!(entry.filename_style, DEFAULT_FILENAME_STYLE);
     assert_eq!(entry.id_length_hex, DEFAULT_ID_LENGTH_HEX as u64);
 }
+
+#[test]
+#[serial]
+fn test_add_file_id_is_keyed_blake3_of_plaintext() {
+    let mut db = TestDbPair::new(DbMode::Fresh);
+
+    let dir = tempdir().unwrap();
+    let plain = dir.path().join("video.bin");
+    let data: Vec<u8> = (0..(2 * 1024 * 1024 + 3))
+        .map(|i| (i % 241) as u8)
+        .collect();
+    fs::write(&plain, &data).unwrap();
+
+    let entry = add_file(
+        &plain,
+        &dir.path().join("video.bin.aes"),
+        &mut db.vault,
+        &db.index,
+        None,
+        None,
+    )
+    .unwrap();
+
+    let hasher = FileIdHasher::load(&db.vault).unwrap();
+    assert!(hasher.is_keyed());
+    assert_eq!(entry.file_id, hasher.file_id(&blake3_hex(&data), None));
+    assert_eq!(entry.content_hash, hasher.content_hash(&blake3_hex(&data)));
+    assert_ne!(entry.file_id, blake3_hex(&data));
+    assert_eq!(entry.plaintext_size, data.len() as u64);
+}